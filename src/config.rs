@@ -1,21 +1,41 @@
+use crate::data::approval::{ApprovalAction, TimesheetApproval};
 use crate::data::client_repositories::ClientRepositories;
 use crate::data::repository::Repository;
+use crate::data::settings::Settings;
 use crate::interface::cli::RcHelpPrompt;
 use crate::interface::help_prompt::{ConfigurationDoc, RCClientRepositories, RCRepository};
+use crate::interface::timesheet_editor::{EditorOutcome, TimesheetEditor};
 use crate::utils;
+use crate::utils::activity;
+use crate::utils::activity::WatchedRepository;
+use crate::utils::calendar::{get_month_range, CalendarAction, CalendarRule};
+use crate::utils::date::date_parser::{check_for_valid_month, check_for_valid_year};
+use crate::utils::email;
+use crate::utils::email::Delivery;
 use crate::utils::exit_process;
+use crate::utils::export;
+use crate::utils::export::ExportFormat;
+use crate::utils::file::backup::{BackupStore, KEEP_BACKUPS};
 use crate::utils::file::file_reader;
+use crate::utils::hooks;
+use crate::utils::hooks::HookEvent;
+use crate::utils::import;
+use crate::utils::import::{DateFormat, ImportFormat};
+use crate::utils::journal;
+use crate::utils::journal::ActivityRecord;
 use crate::utils::link::link_builder;
-extern crate google_calendar3 as calendar3;
-use calendar3::{hyper, hyper_rustls, oauth2};
-use dirs::home_dir;
+use crate::utils::scan;
+use crate::utils::signing;
+use crate::utils::signing::{SignatureStatus, SigningRole};
+use crate::utils::time_source::credentials::CredentialStore;
+use crate::utils::time_source::gcal::GoogleCalendarSource;
+use crate::utils::time_source::{LinkStatus, TimeSource, TimeSourceRegistry};
+use chrono::Datelike;
 use std::cell::{Ref, RefMut};
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::Path;
 use std::process;
 use std::rc::Rc;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
 
 /// Creates and modifies the config file. Config does not directly hold the information
 /// contained in the config file, but provides the various operations that can be
@@ -72,6 +92,66 @@ impl Config {
         }
     }
 
+    /// Read the config file if there is one. Repositories added without prompts write the
+    /// config file themselves, so there's no onboarding when it's missing
+    fn read_config_without_onboarding(self, prompt: RcHelpPrompt) -> ConfigurationDoc {
+        let mut buffer = String::new();
+        let config_exists = file_reader::get_config_file_path()
+            .map(|path| Path::new(&path).exists())
+            .unwrap_or(false);
+
+        if config_exists {
+            file_reader::read_data_from_config_file(&mut buffer, prompt).unwrap_or_else(|err| {
+                eprintln!("Error trying to read from config file: {}", err);
+                std::process::exit(exitcode::DATAERR);
+            });
+        }
+
+        if utils::config_file_found(&mut buffer) {
+            serde_json::from_str(&buffer)
+                .expect("Initialisation of ClientRepository struct from buffer failed")
+        } else {
+            vec![]
+        }
+    }
+
+    /// Onboard every repository found below root after a single confirmation
+    fn scan_and_onboard(self, root: &str, prompt: RcHelpPrompt) {
+        let mut deserialized_config = self.read_config_without_onboarding(Rc::clone(&prompt));
+
+        let discovered =
+            scan::discover(Path::new(root), &deserialized_config).unwrap_or_else(|err| {
+                eprintln!("Error scanning {}: {}", root, err);
+                std::process::exit(exitcode::NOINPUT);
+            });
+
+        if discovered.is_empty() {
+            crate::interface::help_prompt::HelpPrompt::show_no_repositories_found(root);
+            return;
+        }
+
+        let confirmed = prompt
+            .borrow()
+            .confirm_discovered_repositories(&discovered)
+            .unwrap_or_else(|err| {
+                eprintln!("Error confirming repositories: {}", err);
+                std::process::exit(exitcode::IOERR);
+            });
+
+        if !confirmed {
+            exit_process();
+            return;
+        }
+
+        let count = scan::onboard(&mut deserialized_config, &discovered).unwrap_or_else(|err| {
+            eprintln!("Error adding repositories: {}", err);
+            std::process::exit(exitcode::CANTCREAT);
+        });
+
+        Config::write_to_config_file(None, Option::from(&mut deserialized_config));
+        crate::interface::help_prompt::HelpPrompt::show_scanned_repositories_added(count);
+    }
+
     fn fetch_interaction_data(
         mut client_repositories: RefMut<ClientRepositories>,
         repository: Ref<Repository>,
@@ -88,12 +168,10 @@ impl Config {
         deserialized_config: Option<&mut ConfigurationDoc>,
     ) {
         // get path for where to write the config file
-        let config_path =
-            utils::file::file_reader::get_filepath(utils::file::file_reader::get_home_path())
-                .unwrap_or_else(|err| {
-                    eprintln!("Error constructing filepath: {}", err);
-                    std::process::exit(exitcode::CANTCREAT);
-                });
+        let config_path = utils::file::file_reader::get_config_file_path().unwrap_or_else(|err| {
+            eprintln!("Error constructing filepath: {}", err);
+            std::process::exit(exitcode::CANTCREAT);
+        });
 
         let json =
             utils::file::file_reader::serialize_config(client_repositories, deserialized_config)
@@ -146,6 +224,7 @@ impl Config {
                     == c.to_owned().to_lowercase()
                 {
                     option = (Option::None, Option::from(&deserialized_config[i]));
+                    break;
                 } else if i == &deserialized_config.len() - 1 {
                     //TODO - if the client is passed but not found
                     //TODO - it would be good to give options - i.e list of clients, and list of repos
@@ -199,41 +278,147 @@ impl Config {
         }
     }
 
-    async fn save_token(
+    /// Credentials are kept per time source, moving over a token stored by older versions
+    fn credential_store(self) -> CredentialStore {
+        let credentials = CredentialStore::new(file_reader::get_credentials_dir());
+
+        credentials
+            .migrate_legacy_token(&file_reader::get_home_path().join(".autolog.token"), "gcal")
+            .unwrap_or_else(|err| {
+                eprintln!("Error migrating stored token: {}", err);
+                std::process::exit(exitcode::CANTCREAT);
+            });
+
+        credentials
+    }
+
+    /// Register every time source, pointing Google at the configured client secret
+    fn time_source_registry(self, secret_path: Option<&str>) -> TimeSourceRegistry {
+        let settings = Settings::read().unwrap_or_else(|err| {
+            eprintln!("Error reading settings: {}", err);
+            std::process::exit(exitcode::DATAERR);
+        });
+
+        let mut registry = TimeSourceRegistry::default();
+        registry.register(Box::new(GoogleCalendarSource::new(
+            settings.resolve_client_secret_path(secret_path),
+        )));
+
+        registry
+    }
+
+    /// Add hours and notes from linked calendars, using the rules stored against the client
+    async fn add_calendar_events(
         self,
-        token: &oauth2::AccessToken,
-        path: &PathBuf,
+        client_repositories: RCClientRepositories,
+        options: &[Option<String>],
+        dry_run: bool,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let token_json = serde_json::to_string(token)?;
-        let mut file = File::create(path).await?;
-        file.write_all(token_json.as_bytes()).await?;
+        let rules = client_repositories
+            .borrow()
+            .calendar_rules
+            .clone()
+            .unwrap_or_default();
+
+        if rules.is_empty() {
+            return Ok(());
+        }
+
+        let month = check_for_valid_month(&options[1])?;
+        let year: i32 = check_for_valid_year(&options[2])?.parse()?;
+        let (time_min, time_max) = get_month_range(year, month)?;
+
+        let registry = self.time_source_registry(None);
+        // a refreshed token isn't saved during a dry run
+        let credentials = if dry_run {
+            CredentialStore::new(file_reader::get_credentials_dir()).read_only()
+        } else {
+            self.credential_store()
+        };
+
+        for rule in &rules {
+            let source = registry.get(&rule.service)?;
+            let service = match source.calendar_service(rule, &credentials).await? {
+                Some(service) => service,
+                None => {
+                    crate::interface::help_prompt::HelpPrompt::show_calendar_not_linked(
+                        &rule.service,
+                    );
+                    continue;
+                }
+            };
+            let events = service.list_events(time_min, time_max).await?;
+
+            client_repositories
+                .borrow_mut()
+                .apply_calendar_events(rule, &events)?;
+        }
+
         Ok(())
     }
 
-    async fn load_token(
+    /// Store a calendar rule against the client passed to link
+    fn save_calendar_rule(
         self,
-        path: &PathBuf,
-    ) -> Result<oauth2::AccessToken, Box<dyn std::error::Error>> {
-        let token_json = tokio::fs::read_to_string(path).await?;
-        let token: oauth2::AccessToken = serde_json::from_str(&token_json)?;
-        Ok(token)
-    }
+        source: &dyn TimeSource,
+        options: &[Option<String>],
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    ) {
+        let mut buffer = String::new();
+        self.check_for_config_file(
+            &mut buffer,
+            Rc::clone(&repository),
+            Rc::clone(&client_repositories),
+            Rc::clone(&prompt),
+        );
 
-    async fn create_authenticator(
-        self,
-        secret: oauth2::ApplicationSecret,
-        token_path: PathBuf,
-    ) -> oauth2::authenticator::Authenticator<
-        hyper_rustls::HttpsConnector<hyper::client::HttpConnector>,
-    > {
-        oauth2::InstalledFlowAuthenticator::builder(
-            secret,
-            oauth2::InstalledFlowReturnMethod::HTTPRedirect,
-        )
-        .persist_tokens_to_disk(token_path) // Automatically save tokens to disk
-        .build()
-        .await
-        .unwrap()
+        if crate::utils::config_file_found(&mut buffer) {
+            let mut deserialized_config: ConfigurationDoc = serde_json::from_str(&buffer)
+                .expect("Initialisation of ClientRepository struct from buffer failed");
+
+            let client_name = options[1].as_ref().unwrap();
+            let rule = CalendarRule {
+                service: source.name().to_string(),
+                calendar_id: options[2].clone(),
+                attendee_domain: options[3].clone(),
+                title_regex: options[4].clone(),
+                namespace: options[5].clone(),
+                path: options[7].clone(),
+                action: match options[6].as_deref() {
+                    Some("note") => CalendarAction::Note,
+                    Some("absence") => CalendarAction::Absence,
+                    _ => CalendarAction::Hours,
+                },
+            };
+
+            if let Some(title_regex) = &rule.title_regex {
+                regex::Regex::new(title_regex).unwrap_or_else(|err| {
+                    eprintln!("Error parsing title regex: {}", err);
+                    std::process::exit(exitcode::USAGE);
+                });
+            }
+
+            source.validate_rule(&rule).unwrap_or_else(|err| {
+                eprintln!("Error adding calendar rule: {}", err);
+                std::process::exit(exitcode::USAGE);
+            });
+
+            match deserialized_config.iter_mut().find(|client| {
+                client.get_client_name().to_lowercase() == client_name.to_lowercase()
+            }) {
+                Some(client) => {
+                    client.add_calendar_rule(rule);
+                    Config::write_to_config_file(None, Option::Some(&mut deserialized_config));
+                    crate::interface::help_prompt::HelpPrompt::show_calendar_rule_added(
+                        options[0].as_ref().unwrap(),
+                        client_name,
+                    );
+                }
+                None => crate::interface::help_prompt::HelpPrompt::client_or_repository_not_found(),
+            }
+        }
     }
 }
 
@@ -256,6 +441,27 @@ impl Init for Config {
         client_repositories: RCClientRepositories,
         prompt: RcHelpPrompt,
     ) {
+        if let Some(root) = &options[1] {
+            return self.scan_and_onboard(root, prompt);
+        }
+
+        // repositories matching a client's assignment rule are added without prompts
+        let mut deserialized_config = self.read_config_without_onboarding(Rc::clone(&prompt));
+        let assigned = options[0]
+            .as_ref()
+            .and_then(|path| scan::discover_assigned(Path::new(path), &deserialized_config).ok())
+            .flatten();
+
+        if let Some(assigned) = assigned {
+            scan::onboard(&mut deserialized_config, &[assigned]).unwrap_or_else(|err| {
+                eprintln!("Error adding repository to client: {}", err);
+                std::process::exit(exitcode::CANTCREAT);
+            });
+            Config::write_to_config_file(None, Option::from(&mut deserialized_config));
+            crate::interface::help_prompt::HelpPrompt::show_write_new_repo_success();
+            return;
+        }
+
         // try to read config file. Write a new one if it doesn't exist
         let mut buffer = String::new();
         self.check_for_config_file(
@@ -335,13 +541,23 @@ impl Make for Config {
         // try to read config file. Write a new one if it doesn't exist
         let mut buffer = String::new();
         let current_repo_path = file_reader::get_canonical_path(".");
-
-        self.check_for_config_file(
-            &mut buffer,
-            Rc::clone(&repository),
-            Rc::clone(&client_repositories),
-            prompt.clone(),
-        );
+        let dry_run = options[3].as_deref() == Some("true");
+
+        // a dry run previews from a copy of the config held in memory, so an older
+        // file is neither upgraded on disk nor backed up, and onboarding never starts
+        if dry_run {
+            file_reader::read_data_from_config_file_in_memory(&mut buffer).unwrap_or_else(|err| {
+                eprintln!("Error trying to read from config file: {}", err);
+                std::process::exit(exitcode::DATAERR);
+            });
+        } else {
+            self.check_for_config_file(
+                &mut buffer,
+                Rc::clone(&repository),
+                Rc::clone(&client_repositories),
+                prompt.clone(),
+            );
+        }
 
         if crate::utils::config_file_found(&mut buffer) {
             let mut deserialized_config: ConfigurationDoc = serde_json::from_str(&buffer)
@@ -367,6 +583,97 @@ impl Make for Config {
             );
 
             if found_client_repo.is_some() {
+                self.add_calendar_events(Rc::clone(&client_repositories), &options, dry_run)
+                    .await
+                    .unwrap_or_else(|err| {
+                        eprintln!("Error reading calendar events: {}", err);
+                        std::process::exit(exitcode::UNAVAILABLE);
+                    });
+
+                // a dry run uses the stored project numbers and approver, and writes nothing
+                if dry_run {
+                    let document = link_builder::build_document_preview(
+                        Rc::clone(&client_repositories),
+                        options.clone(),
+                    )
+                    .unwrap_or_else(|err| {
+                        eprintln!("Error building timesheet preview: {}", err);
+                        std::process::exit(exitcode::DATAERR);
+                    });
+
+                    match options[4].as_deref() {
+                        Some("json") => {
+                            crate::interface::help_prompt::HelpPrompt::show_timesheet_json(
+                                &document,
+                            )
+                            .unwrap_or_else(|err| {
+                                eprintln!("Error serializing timesheet: {}", err);
+                                std::process::exit(exitcode::DATAERR);
+                            })
+                        }
+                        _ => crate::interface::help_prompt::HelpPrompt::show_timesheet_preview(
+                            &document,
+                        ),
+                    }
+
+                    return;
+                }
+
+                // a passed smtp server is remembered for the next send
+                if let Some(smtp_url) = options[8].as_deref() {
+                    email::remember_smtp_url(smtp_url).unwrap_or_else(|err| {
+                        eprintln!("Error saving settings: {}", err);
+                        std::process::exit(exitcode::CANTCREAT);
+                    });
+                }
+
+                // an export writes a local spreadsheet rather than generating a link
+                if let Some(export) = options[5].as_deref() {
+                    let format: ExportFormat = export.parse().unwrap_or_else(|err| {
+                        eprintln!("{}", err);
+                        std::process::exit(exitcode::USAGE);
+                    });
+                    let document = link_builder::build_document_preview(
+                        Rc::clone(&client_repositories),
+                        options.clone(),
+                    )
+                    .unwrap_or_else(|err| {
+                        eprintln!("Error building timesheet: {}", err);
+                        std::process::exit(exitcode::DATAERR);
+                    });
+                    let output_path = options[6]
+                        .clone()
+                        .unwrap_or_else(|| format.default_file_name(&document));
+
+                    format
+                        .export(&document, std::path::Path::new(&output_path))
+                        .unwrap_or_else(|err| {
+                            eprintln!("Error exporting timesheet to {}: {}", output_path, err);
+                            std::process::exit(exitcode::CANTCREAT);
+                        });
+
+                    crate::interface::help_prompt::HelpPrompt::show_timesheet_exported(
+                        &document.month_year,
+                        &output_path,
+                    );
+
+                    if options[7].as_deref() == Some("true") {
+                        let recipient = email::send_timesheet(
+                            &document,
+                            Delivery::Attachment(std::path::PathBuf::from(&output_path)),
+                        )
+                        .unwrap_or_else(|err| {
+                            eprintln!("Error sending timesheet: {}", err);
+                            std::process::exit(exitcode::UNAVAILABLE);
+                        });
+                        crate::interface::help_prompt::HelpPrompt::show_timesheet_sent(
+                            &document.month_year,
+                            &recipient,
+                        );
+                    }
+                    return;
+                }
+
                 prompt
                     .borrow_mut()
                     .add_project_numbers()
@@ -451,6 +758,26 @@ impl Edit for Config {
             );
 
             if found_client_repo.is_some() {
+                let month = check_for_valid_month(&options[3]).unwrap_or_else(|err| {
+                    eprintln!("Error parsing month: {}", err);
+                    process::exit(exitcode::USAGE);
+                });
+                let year: i32 = check_for_valid_year(&options[4])
+                    .map(|year| year.parse().unwrap())
+                    .unwrap_or_else(|err| {
+                        eprintln!("Error parsing year: {}", err);
+                        process::exit(exitcode::USAGE);
+                    });
+
+                // approved months are locked, and editing a rejected one revises it
+                client_repositories
+                    .borrow_mut()
+                    .prepare_month_for_edit(year, month)
+                    .unwrap_or_else(|err| {
+                        eprintln!("{}", err);
+                        process::exit(exitcode::DATAERR);
+                    });
+
                 repository
                     .borrow_mut()
                     .update_hours_on_month_day_entry(&options)
@@ -537,7 +864,10 @@ impl Remove for Config {
                 prompt
                     .borrow_mut()
                     .prompt_for_client_repo_removal(options, deserialized_config)
-                    .expect("Remove failed");
+                    .unwrap_or_else(|err| {
+                        eprintln!("Error removing from config: {}", err);
+                        std::process::exit(exitcode::USAGE);
+                    });
 
                 // if there are no clients, lets remove the file and next time will be onboarding
                 //TODO - would be nice to improve this
@@ -613,7 +943,10 @@ impl Update for Config {
                 prompt
                     .borrow_mut()
                     .prompt_for_update(options)
-                    .expect("Update failed");
+                    .unwrap_or_else(|err| {
+                        eprintln!("Error updating config: {}", err);
+                        std::process::exit(exitcode::USAGE);
+                    });
 
                 let client_borrow = client_repositories.borrow();
                 let mut new_client_repos = vec![];
@@ -668,70 +1001,880 @@ impl List for Config {
     }
 }
 
+pub trait Tui {
+    /// Review and edit a month of timesheets in a full-screen editor
+    fn tui(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    );
+}
+
+impl Tui for Config {
+    fn tui(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    ) {
+        if !utils::is_interactive() {
+            eprintln!(
+                "The timesheet editor needs input. Use 'autolog edit' to change hours from a script"
+            );
+            std::process::exit(exitcode::USAGE);
+        }
+
+        // try to read config file. Write a new one if it doesn't exist
+        let mut buffer = String::new();
+        let current_repo_path = file_reader::get_canonical_path(".");
+
+        self.check_for_config_file(
+            &mut buffer,
+            Rc::clone(&repository),
+            Rc::clone(&client_repositories),
+            Rc::clone(&prompt),
+        );
+
+        if crate::utils::config_file_found(&mut buffer) {
+            let mut deserialized_config: ConfigurationDoc = serde_json::from_str(&buffer)
+                .expect("Initialisation of ClientRepository struct from buffer failed");
+
+            let (found_repo, found_client_repo) = self
+                .check_for_client_or_repo_in_buffer(
+                    &mut deserialized_config,
+                    Option::from(&current_repo_path),
+                    Option::None,
+                    Option::from(&options[0]),
+                )
+                .unwrap_or_else(|err| {
+                    eprintln!("Error trying to read from config file: {}", err);
+                    std::process::exit(exitcode::DATAERR);
+                });
+
+            Self::push_found_values_into_rcs(
+                Rc::clone(&repository),
+                Rc::clone(&client_repositories),
+                found_repo,
+                found_client_repo,
+            );
+
+            if found_client_repo.is_some() {
+                let month = check_for_valid_month(&options[1]).unwrap_or_else(|err| {
+                    eprintln!("Error parsing month: {}", err);
+                    process::exit(exitcode::DATAERR);
+                });
+                let year: i32 = check_for_valid_year(&options[2])
+                    .map(|year| year.parse().unwrap())
+                    .unwrap_or_else(|err| {
+                        eprintln!("Error parsing year: {}", err);
+                        process::exit(exitcode::DATAERR);
+                    });
+
+                let mut client_repositories_borrow = client_repositories.borrow_mut();
+                let mut editor = TimesheetEditor::new(&mut client_repositories_borrow, year, month);
+                let outcome = editor.run().unwrap_or_else(|err| {
+                    eprintln!("Error running timesheet editor: {}", err);
+                    process::exit(exitcode::SOFTWARE);
+                });
+
+                if outcome == EditorOutcome::Save && editor.is_edited() {
+                    let edited_months = editor.edited_months();
+                    drop(client_repositories_borrow);
+
+                    // approved months are locked, and editing a rejected one revises it
+                    for (year, month) in edited_months {
+                        client_repositories
+                            .borrow_mut()
+                            .prepare_month_for_edit(year, month)
+                            .unwrap_or_else(|err| {
+                                eprintln!("{}", err);
+                                process::exit(exitcode::DATAERR);
+                            });
+                    }
+
+                    let client_borrow = client_repositories.borrow();
+                    let mut new_client_repos: ConfigurationDoc = vec![];
+                    Self::update_client_repositories(
+                        &mut new_client_repos,
+                        deserialized_config,
+                        client_borrow,
+                    );
+
+                    Config::write_to_config_file(None, Option::Some(&mut new_client_repos));
+                    crate::interface::help_prompt::HelpPrompt::show_edited_config_success();
+                } else {
+                    crate::interface::help_prompt::HelpPrompt::show_editor_changes_discarded();
+                }
+            } else {
+                crate::interface::help_prompt::HelpPrompt::client_or_repository_not_found();
+            }
+        }
+    }
+}
+
+pub trait Import {
+    /// Import hours from another time tracker's CSV export
+    fn import(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    );
+}
+
+impl Import for Config {
+    fn import(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    ) {
+        let format: ImportFormat = options[0]
+            .as_deref()
+            .unwrap()
+            .parse()
+            .unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(exitcode::USAGE);
+            });
+
+        let file_path = options[1].as_ref().unwrap();
+        let file = std::fs::File::open(file_path).unwrap_or_else(|err| {
+            eprintln!("Error opening {}: {}", file_path, err);
+            std::process::exit(exitcode::NOINPUT);
+        });
+
+        let date_format: Option<DateFormat> = options[4].as_deref().map(|date_format| {
+            date_format.parse().unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(exitcode::USAGE);
+            })
+        });
+
+        let entries = format.parse(file, date_format).unwrap_or_else(|err| {
+            eprintln!("Error reading {}: {}", file_path, err);
+            std::process::exit(exitcode::DATAERR);
+        });
+
+        let mut buffer = String::new();
+        self.check_for_config_file(
+            &mut buffer,
+            Rc::clone(&repository),
+            Rc::clone(&client_repositories),
+            Rc::clone(&prompt),
+        );
+
+        if crate::utils::config_file_found(&mut buffer) {
+            let mut deserialized_config: ConfigurationDoc = serde_json::from_str(&buffer)
+                .expect("Initialisation of ClientRepository struct from buffer failed");
+
+            let summary = import::merge_entries_into_config(
+                &mut deserialized_config,
+                &entries,
+                options[2].as_ref(),
+                options[3].as_ref(),
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(exitcode::DATAERR);
+            });
+
+            if summary.days > 0 {
+                Config::write_to_config_file(None, Option::Some(&mut deserialized_config));
+            }
+
+            crate::interface::help_prompt::HelpPrompt::show_import_summary(&summary);
+        }
+    }
+}
+
 pub trait Link {
-    /// List repositories under each client
-    fn link(&self, options: Vec<Option<String>>);
+    /// Link a calendar service, optionally adding a rule for a client
+    fn link(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    );
 }
 
 impl Link for Config {
     #[tokio::main]
-    async fn link(&self, options: Vec<Option<String>>) {
-        let home_dir = home_dir().expect("Could not find home directory");
-        let token_path = home_dir.join(".autolog.token");
-
-        crate::interface::help_prompt::HelpPrompt::oauth2_authenticating();
-
-        if let Some(service) = options.get(0).and_then(|s| s.as_deref()) {
-            match service {
-                "gcal" => {
-                    // Load the credentials file
-                    let secret = oauth2::read_application_secret("client_secret.json")
-                        .await
-                        .expect("client_secret.json file not found");
-
-                    if token_path.exists() {
-                        // Load the token from the file
-                        let token = self
-                            .load_token(&token_path)
-                            .await
-                            .expect("Failed to load token");
-
-                        // Check if the token is valid
-                        if token.is_expired() {
-                            crate::interface::help_prompt::HelpPrompt::show_oauth2_expired_token(
-                                service,
-                            );
-                            let auth = self.create_authenticator(secret, token_path.clone()).await;
-                            let token = auth
-                                .token(&["https://www.googleapis.com/auth/calendar"])
-                                .await
-                                .unwrap();
-                            self.save_token(&token, &token_path)
-                                .await
-                                .expect("Failed to save token");
-                            crate::interface::help_prompt::HelpPrompt::show_oauth2_success(service)
-                        } else {
-                            crate::interface::help_prompt::HelpPrompt::oauth2_link_valid(service)
-                        }
-                    } else {
-                        // Create a new token
-                        let auth = self.create_authenticator(secret, token_path.clone()).await;
-                        let token = auth
-                            .token(&["https://www.googleapis.com/auth/calendar"])
-                            .await
-                            .unwrap();
-                        self.save_token(&token, &token_path)
-                            .await
-                            .expect("Failed to save token");
-                        crate::interface::help_prompt::HelpPrompt::show_oauth2_success(service)
+    async fn link(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    ) {
+        // a passed secret is remembered, so that tokens can be refreshed from any directory
+        let secret_path = options[8].as_ref().map(|path| {
+            let path = std::fs::canonicalize(path).unwrap_or_else(|err| {
+                eprintln!("OAuth client secret not found at {}: {}", path, err);
+                std::process::exit(exitcode::NOINPUT);
+            });
+            let path = path.to_string_lossy().to_string();
+
+            Settings::read()
+                .and_then(|mut settings| settings.set_client_secret_path(path.clone()).write())
+                .unwrap_or_else(|err| {
+                    eprintln!("Error saving settings: {}", err);
+                    std::process::exit(exitcode::CANTCREAT);
+                });
+
+            path
+        });
+
+        let registry = self.time_source_registry(secret_path.as_deref());
+        let service = options[0].as_deref().unwrap();
+        let source = registry.get(service).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(exitcode::USAGE);
+        });
+        let has_client = options.get(1).is_some_and(|client| client.is_some());
+
+        // rules are stored first, as authenticating exits the process once complete
+        if has_client {
+            self.save_calendar_rule(source, &options, repository, client_repositories, prompt);
+        }
+
+        let credentials = self.credential_store();
+        let status = source.status(&credentials).await.unwrap_or_else(|err| {
+            eprintln!("Error reading {} credentials: {}", service, err);
+            std::process::exit(exitcode::DATAERR);
+        });
+
+        match status {
+            LinkStatus::NotRequired => {
+                if !has_client {
+                    crate::interface::help_prompt::HelpPrompt::show_calendar_rule_requires_client(
+                        service,
+                    );
+                }
+            }
+            LinkStatus::Linked => {
+                crate::interface::help_prompt::HelpPrompt::oauth2_link_valid(service)
+            }
+            LinkStatus::Expired | LinkStatus::NotLinked => {
+                if status == LinkStatus::Expired {
+                    crate::interface::help_prompt::HelpPrompt::show_oauth2_expired_token(service);
+                }
+
+                crate::interface::help_prompt::HelpPrompt::oauth2_authenticating();
+                source.link(&credentials).await.unwrap_or_else(|err| {
+                    eprintln!("Error linking {}: {}", service, err);
+                    std::process::exit(exitcode::CANTCREAT);
+                });
+                crate::interface::help_prompt::HelpPrompt::show_oauth2_success(service)
+            }
+        }
+    }
+}
+
+pub trait Unlink {
+    /// Remove a time source's credentials, and optionally its rules for a client
+    fn unlink(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    );
+}
+
+impl Unlink for Config {
+    #[tokio::main]
+    async fn unlink(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    ) {
+        let registry = self.time_source_registry(None);
+        let service = options[0].as_deref().unwrap();
+        let source = registry.get(service).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(exitcode::USAGE);
+        });
+
+        if let Some(client_name) = options[1].as_ref() {
+            let mut buffer = String::new();
+            self.check_for_config_file(
+                &mut buffer,
+                Rc::clone(&repository),
+                Rc::clone(&client_repositories),
+                Rc::clone(&prompt),
+            );
+
+            if crate::utils::config_file_found(&mut buffer) {
+                let mut deserialized_config: ConfigurationDoc = serde_json::from_str(&buffer)
+                    .expect("Initialisation of ClientRepository struct from buffer failed");
+
+                match deserialized_config.iter_mut().find(|client| {
+                    client.get_client_name().to_lowercase() == client_name.to_lowercase()
+                }) {
+                    Some(client) => {
+                        let removed = client.remove_calendar_rules(source.name());
+                        Config::write_to_config_file(None, Option::Some(&mut deserialized_config));
+                        crate::interface::help_prompt::HelpPrompt::show_calendar_rules_removed(
+                            removed,
+                            service,
+                            client_name,
+                        );
+                    }
+                    None => {
+                        crate::interface::help_prompt::HelpPrompt::client_or_repository_not_found()
                     }
                 }
-                _ => {
-                    println!("Unsupported service: {}", service);
+            }
+        }
+
+        let removed = source
+            .unlink(&self.credential_store())
+            .await
+            .unwrap_or_else(|err| {
+                eprintln!("Error unlinking {}: {}", service, err);
+                std::process::exit(exitcode::CANTCREAT);
+            });
+
+        crate::interface::help_prompt::HelpPrompt::show_unlinked(service, removed);
+    }
+}
+
+pub trait Restore {
+    /// Replace the config file with one of its backups
+    fn restore(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt);
+}
+
+impl Restore for Config {
+    fn restore(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt) {
+        let backups = BackupStore::new(file_reader::get_backups_dir(), KEEP_BACKUPS);
+
+        let backup_path = match options[0].as_deref() {
+            Some(name) => backups.find(name).unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(exitcode::NOINPUT);
+            }),
+            None => {
+                let found = backups.list().unwrap_or_else(|err| {
+                    eprintln!("Error reading backups: {}", err);
+                    std::process::exit(exitcode::IOERR);
+                });
+                if found.is_empty() {
+                    crate::interface::help_prompt::HelpPrompt::show_no_backups();
+                    return;
+                }
+                prompt.borrow().select_backup(&found).unwrap_or_else(|err| {
+                    eprintln!("Error selecting backup: {}", err);
+                    std::process::exit(exitcode::IOERR);
+                })
+            }
+        };
+
+        file_reader::restore_config_file(&backup_path).unwrap_or_else(|err| {
+            eprintln!("Error restoring {}: {}", backup_path.display(), err);
+            std::process::exit(exitcode::DATAERR);
+        });
+
+        crate::interface::help_prompt::HelpPrompt::show_config_restored(&backup_path);
+    }
+}
+
+pub trait Status {
+    /// Show each time source, whether it's linked and the rules that use it
+    fn status(
+        &self,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    );
+}
+
+impl Status for Config {
+    #[tokio::main]
+    async fn status(
+        &self,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    ) {
+        let mut buffer = String::new();
+        self.check_for_config_file(
+            &mut buffer,
+            Rc::clone(&repository),
+            Rc::clone(&client_repositories),
+            Rc::clone(&prompt),
+        );
+
+        if crate::utils::config_file_found(&mut buffer) {
+            let deserialized_config: ConfigurationDoc = serde_json::from_str(&buffer)
+                .expect("Initialisation of ClientRepository struct from buffer failed");
+
+            let registry = self.time_source_registry(None);
+            let credentials = self.credential_store();
+            let mut rows = vec![];
+
+            for source in registry.sources() {
+                let status = source.status(&credentials).await.unwrap_or_else(|err| {
+                    eprintln!("Error reading {} credentials: {}", source.name(), err);
+                    std::process::exit(exitcode::DATAERR);
+                });
+
+                let clients: Vec<String> = deserialized_config
+                    .iter()
+                    .filter(|client| {
+                        client.calendar_rules.as_ref().is_some_and(|rules| {
+                            rules.iter().any(|rule| rule.service == source.name())
+                        })
+                    })
+                    .map(|client| client.get_client_name())
+                    .collect();
+
+                rows.push(vec![
+                    source.name().to_string(),
+                    source.description().to_string(),
+                    status.to_string(),
+                    clients.join(", "),
+                ]);
+            }
+
+            crate::interface::help_prompt::HelpPrompt::show_time_source_status(rows);
+
+            // every stored month, and the current one which may not have been submitted yet
+            let today = chrono::Local::now();
+            let mut rows = vec![];
+            for client in &deserialized_config {
+                let mut approvals = client.approvals.clone().unwrap_or_default();
+                if client.get_approval(today.year(), today.month()).is_none() {
+                    approvals.push(TimesheetApproval::new(today.year(), today.month()));
+                }
+                approvals.sort_by_key(|approval| (approval.year, approval.month));
+
+                let locale = client.locale.unwrap_or_default();
+                for approval in approvals {
+                    rows.push(vec![
+                        client.get_client_name(),
+                        locale.format_month_year(approval.month, &approval.year.to_string()),
+                        approval.status.to_string(),
+                        approval.comments.unwrap_or_default(),
+                    ]);
                 }
             }
-        } else {
-            println!("No service specified.");
+
+            crate::interface::help_prompt::HelpPrompt::show_timesheet_approvals(rows);
+        }
+    }
+}
+
+pub trait Submit {
+    /// Submit a month's timesheet for approval, or record the approver's decision on it
+    fn submit(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    );
+}
+
+impl Submit for Config {
+    fn submit(
+        &self,
+        options: Vec<Option<String>>,
+        repository: RCRepository,
+        client_repositories: RCClientRepositories,
+        prompt: RcHelpPrompt,
+    ) {
+        let mut buffer = String::new();
+        let current_repo_path = file_reader::get_canonical_path(".");
+
+        self.check_for_config_file(
+            &mut buffer,
+            Rc::clone(&repository),
+            Rc::clone(&client_repositories),
+            Rc::clone(&prompt),
+        );
+
+        if crate::utils::config_file_found(&mut buffer) {
+            let mut deserialized_config: ConfigurationDoc = serde_json::from_str(&buffer)
+                .expect("Initialisation of ClientRepository struct from buffer failed");
+
+            let month = check_for_valid_month(&options[1]).unwrap_or_else(|err| {
+                eprintln!("Error parsing month: {}", err);
+                std::process::exit(exitcode::USAGE);
+            });
+            let year: i32 = check_for_valid_year(&options[2])
+                .map(|year| year.parse().unwrap())
+                .unwrap_or_else(|err| {
+                    eprintln!("Error parsing year: {}", err);
+                    std::process::exit(exitcode::USAGE);
+                });
+            let action: ApprovalAction = options[3]
+                .as_deref()
+                .unwrap_or("submit")
+                .parse()
+                .unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(exitcode::USAGE);
+                });
+
+            let (_found_repo, found_client_repo) = self
+                .check_for_client_or_repo_in_buffer(
+                    &mut deserialized_config,
+                    Option::from(&current_repo_path),
+                    Option::None,
+                    Option::from(&options[0]),
+                )
+                .unwrap_or_else(|err| {
+                    eprintln!("Error trying to read from config file: {}", err);
+                    std::process::exit(exitcode::DATAERR);
+                });
+
+            let client_name = match found_client_repo {
+                Some(client) => client.get_client_name(),
+                None => {
+                    crate::interface::help_prompt::HelpPrompt::client_or_repository_not_found();
+                    return;
+                }
+            };
+
+            let status = deserialized_config
+                .iter_mut()
+                .find(|client| client.get_client_name() == client_name)
+                .unwrap()
+                .apply_approval_action(year, month, action, options[4].clone())
+                .unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    std::process::exit(exitcode::DATAERR);
+                });
+
+            Config::write_to_config_file(None, Option::Some(&mut deserialized_config));
+            crate::interface::help_prompt::HelpPrompt::show_approval_status_updated(
+                &client_name,
+                month,
+                year,
+                status,
+            );
+        }
+    }
+}
+
+pub trait Hooks {
+    /// Add git hooks that record activity to the journal, or record activity from one
+    fn hooks(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt);
+}
+
+impl Hooks for Config {
+    fn hooks(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt) {
+        // run by the hooks themselves, from inside the repository
+        if options[0].as_deref() == Some("record") {
+            let event = options[3].clone().unwrap();
+            let record = ActivityRecord::from_current_repository(&event).unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(exitcode::NOINPUT);
+            });
+            journal::append(&file_reader::get_journal_path(), &record).unwrap_or_else(|err| {
+                eprintln!("Error writing to the journal: {}", err);
+                std::process::exit(exitcode::CANTCREAT);
+            });
+            return;
+        }
+
+        let deserialized_config = self.read_config_without_onboarding(prompt);
+        let clients: Vec<&ClientRepositories> = deserialized_config
+            .iter()
+            .filter(|client| match options[1].as_deref() {
+                Some(client_name) => client.get_client_name().eq_ignore_ascii_case(client_name),
+                None => true,
+            })
+            .collect();
+
+        if clients.is_empty() {
+            crate::interface::help_prompt::HelpPrompt::client_or_repository_not_found();
+            return;
+        }
+
+        let mut events = vec![HookEvent::PostCommit];
+        if options[2].as_deref() == Some("true") {
+            events.push(HookEvent::PostCheckout);
+        }
+
+        // the hooks run this binary, wherever it's been installed
+        let executable = std::env::current_exe().unwrap_or_else(|err| {
+            eprintln!("Error finding the autolog executable: {}", err);
+            std::process::exit(exitcode::OSFILE);
+        });
+
+        let mut rows = vec![];
+        for client in clients {
+            for repository in client.repositories.iter().flatten() {
+                for event in &events {
+                    let status = match &repository.git_path {
+                        Some(git_path) => hooks::install(Path::new(git_path), *event, &executable)
+                            .map(|status| status.to_string())
+                            .unwrap_or_else(|err| err.to_string()),
+                        None => "no git path".to_string(),
+                    };
+                    rows.push(vec![
+                        client.get_client_name(),
+                        repository.namespace.clone().unwrap_or_default(),
+                        event.to_string(),
+                        status,
+                    ]);
+                }
+            }
+        }
+
+        crate::interface::help_prompt::HelpPrompt::show_hooks_installed(
+            rows,
+            &file_reader::get_journal_path(),
+        );
+    }
+}
+
+pub trait Daemon {
+    /// Watch the repositories for file changes, recording active sessions until stopped
+    fn daemon(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt);
+}
+
+impl Daemon for Config {
+    fn daemon(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt) {
+        let idle_minutes = match options[1].as_deref() {
+            Some(minutes) => minutes
+                .parse::<i64>()
+                .ok()
+                .filter(|minutes| *minutes > 0)
+                .unwrap_or_else(|| {
+                    eprintln!("The idle timeout should be a number of minutes, i.e. --idle=15");
+                    std::process::exit(exitcode::USAGE);
+                }),
+            None => activity::DEFAULT_IDLE_MINUTES,
+        };
+
+        let deserialized_config = self.read_config_without_onboarding(prompt);
+        // the daemon never writes the config back and runs until it's stopped,
+        // so other commands mustn't be kept waiting on its lock
+        file_reader::unlock_config_file().unwrap_or_else(|err| {
+            eprintln!("Error releasing the config file: {}", err);
+            std::process::exit(exitcode::IOERR);
+        });
+        let clients: Vec<&ClientRepositories> = deserialized_config
+            .iter()
+            .filter(|client| match options[0].as_deref() {
+                Some(client_name) => client.get_client_name().eq_ignore_ascii_case(client_name),
+                None => true,
+            })
+            .collect();
+
+        let mut rows = vec![];
+        let mut repositories = vec![];
+        for client in clients {
+            for repository in client.repositories.iter().flatten() {
+                let git_path = match &repository.git_path {
+                    Some(git_path) => git_path,
+                    None => continue,
+                };
+                // repositories added before repo_path was stored sit above their git directory
+                let repo_path = match &repository.repo_path {
+                    Some(repo_path) => Path::new(repo_path).to_path_buf(),
+                    None => match Path::new(git_path).parent() {
+                        Some(parent) => parent.to_path_buf(),
+                        None => continue,
+                    },
+                };
+                if !repo_path.is_dir() {
+                    continue;
+                }
+
+                rows.push(vec![
+                    client.get_client_name(),
+                    repository.namespace.clone().unwrap_or_default(),
+                ]);
+                repositories.push(WatchedRepository {
+                    repo_path,
+                    git_path: git_path.clone(),
+                });
+            }
+        }
+
+        if repositories.is_empty() {
+            crate::interface::help_prompt::HelpPrompt::client_or_repository_not_found();
+            return;
+        }
+
+        let log_path = file_reader::get_activity_log_path();
+        crate::interface::help_prompt::HelpPrompt::show_daemon_started(
+            rows,
+            idle_minutes,
+            &log_path,
+        );
+
+        activity::watch(
+            &repositories,
+            chrono::Duration::minutes(idle_minutes),
+            &log_path,
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("Error watching repositories: {}", err);
+            std::process::exit(exitcode::IOERR);
+        });
+
+        crate::interface::help_prompt::HelpPrompt::show_daemon_stopped();
+    }
+}
+
+pub trait Sign {
+    /// Sign an exported timesheet as the user or the approver
+    fn sign(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt);
+}
+
+impl Sign for Config {
+    fn sign(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt) {
+        let path = Path::new(options[0].as_deref().unwrap());
+        let role: SigningRole = options[1]
+            .as_deref()
+            .unwrap_or("user")
+            .parse()
+            .unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(exitcode::USAGE);
+            });
+
+        let mut document = export::json::read(path).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(exitcode::NOINPUT);
+        });
+
+        let key_path = file_reader::get_signing_key_path();
+        let (key, created) = signing::load_or_generate_key(&key_path).unwrap_or_else(|err| {
+            eprintln!("Error reading the signing key: {}", err);
+            std::process::exit(exitcode::CANTCREAT);
+        });
+        let public_key = signing::encode_public_key(&key);
+
+        let signed_by = match role {
+            SigningRole::User => document
+                .user
+                .as_ref()
+                .map(|user| format!("{} <{}>", user.name, user.email)),
+            SigningRole::Approver => document.approver.as_ref().and_then(|approver| {
+                approver
+                    .approvers_name
+                    .clone()
+                    .map(|name| match &approver.approvers_email {
+                        Some(email) => format!("{} <{}>", name, email),
+                        None => name,
+                    })
+            }),
+        };
+
+        signing::sign(&mut document, &key, role, signed_by)
+            .and_then(|_| export::json::export(&document, path))
+            .unwrap_or_else(|err| {
+                eprintln!("Error signing {}: {}", path.display(), err);
+                std::process::exit(exitcode::CANTCREAT);
+            });
+
+        // the user's key is kept with the client, so their signatures can be checked later
+        if role == SigningRole::User {
+            let mut deserialized_config = self.read_config_without_onboarding(prompt);
+            let client_id = document.client.as_ref().map(|client| client.id.clone());
+            let client = deserialized_config
+                .iter_mut()
+                .find(|client| client.client.as_ref().map(|client| client.id.clone()) == client_id);
+
+            if let Some(client) = client {
+                if client.user_signature.as_ref() != Some(&public_key) {
+                    client.user_signature = Some(public_key.clone());
+                    Config::write_to_config_file(None, Option::Some(&mut deserialized_config));
+                }
+            }
+        }
+
+        crate::interface::help_prompt::HelpPrompt::show_document_signed(
+            path,
+            role,
+            &public_key,
+            created.then_some(key_path.as_path()),
+        );
+    }
+}
+
+pub trait Verify {
+    /// Check the signatures on an exported timesheet
+    fn verify(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt);
+}
+
+impl Verify for Config {
+    fn verify(&self, options: Vec<Option<String>>, prompt: RcHelpPrompt) {
+        let path = Path::new(options[0].as_deref().unwrap());
+        let document = export::json::read(path).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(exitcode::NOINPUT);
+        });
+
+        if document.signatures.is_empty() {
+            eprintln!("{} hasn't been signed", path.display());
+            std::process::exit(exitcode::DATAERR);
+        }
+
+        // keys stored for the client have to match the keys the document was signed with
+        let deserialized_config = self.read_config_without_onboarding(prompt);
+        let client = document.client.as_ref().and_then(|document_client| {
+            deserialized_config.iter().find(|client| {
+                client
+                    .client
+                    .as_ref()
+                    .is_some_and(|client| client.id == document_client.id)
+            })
+        });
+
+        let checks = signing::verify(&document, |role| {
+            client.and_then(|client| match role {
+                SigningRole::User => client.user_signature.clone(),
+                SigningRole::Approver => client.approver_signature.clone(),
+            })
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("Error verifying {}: {}", path.display(), err);
+            std::process::exit(exitcode::DATAERR);
+        });
+
+        let rows = checks
+            .iter()
+            .map(|(signature, status)| {
+                vec![
+                    signature.role.to_string(),
+                    signature.signed_by.clone().unwrap_or_default(),
+                    signature.signed_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+                    status.to_string(),
+                ]
+            })
+            .collect();
+        crate::interface::help_prompt::HelpPrompt::show_signature_checks(rows);
+
+        let has_status = |role: Option<SigningRole>, status: SignatureStatus| {
+            checks.iter().any(|(signature, signature_status)| {
+                role.is_none_or(|role| signature.role == role) && signature_status == &status
+            })
+        };
+
+        if has_status(Some(SigningRole::Approver), SignatureStatus::Invalid) {
+            eprintln!("The timesheet has changed since it was approved");
+            std::process::exit(exitcode::DATAERR);
+        } else if has_status(None, SignatureStatus::Invalid) {
+            eprintln!("The timesheet has changed since it was signed");
+            std::process::exit(exitcode::DATAERR);
+        } else if has_status(None, SignatureStatus::UnexpectedKey) {
+            eprintln!("The timesheet was signed with a key that isn't stored for the client");
+            std::process::exit(exitcode::DATAERR);
         }
     }
 }
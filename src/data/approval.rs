@@ -0,0 +1,191 @@
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Where a month's timesheet is in the approval workflow. Months without a stored
+/// approval are drafts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    #[default]
+    Draft,
+    Submitted,
+    Approved,
+    Rejected,
+    Revised,
+}
+
+impl fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApprovalStatus::Draft => write!(f, "draft"),
+            ApprovalStatus::Submitted => write!(f, "submitted"),
+            ApprovalStatus::Approved => write!(f, "approved"),
+            ApprovalStatus::Rejected => write!(f, "rejected"),
+            ApprovalStatus::Revised => write!(f, "revised"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalAction {
+    Submit,
+    Approve,
+    Reject,
+    Revise,
+}
+
+impl fmt::Display for ApprovalAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApprovalAction::Submit => write!(f, "submit"),
+            ApprovalAction::Approve => write!(f, "approve"),
+            ApprovalAction::Reject => write!(f, "reject"),
+            ApprovalAction::Revise => write!(f, "revise"),
+        }
+    }
+}
+
+impl ApprovalAction {
+    fn past_tense(self) -> &'static str {
+        match self {
+            ApprovalAction::Submit => "submitted",
+            ApprovalAction::Approve => "approved",
+            ApprovalAction::Reject => "rejected",
+            ApprovalAction::Revise => "revised",
+        }
+    }
+}
+
+impl FromStr for ApprovalAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "submit" => Ok(ApprovalAction::Submit),
+            "approve" => Ok(ApprovalAction::Approve),
+            "reject" => Ok(ApprovalAction::Reject),
+            "revise" => Ok(ApprovalAction::Revise),
+            _ => Err(format!("'{}' isn't an approval action", s)),
+        }
+    }
+}
+
+impl ApprovalStatus {
+    /// The status after action, or an error if the action can't be taken from this status
+    pub fn apply(self, action: ApprovalAction) -> Result<ApprovalStatus, String> {
+        match (self, action) {
+            (ApprovalStatus::Draft, ApprovalAction::Submit)
+            | (ApprovalStatus::Revised, ApprovalAction::Submit) => Ok(ApprovalStatus::Submitted),
+            (ApprovalStatus::Submitted, ApprovalAction::Approve) => Ok(ApprovalStatus::Approved),
+            (ApprovalStatus::Submitted, ApprovalAction::Reject) => Ok(ApprovalStatus::Rejected),
+            (ApprovalStatus::Rejected, ApprovalAction::Revise) => Ok(ApprovalStatus::Revised),
+            _ => Err(format!(
+                "A timesheet that's {} can't be {}",
+                self,
+                action.past_tense()
+            )),
+        }
+    }
+
+    /// Approved timesheets can't be changed
+    pub fn is_locked(self) -> bool {
+        self == ApprovalStatus::Approved
+    }
+}
+
+/// The approval status of one month's timesheet for a client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimesheetApproval {
+    pub year: i32,
+    pub month: u32,
+    pub status: ApprovalStatus,
+    /// The approver's comments on a rejected timesheet, kept until it's approved
+    pub comments: Option<String>,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+impl TimesheetApproval {
+    pub fn new(year: i32, month: u32) -> Self {
+        TimesheetApproval {
+            year,
+            month,
+            status: ApprovalStatus::Draft,
+            comments: None,
+            updated_at: Local::now().fixed_offset(),
+        }
+    }
+
+    pub fn apply(
+        &mut self,
+        action: ApprovalAction,
+        comments: Option<String>,
+    ) -> Result<&mut Self, String> {
+        self.status = self.status.apply(action)?;
+        match action {
+            ApprovalAction::Reject => self.comments = comments,
+            ApprovalAction::Approve => self.comments = None,
+            _ => {}
+        }
+        self.updated_at = Local::now().fixed_offset();
+
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_moves_through_the_approval_workflow() {
+        let mut approval = TimesheetApproval::new(2021, 11);
+
+        approval.apply(ApprovalAction::Submit, None).unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Submitted);
+
+        approval
+            .apply(ApprovalAction::Reject, Some("Missing the 3rd".to_string()))
+            .unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Rejected);
+        assert_eq!(approval.comments, Some("Missing the 3rd".to_string()));
+
+        approval.apply(ApprovalAction::Revise, None).unwrap();
+        approval.apply(ApprovalAction::Submit, None).unwrap();
+        assert_eq!(approval.comments, Some("Missing the 3rd".to_string()));
+
+        approval.apply(ApprovalAction::Approve, None).unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Approved);
+        assert_eq!(approval.comments, None);
+        assert!(approval.status.is_locked());
+    }
+
+    #[test]
+    fn it_rejects_actions_that_dont_follow_the_workflow() {
+        assert_eq!(
+            ApprovalStatus::Draft
+                .apply(ApprovalAction::Approve)
+                .unwrap_err(),
+            "A timesheet that's draft can't be approved"
+        );
+        assert_eq!(
+            ApprovalStatus::Approved
+                .apply(ApprovalAction::Submit)
+                .unwrap_err(),
+            "A timesheet that's approved can't be submitted"
+        );
+        assert!(ApprovalStatus::Rejected
+            .apply(ApprovalAction::Submit)
+            .is_err());
+    }
+
+    #[test]
+    fn it_serializes_statuses_in_lowercase() {
+        assert_eq!(
+            serde_json::to_string(&ApprovalStatus::Revised).unwrap(),
+            "\"revised\""
+        );
+        assert_eq!("reject".parse(), Ok(ApprovalAction::Reject));
+    }
+}
@@ -1,5 +1,13 @@
 use crate::config::New;
+use crate::data::approval::{ApprovalAction, ApprovalStatus, TimesheetApproval};
 use crate::data::repository::{GitLogDates, Repository};
+use crate::utils::activity;
+use crate::utils::assignment::AssignmentRule;
+use crate::utils::calendar::{CalendarEvent, CalendarRule};
+use crate::utils::file::file_reader;
+use crate::utils::journal;
+use crate::utils::locale::Locale;
+use chrono::Datelike;
 use serde::{Deserialize, Serialize};
 use std::cell::Ref;
 use std::ops::Deref;
@@ -43,6 +51,11 @@ pub struct ClientRepositories {
     pub user_signature: Option<String>,
     pub approver_signature: Option<String>,
     pub approver: Option<Approver>,
+    pub calendar_rules: Option<Vec<CalendarRule>>,
+    pub locale: Option<Locale>,
+    pub assignment_rules: Option<Vec<AssignmentRule>>,
+    pub mine_reflog: Option<bool>,
+    pub approvals: Option<Vec<TimesheetApproval>>,
 }
 
 impl New for ClientRepositories {
@@ -88,6 +101,89 @@ impl ClientRepositories {
         self
     }
 
+    pub fn get_approval(&self, year: i32, month: u32) -> Option<&TimesheetApproval> {
+        self.approvals
+            .iter()
+            .flatten()
+            .find(|approval| approval.year == year && approval.month == month)
+    }
+
+    pub fn get_approval_status(&self, year: i32, month: u32) -> ApprovalStatus {
+        self.get_approval(year, month)
+            .map(|approval| approval.status)
+            .unwrap_or_default()
+    }
+
+    /// Move a month's timesheet through the approval workflow, storing it the first time
+    pub fn apply_approval_action(
+        &mut self,
+        year: i32,
+        month: u32,
+        action: ApprovalAction,
+        comments: Option<String>,
+    ) -> Result<ApprovalStatus, Box<dyn std::error::Error>> {
+        let approvals = self.approvals.get_or_insert_with(Vec::new);
+        let index = match approvals
+            .iter()
+            .position(|approval| approval.year == year && approval.month == month)
+        {
+            Some(index) => index,
+            None => {
+                approvals.push(TimesheetApproval::new(year, month));
+                approvals.len() - 1
+            }
+        };
+
+        // a failed action leaves the stored approvals as they were
+        let mut approval = approvals[index].clone();
+        approval.apply(action, comments)?;
+        approvals[index] = approval;
+
+        Ok(approvals[index].status)
+    }
+
+    /// Months that are approved or waiting on the approver. Syncing leaves them as they are
+    pub fn locked_months(&self) -> Vec<(i32, u32)> {
+        self.approvals
+            .iter()
+            .flatten()
+            .filter(|approval| {
+                approval.status.is_locked() || approval.status == ApprovalStatus::Submitted
+            })
+            .map(|approval| (approval.year, approval.month))
+            .collect()
+    }
+
+    /// Check that a month can be edited. Editing a rejected timesheet revises it
+    pub fn prepare_month_for_edit(
+        &mut self,
+        year: i32,
+        month: u32,
+    ) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        match self.get_approval_status(year, month) {
+            status if status.is_locked() => {
+                return Err(format!(
+                    "The timesheet for {}/{} has been approved and can't be edited",
+                    month, year
+                )
+                .into())
+            }
+            ApprovalStatus::Submitted => {
+                return Err(format!(
+                    "The timesheet for {}/{} has been submitted and can't be edited until the approver rejects it",
+                    month, year
+                )
+                .into())
+            }
+            ApprovalStatus::Rejected => {
+                self.apply_approval_action(year, month, ApprovalAction::Revise, None)?;
+            }
+            _ => {}
+        }
+
+        Ok(self)
+    }
+
     pub fn get_client_name(&self) -> String {
         self.client.as_ref().unwrap().clone().client_name
     }
@@ -171,6 +267,16 @@ impl ClientRepositories {
         self
     }
 
+    /// Find a repository under the client by its namespace or alias
+    pub fn find_repository_mut(&mut self, namespace: &str) -> Option<&mut Repository> {
+        self.repositories.as_mut()?.iter_mut().find(|repository| {
+            [&repository.namespace, &repository.namespace_alias]
+                .into_iter()
+                .flatten()
+                .any(|value| value.to_lowercase() == namespace.to_lowercase())
+        })
+    }
+
     pub fn set_approvers_name(&mut self, value: String) -> &mut Self {
         if let Some(approver) = self.approver.as_mut() {
             approver.approvers_name = Option::from(value);
@@ -231,7 +337,75 @@ impl ClientRepositories {
         self
     }
 
+    pub fn add_calendar_rule(&mut self, rule: CalendarRule) -> &mut Self {
+        self.calendar_rules.get_or_insert_with(Vec::new).push(rule);
+        self
+    }
+
+    /// Add a rule for assigning new repositories to this client, unless it's already set
+    pub fn add_assignment_rule(&mut self, rule: AssignmentRule) -> &mut Self {
+        let rules = self.assignment_rules.get_or_insert_with(Vec::new);
+        if !rules.contains(&rule) {
+            rules.push(rule);
+        }
+        self
+    }
+
+    /// Remove every rule for a service, returning how many were removed
+    pub fn remove_calendar_rules(&mut self, service: &str) -> usize {
+        match self.calendar_rules.as_mut() {
+            Some(rules) => {
+                let count = rules.len();
+                rules.retain(|rule| rule.service != service);
+                count - rules.len()
+            }
+            None => 0,
+        }
+    }
+
+    /// Add events matching the rule to the rule's repository, or the first repository
+    /// when the rule doesn't name one
+    pub fn apply_calendar_events(
+        &mut self,
+        rule: &CalendarRule,
+        events: &[CalendarEvent],
+    ) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        let locked_months = self.locked_months();
+        let repositories = self
+            .repositories
+            .as_mut()
+            .ok_or("Client has no repositories")?;
+
+        let repository = match &rule.namespace {
+            Some(namespace) => repositories.iter_mut().find(|repository| {
+                repository
+                    .namespace
+                    .as_ref()
+                    .is_some_and(|value| value.to_lowercase() == namespace.to_lowercase())
+            }),
+            None => repositories.first_mut(),
+        }
+        .ok_or("Calendar rule namespace not found under client")?;
+
+        for event in events {
+            if locked_months.contains(&(event.date.year(), event.date.month())) {
+                continue;
+            }
+            if rule.matches(event)? {
+                repository.add_calendar_event(event, &rule.action);
+            }
+        }
+
+        Ok(self)
+    }
+
     pub fn exec_generate_timesheets_from_git_history(&mut self) -> &mut Self {
+        // activity recorded by the git hooks keeps days whose commits have since been rewritten
+        let journal = journal::read(&file_reader::get_journal_path()).unwrap_or_default();
+        // and sessions recorded by the daemon keep days spent on changes that were never committed
+        let sessions = activity::read(&file_reader::get_activity_log_path()).unwrap_or_default();
+        let mine_reflog = self.mine_reflog.unwrap_or(false);
+
         if let Some(repositories) = &mut self.repositories {
             for repository in repositories {
                 let command = String::from("--author");
@@ -253,6 +427,34 @@ impl ClientRepositories {
                     .unwrap_or_else(|_| "Parsing output failed".to_string());
 
                 repository.parse_git_log_dates_from_git_history(output_string);
+                repository.add_git_log_dates(journal::git_log_dates_for(
+                    &journal,
+                    repository.git_path.as_ref().unwrap(),
+                ));
+                repository.add_git_log_dates(activity::git_log_dates_for(
+                    &sessions,
+                    repository.git_path.as_ref().unwrap(),
+                ));
+
+                // as does the reflog, until git expires it
+                if mine_reflog {
+                    let output = Command::new("git")
+                        .arg("-C")
+                        .arg(repository.git_path.as_ref().unwrap())
+                        .arg("reflog")
+                        .arg("show")
+                        .arg("--all")
+                        .arg("--date=rfc")
+                        .arg("--format=%gn%x09%ge%x09%gd%x09%gs")
+                        .output()
+                        .expect("Failed to execute command");
+
+                    let output_string =
+                        crate::utils::trim_output_from_utf8(output).unwrap_or_default();
+
+                    let reflog_dates = repository.parse_git_log_dates_from_reflog(&output_string);
+                    repository.add_git_log_dates(reflog_dates);
+                }
             }
         }
 
@@ -260,6 +462,8 @@ impl ClientRepositories {
     }
 
     pub fn compare_logs_and_set_timesheets(&mut self) -> &mut Self {
+        let locked_months = self.locked_months();
+
         if let Some(repositories) = &mut self.repositories {
             for i in 0..repositories.len() {
                 // for each repository, build a vec of the git_log_dates from the other repositories
@@ -270,7 +474,7 @@ impl ClientRepositories {
                     .map(|(_, repo)| repo.git_log_dates.as_ref().unwrap().clone())
                     .collect();
 
-                let timesheet = match &repositories[i].git_log_dates {
+                let mut timesheet = match &repositories[i].git_log_dates {
                     Some(git_log_dates) => {
                         crate::utils::date::date_parser::get_timesheet_map_from_date_hashmap(
                             git_log_dates.clone(),
@@ -284,6 +488,35 @@ impl ClientRepositories {
                     }
                 };
 
+                if let Some(previous_timesheet) = &repositories[i].timesheet {
+                    crate::utils::date::date_parser::merge_user_edited_months(
+                        &mut timesheet,
+                        previous_timesheet,
+                    );
+                }
+
+                // locked months keep what was there before the sync
+                let previous_timesheet = repositories[i].timesheet.clone().unwrap_or_default();
+                for (year, month) in &locked_months {
+                    let (year, month) = (year.to_string(), month.to_string());
+                    match previous_timesheet
+                        .get(&year)
+                        .and_then(|months| months.get(&month))
+                    {
+                        Some(days) => {
+                            timesheet
+                                .entry(year)
+                                .or_default()
+                                .insert(month, days.clone());
+                        }
+                        None => {
+                            if let Some(months) = timesheet.get_mut(&year) {
+                                months.remove(&month);
+                            }
+                        }
+                    }
+                }
+
                 repositories[i].set_timesheet(timesheet);
             }
         }
@@ -294,12 +527,17 @@ impl ClientRepositories {
 
 #[cfg(test)]
 mod tests {
+    use crate::data::approval::{ApprovalAction, ApprovalStatus};
     use crate::data::client_repositories::{Client, ClientRepositories, User};
     use crate::data::repository::Repository;
     use crate::helpers::mocks;
+    use crate::utils::assignment::AssignmentRule;
+    use crate::utils::calendar::{CalendarAction, CalendarEvent, CalendarRule};
+    use chrono::NaiveDate;
     use nanoid::nanoid;
     use serde_json::json;
     use std::cell::RefCell;
+    use std::collections::HashMap;
 
     #[test]
     fn it_set_requires_approval() {
@@ -608,4 +846,259 @@ mod tests {
 
         assert_eq!(ts, vec![8.0, 4.0, 2.6666666666666665]);
     }
+
+    #[test]
+    fn it_applies_matching_calendar_events_to_the_rule_namespace() {
+        let mut client_repositories = ClientRepositories {
+            repositories: Option::from(vec![
+                Repository {
+                    namespace: Option::Some("Project_1".to_string()),
+                    ..Default::default()
+                },
+                Repository {
+                    namespace: Option::Some("Project_2".to_string()),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let rule = CalendarRule {
+            service: "gcal".to_string(),
+            calendar_id: None,
+            attendee_domain: Option::from("alphabet.com".to_string()),
+            title_regex: None,
+            namespace: Option::from("project_2".to_string()),
+            path: None,
+            action: CalendarAction::Hours,
+        };
+
+        let event = CalendarEvent {
+            calendar_id: "primary".to_string(),
+            title: "Planning".to_string(),
+            attendees: vec!["john@alphabet.com".to_string()],
+            date: NaiveDate::from_ymd_opt(2021, 2, 1).unwrap(),
+            hours: 2.0,
+            all_day: false,
+        };
+        let unmatched_event = CalendarEvent {
+            attendees: vec![],
+            ..event.clone()
+        };
+
+        client_repositories
+            .add_calendar_rule(rule.clone())
+            .apply_calendar_events(&rule, &[event, unmatched_event])
+            .unwrap();
+
+        let repositories = client_repositories.repositories.clone().unwrap();
+        assert!(repositories[0].timesheet.is_none());
+        assert_eq!(
+            repositories[1].timesheet.as_ref().unwrap()["2021"]["2"][0]["hours"],
+            json!(2.0)
+        );
+        assert_eq!(
+            client_repositories.calendar_rules.as_ref().unwrap().len(),
+            1
+        );
+        assert_eq!(client_repositories.remove_calendar_rules("ics"), 0);
+        assert_eq!(client_repositories.remove_calendar_rules("gcal"), 1);
+    }
+
+    #[test]
+    fn it_leaves_locked_months_alone_when_syncing() {
+        let day = json!({"hours": 1.5, "weekend": false, "user_edited": false});
+        let previous_february = vec![day.as_object().unwrap().clone(); 28];
+        let mut client_repositories = ClientRepositories {
+            repositories: Option::Some(vec![Repository {
+                client_name: Option::Some("Alphabet".to_string()),
+                namespace: Option::Some("Project_1".to_string()),
+                git_log_dates: Option::Some(mocks::generate_project_git_log_dates([1, 2, 3])),
+                timesheet: Option::Some(HashMap::from([(
+                    "2021".to_string(),
+                    HashMap::from([("2".to_string(), previous_february.clone())]),
+                )])),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        for action in [ApprovalAction::Submit, ApprovalAction::Approve] {
+            client_repositories
+                .apply_approval_action(2021, 2, action, None)
+                .unwrap();
+        }
+        assert_eq!(client_repositories.locked_months(), vec![(2021, 2)]);
+
+        let rule = CalendarRule {
+            service: "ics".to_string(),
+            calendar_id: None,
+            attendee_domain: None,
+            title_regex: None,
+            namespace: None,
+            path: None,
+            action: CalendarAction::Note,
+        };
+        let event = CalendarEvent {
+            calendar_id: "work.ics".to_string(),
+            title: "Planning".to_string(),
+            attendees: vec![],
+            date: NaiveDate::from_ymd_opt(2021, 2, 1).unwrap(),
+            hours: 2.0,
+            all_day: false,
+        };
+
+        client_repositories
+            .compare_logs_and_set_timesheets()
+            .apply_calendar_events(&rule, &[event])
+            .unwrap();
+
+        let repositories = client_repositories.repositories.unwrap();
+        assert_eq!(
+            repositories[0].timesheet.as_ref().unwrap()["2021"]["2"],
+            previous_february
+        );
+    }
+
+    #[test]
+    fn it_finds_a_repository_by_namespace_or_alias() {
+        let mut client_repositories = ClientRepositories {
+            repositories: Option::from(vec![Repository {
+                namespace: Option::from("autolog".to_string()),
+                namespace_alias: Option::from("timesheets".to_string()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        assert!(client_repositories.find_repository_mut("AUTOLOG").is_some());
+        assert!(client_repositories
+            .find_repository_mut("timesheets")
+            .is_some());
+        assert!(client_repositories.find_repository_mut("potato").is_none());
+    }
+
+    #[test]
+    fn it_adds_an_assignment_rule_once() {
+        let mut client_repositories = ClientRepositories::default();
+        let rule = AssignmentRule {
+            remote_org: Option::from("acme-corp".to_string()),
+            path: None,
+        };
+
+        client_repositories
+            .add_assignment_rule(rule.clone())
+            .add_assignment_rule(rule.clone());
+
+        assert_eq!(client_repositories.assignment_rules, Some(vec![rule]));
+    }
+
+    #[test]
+    fn it_throws_an_error_when_a_calendar_rule_namespace_is_not_found() {
+        let mut client_repositories = ClientRepositories {
+            repositories: Option::from(vec![Repository {
+                namespace: Option::Some("Project_1".to_string()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        let rule = CalendarRule {
+            service: "gcal".to_string(),
+            calendar_id: None,
+            attendee_domain: None,
+            title_regex: None,
+            namespace: Option::from("Project_9".to_string()),
+            path: None,
+            action: CalendarAction::Note,
+        };
+
+        assert!(client_repositories
+            .apply_calendar_events(&rule, &[])
+            .is_err());
+    }
+
+    #[test]
+    fn it_applies_approval_actions_to_a_month() {
+        let mut client_repo = ClientRepositories {
+            ..Default::default()
+        };
+
+        assert_eq!(
+            client_repo.get_approval_status(2021, 11),
+            ApprovalStatus::Draft
+        );
+        assert_eq!(
+            client_repo
+                .apply_approval_action(2021, 11, ApprovalAction::Submit, None)
+                .unwrap(),
+            ApprovalStatus::Submitted
+        );
+        client_repo
+            .apply_approval_action(
+                2021,
+                11,
+                ApprovalAction::Reject,
+                Some("Missing the 3rd".to_string()),
+            )
+            .unwrap();
+
+        let approval = client_repo.get_approval(2021, 11).unwrap();
+        assert_eq!(approval.status, ApprovalStatus::Rejected);
+        assert_eq!(approval.comments.as_deref(), Some("Missing the 3rd"));
+        assert_eq!(
+            client_repo.get_approval_status(2021, 12),
+            ApprovalStatus::Draft
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_status_when_an_approval_action_fails() {
+        let mut client_repo = ClientRepositories {
+            ..Default::default()
+        };
+
+        client_repo
+            .apply_approval_action(2021, 11, ApprovalAction::Submit, None)
+            .unwrap();
+        assert!(client_repo
+            .apply_approval_action(2021, 11, ApprovalAction::Submit, None)
+            .is_err());
+        assert_eq!(
+            client_repo.get_approval_status(2021, 11),
+            ApprovalStatus::Submitted
+        );
+    }
+
+    #[test]
+    fn it_revises_a_rejected_month_and_locks_an_approved_one() {
+        let mut client_repo = ClientRepositories {
+            ..Default::default()
+        };
+
+        for action in [ApprovalAction::Submit, ApprovalAction::Reject] {
+            client_repo
+                .apply_approval_action(2021, 11, action, None)
+                .unwrap();
+        }
+        client_repo.prepare_month_for_edit(2021, 11).unwrap();
+        assert_eq!(
+            client_repo.get_approval_status(2021, 11),
+            ApprovalStatus::Revised
+        );
+
+        client_repo
+            .apply_approval_action(2021, 11, ApprovalAction::Submit, None)
+            .unwrap();
+        assert!(client_repo
+            .prepare_month_for_edit(2021, 11)
+            .unwrap_err()
+            .to_string()
+            .contains("has been submitted"));
+
+        client_repo
+            .apply_approval_action(2021, 11, ApprovalAction::Approve, None)
+            .unwrap();
+        assert!(client_repo.prepare_month_for_edit(2021, 11).is_err());
+        assert!(client_repo.prepare_month_for_edit(2021, 10).is_ok());
+    }
 }
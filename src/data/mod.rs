@@ -1,2 +1,4 @@
+pub mod approval;
 pub mod client_repositories;
 pub mod repository;
+pub mod settings;
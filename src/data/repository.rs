@@ -1,10 +1,11 @@
+use crate::utils::calendar::{CalendarAction, CalendarEvent};
 use crate::utils::date::date_parser::{
-    check_for_valid_day, check_for_valid_month, check_for_valid_year, create_single_day_object,
-    DayMap, TimesheetYears,
+    check_for_valid_day, check_for_valid_month, check_for_valid_year, create_month_of_days,
+    create_single_day_object, DayMap, TimesheetYears,
 };
-use chrono::{DateTime, Datelike};
+use chrono::{DateTime, Datelike, NaiveDate};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Number, Value};
 use std::collections::{HashMap, HashSet};
 use std::process;
 use std::process::{Command, Output};
@@ -161,6 +162,20 @@ impl Repository {
         self
     }
 
+    /// Add days found elsewhere, i.e. the activity journal, to the days from git log
+    pub fn add_git_log_dates(&mut self, value: GitLogDates) -> &mut Self {
+        let git_log_dates = self.git_log_dates.get_or_insert_with(GitLogDates::new);
+
+        for (year, months) in value {
+            let year = git_log_dates.entry(year).or_default();
+            for (month, days) in months {
+                year.entry(month).or_default().extend(days);
+            }
+        }
+
+        self
+    }
+
     pub fn find_namespace_from_git_path(
         &mut self,
     ) -> Result<&mut Self, Box<dyn std::error::Error>> {
@@ -351,6 +366,43 @@ impl Repository {
         self.set_git_log_dates(year_month_map);
     }
 
+    /// Find days with commit or checkout activity by the user in `git reflog` output,
+    /// formatted as name, email, selector and subject separated by tabs
+    pub fn parse_git_log_dates_from_reflog(&self, reflog: &str) -> GitLogDates {
+        let mut year_month_map: GitLogDates = HashMap::new();
+        let selector_date = regex::Regex::new(r"@\{(?P<date>[^}]+)\}").unwrap();
+
+        for line in reflog.lines() {
+            let fields: Vec<&str> = line.splitn(4, '\t').collect();
+            if fields.len() < 4 {
+                continue;
+            }
+
+            let is_user =
+                self.name.as_deref() == Some(fields[0]) || self.email.as_deref() == Some(fields[1]);
+            let is_activity = fields[3].starts_with("commit") || fields[3].starts_with("checkout:");
+            if !is_user || !is_activity {
+                continue;
+            }
+
+            let date = selector_date
+                .captures(fields[2])
+                .and_then(|cap| DateTime::parse_from_rfc2822(&cap["date"]).ok())
+                .map(|date_time| date_time.date_naive());
+
+            if let Some(date) = date {
+                year_month_map
+                    .entry(date.year())
+                    .or_default()
+                    .entry(date.month())
+                    .or_default()
+                    .insert(date.day());
+            }
+        }
+
+        year_month_map
+    }
+
     pub fn mutate_timesheet_entry(
         &mut self,
         year_string: &String,
@@ -389,6 +441,139 @@ impl Repository {
         Ok(option)
     }
 
+    /// Make sure a month exists in the timesheet so that its days can be edited
+    pub fn ensure_timesheet_month(&mut self, year: i32, month: u32) -> &mut Self {
+        self.timesheet
+            .get_or_insert_with(TimesheetYears::new)
+            .entry(year.to_string())
+            .or_default()
+            .entry(month.to_string())
+            .or_insert_with(|| create_month_of_days(year, month));
+
+        self
+    }
+
+    /// Find the commits made by the repository user on a given day
+    pub fn find_commits_for_day(
+        &self,
+        year: i32,
+        month: u32,
+        day: u32,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let git_path = self.git_path.as_ref().ok_or("Git path not set")?;
+        let name = self.name.as_ref().ok_or("User name not set")?;
+        let date = format!("{}-{:02}-{:02}", year, month, day);
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(git_path)
+            .arg("log")
+            .arg("--all")
+            .arg(format!("--author={}", name))
+            .arg(format!("--since={} 00:00:00", date))
+            .arg(format!("--until={} 23:59:59", date))
+            .arg("--pretty=format:%h %s")
+            .output()?;
+
+        let commits = crate::utils::trim_output_from_utf8(output)?
+            .lines()
+            .map(String::from)
+            .collect();
+
+        Ok(commits)
+    }
+
+    /// Add a calendar event to the day it starts on. Days the user has edited are left
+    /// alone, and all-day events never contribute hours
+    pub fn add_calendar_event(
+        &mut self,
+        event: &CalendarEvent,
+        action: &CalendarAction,
+    ) -> &mut Self {
+        let (year, month) = (event.date.year(), event.date.month());
+        self.ensure_timesheet_month(year, month);
+
+        let day = &mut self
+            .timesheet
+            .as_mut()
+            .unwrap()
+            .get_mut(&year.to_string())
+            .unwrap()
+            .get_mut(&month.to_string())
+            .unwrap()[event.date.day() as usize - 1];
+
+        let user_edited = day
+            .get("user_edited")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        match action {
+            CalendarAction::Hours => {
+                if !user_edited && !event.all_day {
+                    let hours = day.get("hours").and_then(Value::as_f64).unwrap_or(0.0);
+                    let total = (hours + event.hours).min(24.0);
+                    day.insert(
+                        "hours".to_string(),
+                        Value::Number(Number::from_f64(total).unwrap()),
+                    );
+                }
+            }
+            CalendarAction::Absence => {
+                if !user_edited {
+                    day.insert("absence".to_string(), Value::Bool(true));
+                    day.insert(
+                        "hours".to_string(),
+                        Value::Number(Number::from_f64(0.0).unwrap()),
+                    );
+                }
+            }
+            CalendarAction::Note => {
+                let note = day.get("note").and_then(Value::as_str).unwrap_or("");
+
+                if !user_edited && !note.split("; ").any(|entry| entry == event.title) {
+                    let note = match note {
+                        "" => event.title.clone(),
+                        _ => format!("{}; {}", note, event.title),
+                    };
+                    day.insert("note".to_string(), Value::String(note));
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Set a day's hours from another time tracker. The day is marked as user edited so that
+    /// syncing with git history keeps it
+    pub fn set_imported_day(
+        &mut self,
+        date: NaiveDate,
+        hours: f64,
+        note: String,
+    ) -> Result<&mut Self, Box<dyn std::error::Error>> {
+        let hours = Number::from_f64(hours)
+            .ok_or_else(|| format!("'{}' isn't a number of hours", hours))?;
+        let (year, month) = (date.year(), date.month());
+        self.ensure_timesheet_month(year, month);
+
+        let day = &mut self
+            .timesheet
+            .as_mut()
+            .unwrap()
+            .get_mut(&year.to_string())
+            .unwrap()
+            .get_mut(&month.to_string())
+            .unwrap()[date.day() as usize - 1];
+
+        day.insert("hours".to_string(), Value::Number(hours));
+        day.insert("user_edited".to_string(), Value::Bool(true));
+        if !note.is_empty() {
+            day.insert("note".to_string(), Value::String(note));
+        }
+
+        Ok(self)
+    }
+
     pub fn update_hours_on_month_day_entry(
         &mut self,
         options: &[Option<String>],
@@ -528,6 +713,99 @@ origin  git@github.com:daveymoores/autolog.git (push)\
         );
     }
 
+    #[test]
+    fn it_ensures_a_timesheet_month_exists() {
+        let mut ts = Repository {
+            ..Default::default()
+        };
+
+        ts.set_timesheet(mocks::get_mock_year_map());
+        ts.ensure_timesheet_month(2021, 12);
+        ts.ensure_timesheet_month(2021, 11);
+
+        let year = ts.timesheet.as_ref().unwrap().get("2021").unwrap();
+        assert_eq!(year.get("12").unwrap().len(), 31);
+        // existing months are left untouched
+        assert_eq!(year.get("11").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn it_adds_calendar_events_to_a_day() {
+        let mut ts = Repository {
+            ..Default::default()
+        };
+
+        let event = crate::utils::calendar::CalendarEvent {
+            calendar_id: "primary".to_string(),
+            title: "Alphabet planning".to_string(),
+            attendees: vec![],
+            date: chrono::NaiveDate::from_ymd_opt(2021, 12, 3).unwrap(),
+            hours: 1.5,
+            all_day: false,
+        };
+
+        ts.add_calendar_event(&event, &CalendarAction::Hours)
+            .add_calendar_event(&event, &CalendarAction::Hours)
+            .add_calendar_event(&event, &CalendarAction::Note)
+            .add_calendar_event(&event, &CalendarAction::Note);
+
+        let day = &ts.timesheet.as_ref().unwrap()["2021"]["12"][2];
+        assert_eq!(day["hours"], json!(3.0));
+        assert_eq!(day["note"], json!("Alphabet planning"));
+    }
+
+    #[test]
+    fn it_marks_absence_days_from_calendar_events() {
+        let mut ts = Repository {
+            ..Default::default()
+        };
+
+        let event = crate::utils::calendar::CalendarEvent {
+            calendar_id: "holidays.ics".to_string(),
+            title: "Holiday".to_string(),
+            attendees: vec![],
+            date: chrono::NaiveDate::from_ymd_opt(2021, 12, 3).unwrap(),
+            hours: 0.0,
+            all_day: true,
+        };
+
+        ts.add_calendar_event(&event, &CalendarAction::Hours)
+            .add_calendar_event(&event, &CalendarAction::Absence);
+
+        let day = &ts.timesheet.as_ref().unwrap()["2021"]["12"][2];
+        assert_eq!(day["hours"], json!(0.0));
+        assert_eq!(day["absence"], json!(true));
+    }
+
+    #[test]
+    fn it_leaves_user_edited_days_alone_when_adding_calendar_events() {
+        let mut ts = Repository {
+            ..Default::default()
+        };
+
+        ts.set_timesheet(mocks::get_mock_year_map());
+
+        let event = crate::utils::calendar::CalendarEvent {
+            calendar_id: "primary".to_string(),
+            title: "Standup".to_string(),
+            attendees: vec![],
+            date: chrono::NaiveDate::from_ymd_opt(2021, 11, 1).unwrap(),
+            hours: 1.0,
+            all_day: false,
+        };
+
+        let note = ts.timesheet.as_ref().unwrap()["2021"]["11"][0]
+            .get("note")
+            .cloned();
+        ts.add_calendar_event(&event, &CalendarAction::Hours)
+            .add_calendar_event(&event, &CalendarAction::Note);
+
+        let day = &ts.timesheet.as_ref().unwrap()["2021"]["11"][0];
+        assert_eq!(day["user_edited"], json!(true));
+        assert_eq!(day["hours"], json!(0.0));
+        assert_eq!(day.get("note").cloned(), note);
+    }
+
     #[test]
     fn it_sets_values_from_buffer() {
         let mut timesheet = Repository {
@@ -612,6 +890,44 @@ origin  git@github.com:daveymoores/autolog.git (push)\
         );
     }
 
+    #[test]
+    fn it_adds_git_log_dates() {
+        let mut repo = Repository {
+            ..Default::default()
+        };
+        repo.set_git_log_dates(mocks::generate_project_git_log_dates([1, 2, 3]));
+        repo.add_git_log_dates(mocks::generate_project_git_log_dates([3, 4, 5]));
+
+        let mut days: Vec<u32> = repo.git_log_dates.as_ref().unwrap()[&2021][&9]
+            .iter()
+            .copied()
+            .collect();
+        days.sort();
+        assert_eq!(days, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn it_parses_git_log_dates_from_reflog() {
+        let repo = Repository {
+            name: Option::from("Jim Jones".to_string()),
+            email: Option::from("jim@jones.com".to_string()),
+            ..Default::default()
+        };
+        let reflog = "Jim Jones\tjim@jones.com\tHEAD@{Tue, 2 Nov 2021 09:30:00 +0100}\tcommit: Add login\n\
+            Jim Jones\tjim@jones.com\tfeature@{Tue, 2 Nov 2021 09:30:00 +0100}\tcommit: Add login\n\
+            J Jones\tjim@jones.com\tHEAD@{Wed, 3 Nov 2021 18:00:00 +0100}\tcheckout: moving from main to feature\n\
+            Jim Jones\tjim@jones.com\tHEAD@{Thu, 4 Nov 2021 10:00:00 +0100}\treset: moving to HEAD~1\n\
+            Sam Smith\tsam@smith.com\tHEAD@{Fri, 5 Nov 2021 10:00:00 +0100}\tcommit: Fix tests\n\
+            Jim Jones\tjim@jones.com\tHEAD@{Wed, 1 Dec 2021 10:00:00 +0000}\tcommit (amend): Add login";
+
+        let git_log_dates = repo.parse_git_log_dates_from_reflog(reflog);
+
+        let mut november: Vec<u32> = git_log_dates[&2021][&11].iter().copied().collect();
+        november.sort();
+        assert_eq!(november, vec![2, 3]);
+        assert_eq!(git_log_dates[&2021][&12], HashSet::from([1]));
+    }
+
     #[test]
     fn it_parses_git_log_dates_from_git_history() {
         let mut timesheet = Repository {
@@ -0,0 +1,169 @@
+use crate::utils::file::file_reader;
+use crate::utils::locale::Locale;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CLIENT_SECRET_ENV: &str = "AUTOLOG_CLIENT_SECRET";
+const CLIENT_SECRET_FILE_NAME: &str = "client_secret.json";
+const SMTP_URL_ENV: &str = "AUTOLOG_SMTP_URL";
+
+/// User settings that apply across every client, stored in settings.json in the
+/// autolog config directory. A missing file is the same as the default settings.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub client_secret_path: Option<String>,
+    pub locale: Option<Locale>,
+    /// i.e. smtps://smtp.example.com or smtp://localhost:1025. The username and password
+    /// are kept in the credential store
+    pub smtp_url: Option<String>,
+    /// The address timesheets are sent from. Defaults to the user's email
+    pub smtp_from: Option<String>,
+}
+
+impl Settings {
+    pub fn read_from(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Settings::default());
+        }
+
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json)
+            .map_err(|err| format!("Error parsing {}: {}", path.display(), err).into())
+    }
+
+    pub fn write_to(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn read() -> Result<Self, Box<dyn Error>> {
+        Self::read_from(&file_reader::get_settings_path())
+    }
+
+    pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        self.write_to(&file_reader::get_settings_path())
+    }
+
+    pub fn set_client_secret_path(&mut self, value: String) -> &mut Self {
+        self.client_secret_path = Option::from(value);
+        self
+    }
+
+    pub fn set_smtp_url(&mut self, value: String) -> &mut Self {
+        self.smtp_url = Option::from(value);
+        self
+    }
+
+    /// Find the SMTP server to send timesheets through. AUTOLOG_SMTP_URL wins over the
+    /// settings file
+    pub fn resolve_smtp_url(&self) -> Option<String> {
+        std::env::var(SMTP_URL_ENV)
+            .ok()
+            .or_else(|| self.smtp_url.clone())
+    }
+
+    /// Find the OAuth client secret. A passed path wins, then AUTOLOG_CLIENT_SECRET, then
+    /// the settings file, falling back to client_secret.json in the config directory
+    pub fn resolve_client_secret_path(&self, passed_path: Option<&str>) -> PathBuf {
+        passed_path
+            .map(String::from)
+            .or_else(|| std::env::var(CLIENT_SECRET_ENV).ok())
+            .or_else(|| self.client_secret_path.clone())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| file_reader::get_config_dir().join(CLIENT_SECRET_FILE_NAME))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use envtestkit::lock::lock_test;
+    use envtestkit::set_env;
+    use std::ffi::OsString;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_reads_default_settings_when_there_is_no_file() {
+        let dir = tempdir().unwrap();
+        let settings = Settings::read_from(&dir.path().join("settings.json")).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn it_writes_and_reads_settings() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("autolog").join("settings.json");
+
+        Settings::default()
+            .set_client_secret_path("/secrets/google.json".to_string())
+            .write_to(&path)
+            .unwrap();
+
+        let settings = Settings::read_from(&path).unwrap();
+        assert_eq!(
+            settings.client_secret_path,
+            Some("/secrets/google.json".to_string())
+        );
+    }
+
+    #[test]
+    fn it_throws_an_error_for_invalid_settings() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "{ client_secret_path").unwrap();
+
+        assert!(Settings::read_from(&path).is_err());
+    }
+
+    #[test]
+    fn it_resolves_the_client_secret_path_in_order() {
+        let _lock = lock_test();
+        let mut settings = Settings::default();
+
+        assert_eq!(
+            settings.resolve_client_secret_path(None),
+            file_reader::get_config_dir().join("client_secret.json")
+        );
+
+        settings.set_client_secret_path("/from/settings.json".to_string());
+        assert_eq!(
+            settings.resolve_client_secret_path(None),
+            PathBuf::from("/from/settings.json")
+        );
+
+        let _env = set_env(OsString::from(CLIENT_SECRET_ENV), "/from/env.json");
+        assert_eq!(
+            settings.resolve_client_secret_path(None),
+            PathBuf::from("/from/env.json")
+        );
+        assert_eq!(
+            settings.resolve_client_secret_path(Some("/from/flag.json")),
+            PathBuf::from("/from/flag.json")
+        );
+    }
+
+    #[test]
+    fn it_resolves_the_smtp_url_from_the_environment_first() {
+        let _lock = lock_test();
+        let mut settings = Settings::default();
+        assert_eq!(settings.resolve_smtp_url(), None);
+
+        settings.set_smtp_url("smtp://localhost:1025".to_string());
+        assert_eq!(
+            settings.resolve_smtp_url(),
+            Some("smtp://localhost:1025".to_string())
+        );
+
+        let _env = set_env(OsString::from(SMTP_URL_ENV), "smtps://smtp.example.com");
+        assert_eq!(
+            settings.resolve_smtp_url(),
+            Some("smtps://smtp.example.com".to_string())
+        );
+    }
+}
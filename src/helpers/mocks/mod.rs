@@ -115,3 +115,105 @@ pub fn generate_project_git_log_dates(days: [u32; 3]) -> GitLogDates {
         ),
     ])
 }
+
+/// Serve a single HTTP response from a local port, handing back the base url and the raw request
+#[cfg(test)]
+pub fn serve_mock_http_response(body: String) -> (String, std::thread::JoinHandle<String>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}/", listener.local_addr().unwrap());
+
+    let handle = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request = String::new();
+        let mut content_length = 0;
+
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(length) = line.to_lowercase().strip_prefix("content-length:") {
+                content_length = length.trim().parse().unwrap_or(0);
+            }
+            request.push_str(&line);
+            if line == "\r\n" || line.is_empty() {
+                break;
+            }
+        }
+
+        let mut request_body = vec![0; content_length];
+        reader.read_exact(&mut request_body).unwrap();
+        request.push_str(&String::from_utf8_lossy(&request_body));
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).unwrap();
+        request
+    });
+
+    (base_url, handle)
+}
+
+/// Answers prompts in turn like a scripted prompter, recording each question asked
+#[cfg(test)]
+#[derive(Debug)]
+pub struct MockPrompter {
+    answers: crate::interface::prompter::ScriptedPrompter,
+    pub questions: RefCell<Vec<String>>,
+}
+
+#[cfg(test)]
+impl MockPrompter {
+    pub fn new(answers: &[&str]) -> Self {
+        MockPrompter {
+            answers: crate::interface::prompter::ScriptedPrompter::new(
+                &answers
+                    .iter()
+                    .map(|answer| format!("{}\n", answer))
+                    .collect::<String>(),
+            ),
+            questions: RefCell::new(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+impl crate::interface::prompter::Prompter for MockPrompter {
+    fn confirm(&self, question: &str, default: bool) -> Result<bool, Box<dyn std::error::Error>> {
+        self.questions.borrow_mut().push(question.to_string());
+        self.answers.confirm(question, default)
+    }
+
+    fn input(
+        &self,
+        question: &str,
+        initial_text: Option<&str>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        self.questions.borrow_mut().push(question.to_string());
+        self.answers.input(question, initial_text)
+    }
+
+    fn select(
+        &self,
+        question: &str,
+        items: &[String],
+        default: Option<usize>,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        self.questions.borrow_mut().push(question.to_string());
+        self.answers.select(question, items, default)
+    }
+
+    fn edit(
+        &self,
+        question: &str,
+        initial_text: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.questions.borrow_mut().push(question.to_string());
+        self.answers.edit(question, initial_text)
+    }
+}
@@ -1,15 +1,24 @@
+use crate::data::approval::ApprovalStatus;
 use crate::data::client_repositories::ClientRepositories;
 use crate::data::repository::Repository;
+use crate::interface::prompter::{RcPrompter, TerminalPrompter};
+use crate::utils::assignment::AssignmentRule;
 use crate::utils::file::file_reader;
+use crate::utils::import::ImportSummary;
+use crate::utils::link::link_builder::TimesheetDocument;
+use crate::utils::locale::{Locale, Message};
+use crate::utils::scan::DiscoveredRepository;
+use crate::utils::signing::SigningRole;
+use crate::utils::InputMode;
 use ansi_term::Style;
 use ascii_table::AsciiTable;
 /// Help prompt handles all of the interactions with the user.
 /// It writes to the std output, and returns input data or a boolean
-use dialoguer::{Confirm, Editor, Input, Select};
 use nanoid::nanoid;
 use regex::Regex;
 use std::cell::RefCell;
 use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 pub type ConfigurationDoc = Vec<ClientRepositories>;
@@ -21,6 +30,7 @@ pub type RCClientRepositories = Rc<RefCell<ClientRepositories>>;
 pub struct HelpPrompt {
     repository: RCRepository,
     client_repositories: RCClientRepositories,
+    prompter: RcPrompter,
 }
 
 pub trait Onboarding {
@@ -61,9 +71,16 @@ impl HelpPrompt {
         Self {
             repository,
             client_repositories,
+            prompter: Rc::new(TerminalPrompter::default()),
         }
     }
 
+    /// Take answers from another prompter, i.e. a file of scripted answers
+    pub fn with_prompter(mut self, prompter: RcPrompter) -> Self {
+        self.prompter = prompter;
+        self
+    }
+
     pub fn repo_already_initialised() {
         println!(
             "\u{1F916} autolog has already been initialised for this repository.\n\
@@ -102,25 +119,39 @@ impl HelpPrompt {
     }
 
     pub fn show_edited_config_success() {
-        println!("\nautolog successfully edited \u{1F389}");
+        println!(
+            "\n{} \u{1F389}",
+            Locale::current().text(Message::EditedConfig)
+        );
+        crate::utils::exit_process();
+    }
+
+    pub fn show_editor_changes_discarded() {
+        println!("\nNo changes saved.");
         crate::utils::exit_process();
     }
 
     pub fn show_updated_config_success() {
-        println!("\nautolog successfully updated \u{1F389}");
+        println!(
+            "\n{} \u{1F389}",
+            Locale::current().text(Message::UpdatedConfig)
+        );
         crate::utils::exit_process();
     }
 
     pub fn show_generating_timesheet_message(month_year_string: &str) {
         let text = Self::dim_text(&format!(
-            "\n\u{1F916} Generating timesheet for {}...",
-            month_year_string
+            "\n\u{1F916} {}",
+            Locale::current().format(Message::GeneratingTimesheet, &[month_year_string])
         ));
         println!("{}", text);
     }
 
     pub fn client_or_repository_not_found() {
-        println!("\n\u{1F916} Client or repository not found.");
+        println!(
+            "\n\u{1F916} {}",
+            Locale::current().text(Message::ClientOrRepositoryNotFound)
+        );
         crate::utils::exit_process();
     }
 
@@ -131,6 +162,147 @@ impl HelpPrompt {
         ));
     }
 
+    pub fn show_timesheet_preview(document: &TimesheetDocument) {
+        Self::print_question(&format!("Timesheet preview for {}", document.month_year));
+        let ascii_table = AsciiTable::default();
+        let mut data = vec![];
+
+        if let Some(client) = document.client.as_ref() {
+            data.push(vec![Self::dim_text("Client:"), client.client_name.clone()]);
+            data.push(vec![
+                Self::dim_text("Client contact person:"),
+                client.client_contact_person.clone(),
+            ]);
+        }
+        if let Some(user) = document.user.as_ref() {
+            data.push(vec![
+                Self::dim_text("User:"),
+                format!("{} <{}>", user.name, user.email),
+            ]);
+        }
+        if let Some(approver) = document.approver.as_ref() {
+            data.push(vec![
+                Self::dim_text("Approver:"),
+                format!(
+                    "{} <{}>",
+                    approver.approvers_name.clone().unwrap_or_default(),
+                    approver.approvers_email.clone().unwrap_or_default()
+                ),
+            ]);
+        }
+        ascii_table.print(data);
+
+        let mut rows = vec![vec![
+            Self::dim_text("Namespace"),
+            Self::dim_text("Project number"),
+            Self::dim_text("Days worked"),
+            Self::dim_text("Total hours"),
+        ]];
+        for timesheet in &document.timesheets {
+            let days_worked = timesheet
+                .timesheet
+                .iter()
+                .filter(|day| day.get("hours").and_then(|hours| hours.as_f64()) > Some(0.0))
+                .count();
+
+            rows.push(vec![
+                timesheet.namespace.clone(),
+                timesheet.project_number.clone().unwrap_or_default(),
+                days_worked.to_string(),
+                timesheet.total_hours.to_string(),
+            ]);
+        }
+        let total_hours: f64 = document.timesheets.iter().map(|x| x.total_hours).sum();
+        rows.push(vec![
+            Style::new().bold().paint("Total").to_string(),
+            String::new(),
+            String::new(),
+            Style::new()
+                .bold()
+                .paint(total_hours.to_string())
+                .to_string(),
+        ]);
+        println!();
+        ascii_table.print(rows);
+
+        println!(
+            "\n{}",
+            Self::dim_text("Dry run: nothing has been sent or saved.")
+        );
+    }
+
+    pub fn show_legacy_config_moved(moved: &[(PathBuf, PathBuf)]) {
+        for (from, to) in moved {
+            println!(
+                "{}",
+                Self::dim_text(&format!(
+                    "\u{1F916} Moved {} to {}",
+                    from.display(),
+                    to.display()
+                ))
+            );
+        }
+    }
+
+    pub fn show_no_backups() {
+        println!(
+            "\n\u{1F916} No backups found. One is taken each time autolog changes the config file."
+        );
+    }
+
+    pub fn select_backup(&self, backups: &[PathBuf]) -> Result<PathBuf, Box<dyn Error>> {
+        // --yes restores the newest, as it's the default selection
+        if crate::utils::get_input_mode() == InputMode::AssumeYes {
+            return Ok(backups[0].clone());
+        }
+        crate::utils::get_input_mode().require("backup", "the backup's name")?;
+
+        Self::print_question("Which backup would you like to restore? Newest is first");
+        let names: Vec<String> = backups
+            .iter()
+            .map(|backup| {
+                backup
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        let selection = self.prompter.select("Backup", &names, Some(0))?;
+
+        Ok(backups[selection].clone())
+    }
+
+    pub fn show_config_restored(backup_path: &Path) {
+        println!(
+            "\nConfig restored from {} \u{1F389}",
+            backup_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+        );
+        crate::utils::exit_process();
+    }
+
+    pub fn show_timesheet_exported(month_year: &str, output_path: &str) {
+        println!(
+            "\n{} \u{1F4C4}",
+            Locale::current().format(Message::TimesheetExported, &[month_year, output_path])
+        );
+    }
+
+    pub fn show_timesheet_sent(month_year: &str, recipient: &str) {
+        println!(
+            "\n\u{1F4E8} Timesheet for {} sent to {}",
+            month_year, recipient
+        );
+    }
+
+    pub fn show_timesheet_json(document: &TimesheetDocument) -> Result<(), Box<dyn Error>> {
+        println!("{}", serde_json::to_string_pretty(document)?);
+        Ok(())
+    }
+
     pub fn oauth2_authenticating() {
         println!(
             "{}",
@@ -148,9 +320,252 @@ impl HelpPrompt {
             "\nToken for {} has expired. Please re-authenticate.",
             service
         );
+    }
+
+    pub fn show_unlinked(service: &str, had_credentials: bool) {
+        match had_credentials {
+            true => println!(
+                "\n{} has been unlinked and its credentials removed.",
+                service
+            ),
+            false => println!("\n{} has no stored credentials to remove.", service),
+        }
+        crate::utils::exit_process();
+    }
+
+    pub fn show_calendar_rules_removed(count: usize, service: &str, client: &str) {
+        println!(
+            "\nRemoved {} {} calendar rule(s) from {}.",
+            count, service, client
+        );
+    }
+
+    pub fn show_time_source_status(rows: Vec<Vec<String>>) {
+        let ascii_table = AsciiTable::default();
+        let mut data = vec![vec![
+            Self::dim_text("Service"),
+            Self::dim_text("Description"),
+            Self::dim_text("Status"),
+            Self::dim_text("Clients with rules"),
+        ]];
+        data.extend(rows);
+        ascii_table.print(data);
+    }
+
+    pub fn show_timesheet_approvals(rows: Vec<Vec<String>>) {
+        Self::print_question("\u{1F916} Timesheets");
+        let ascii_table = AsciiTable::default();
+        let mut data = vec![vec![
+            Self::dim_text("Client"),
+            Self::dim_text("Month"),
+            Self::dim_text("Status"),
+            Self::dim_text("Comments"),
+        ]];
+        data.extend(rows);
+        ascii_table.print(data);
+    }
+
+    pub fn show_approval_status_updated(
+        client_name: &str,
+        month: u32,
+        year: i32,
+        status: ApprovalStatus,
+    ) {
+        println!(
+            "\n\u{1F916} The {}/{} timesheet for {} is now {}.",
+            month, year, client_name, status
+        );
+        crate::utils::exit_process();
+    }
+
+    pub fn show_document_signed(
+        path: &Path,
+        role: SigningRole,
+        public_key: &str,
+        new_key_path: Option<&Path>,
+    ) {
+        if let Some(new_key_path) = new_key_path {
+            println!(
+                "\n\u{1F511} Generated a signing key at {}. Keep it private.",
+                new_key_path.display()
+            );
+        }
+        println!(
+            "\n\u{1F916} Signed {} as the {}. The public key to verify it is:\n{}",
+            path.display(),
+            role,
+            public_key
+        );
+        crate::utils::exit_process();
+    }
+
+    pub fn show_signature_checks(rows: Vec<Vec<String>>) {
+        Self::print_question("\u{1F916} Signatures");
+        let ascii_table = AsciiTable::default();
+        let mut data = vec![vec![
+            Self::dim_text("Role"),
+            Self::dim_text("Signed by"),
+            Self::dim_text("Signed at"),
+            Self::dim_text("Status"),
+        ]];
+        data.extend(rows);
+        ascii_table.print(data);
+    }
+
+    pub fn show_hooks_installed(rows: Vec<Vec<String>>, journal_path: &Path) {
+        Self::print_question("\u{1F916} Git hooks");
+        let ascii_table = AsciiTable::default();
+        let mut data = vec![vec![
+            Self::dim_text("Client"),
+            Self::dim_text("Repository"),
+            Self::dim_text("Hook"),
+            Self::dim_text("Status"),
+        ]];
+        data.extend(rows);
+        ascii_table.print(data);
+
+        println!(
+            "{}",
+            Self::dim_text(&format!(
+                "Activity is recorded to {} and used when generating timesheets.",
+                journal_path.display()
+            ))
+        );
+    }
+
+    pub fn show_daemon_started(rows: Vec<Vec<String>>, idle_minutes: i64, log_path: &Path) {
+        Self::print_question("\u{1F916} Watching for changes");
+        let ascii_table = AsciiTable::default();
+        let mut data = vec![vec![Self::dim_text("Client"), Self::dim_text("Repository")]];
+        data.extend(rows);
+        ascii_table.print(data);
+
+        println!(
+            "{}",
+            Self::dim_text(&format!(
+                "Sessions end after {} minutes without changes, and are recorded to {}. \n\
+                Press Ctrl+C to stop.",
+                idle_minutes,
+                log_path.display()
+            ))
+        );
+    }
+
+    pub fn show_daemon_stopped() {
+        println!("\n\u{1F916} Stopped watching. Open sessions have been recorded.");
+    }
+
+    pub fn show_no_repositories_found(root: &str) {
+        println!("\n\u{1F916} No new git repositories found below {}.", root);
+        crate::utils::exit_process();
+    }
+
+    /// List the repositories found by a scan with their proposed clients, then
+    /// ask once whether to onboard all of them
+    pub fn confirm_discovered_repositories(
+        &self,
+        discovered: &[DiscoveredRepository],
+    ) -> Result<bool, Box<dyn Error>> {
+        Self::print_question("\u{1F916} These repositories were found:");
+
+        let ascii_table = AsciiTable::default();
+        let mut data = vec![vec![
+            Self::dim_text("Repository"),
+            Self::dim_text("Client"),
+            Self::dim_text("Matched by"),
+            Self::dim_text("Path"),
+        ]];
+        data.extend(discovered.iter().map(|found| {
+            vec![
+                found.get_name(),
+                found.client.clone().unwrap_or("-".to_string()),
+                found
+                    .source
+                    .map(|source| source.to_string())
+                    .unwrap_or("-".to_string()),
+                found.path.to_string_lossy().to_string(),
+            ]
+        }));
+        ascii_table.print(data);
+
+        if discovered.iter().any(|found| found.client.is_none()) {
+            println!(
+                "{}",
+                Self::dim_text(
+                    "Repositories without a client will be skipped. Add them with 'autolog init -p <path>'."
+                )
+            );
+        }
+
+        self.confirm("Would you like to add these repositories to their clients?")
+    }
+
+    pub fn show_scanned_repositories_added(count: usize) {
+        println!(
+            "\n{}",
+            Style::new()
+                .bold()
+                .paint(format!("{} repositories added \u{1F389} \n", count))
+        );
+        println!(
+            "Add client contact details with 'autolog update -c <client>' \n\
+            or try 'autolog make' to create your first timesheet."
+        );
+        crate::utils::exit_process();
+    }
+
+    pub fn show_import_summary(summary: &ImportSummary) {
+        let locale = Locale::current();
+        println!(
+            "\n{} \u{1F4E5}",
+            locale.format(
+                Message::Imported,
+                &[
+                    &locale.format_number(summary.hours),
+                    &summary.days.to_string()
+                ]
+            )
+        );
+
+        if !summary.skipped.is_empty() {
+            println!(
+                "{}",
+                Self::dim_text(&locale.format(
+                    Message::ImportSkippedRows,
+                    &[&summary.skipped.len().to_string()]
+                ))
+            );
+            for row in &summary.skipped {
+                println!("{}", Self::dim_text(&format!("  {}", row)));
+            }
+        }
+    }
+
+    pub fn show_calendar_rule_added(service: &str, client: &str) {
+        println!(
+            "\nMatching {} events will be added to {}'s timesheets \u{1F4C5}",
+            service, client
+        );
+    }
+
+    pub fn show_calendar_rule_requires_client(service: &str) {
+        println!(
+            "\nA {} calendar needs a client to add events to. Pass one with --client.",
+            service
+        );
         crate::utils::exit_process();
     }
 
+    pub fn show_calendar_not_linked(service: &str) {
+        println!(
+            "\n{}",
+            Self::dim_text(&format!(
+                "Calendar events skipped: {} isn't linked or its token has expired. Run 'autolog link -s {}'.",
+                service, service
+            ))
+        );
+    }
+
     pub fn oauth2_link_valid(service: &str) {
         println!(
             "\nToken for {} is still valid. \nRun 'autolog make' to create a timesheet.",
@@ -159,69 +574,204 @@ impl HelpPrompt {
         crate::utils::exit_process();
     }
 
-    fn take_and_validate_email<'a>(initial_text: Option<&str>) -> futures::io::Result<String> {
-        let text = initial_text.unwrap_or_default();
+    fn is_valid_email(input: &str) -> bool {
+        Regex::new(r"^([a-zA-Z0-9_\-.]+)@([a-zA-Z0-9_\-.]+)\.([a-zA-Z]{2,5})$")
+            .unwrap()
+            .is_match(input)
+    }
 
-        Input::new()
-            .with_initial_text(text)
-            .validate_with(|input: &String| -> Result<(), &str> {
-                let re = Regex::new(r"^([a-zA-Z0-9_\-.]+)@([a-zA-Z0-9_\-.]+)\.([a-zA-Z]{2,5})$")
-                    .unwrap();
-                if re.is_match(input) {
-                    Ok(())
-                } else {
-                    Err("This is not a mail address")
-                }
-            })
-            .interact_text()
+    fn take_and_validate_email(
+        &self,
+        question: &str,
+        initial_text: Option<&str>,
+    ) -> Result<String, Box<dyn Error>> {
+        loop {
+            let input = self.prompter.input(question, initial_text)?;
+            if Self::is_valid_email(&input) {
+                return Ok(input);
+            }
+            println!("{}", Self::dim_text("This is not a mail address"));
+        }
+    }
+
+    fn validate_email(input: &str) -> Result<String, Box<dyn Error>> {
+        match Self::is_valid_email(input) {
+            true => Ok(input.to_string()),
+            false => Err(format!("'{}' is not a mail address", input).into()),
+        }
+    }
+
+    /// Ask a yes/no question, taking yes in test mode and with --yes
+    fn confirm(&self, question: &str) -> Result<bool, Box<dyn Error>> {
+        Self::print_question(question);
+        if crate::utils::is_test_mode() {
+            return Ok(true);
+        }
+
+        match crate::utils::get_input_mode().confirmation()? {
+            Some(answer) => Ok(answer),
+            None => self.prompter.confirm(question, true),
+        }
+    }
+
+    /// Ask an optional yes/no question, or take the answer given when it can't be asked
+    fn ask(&self, question: &str, without_input: bool) -> Result<bool, Box<dyn Error>> {
+        if !crate::utils::is_interactive() {
+            return Ok(without_input);
+        }
+
+        Self::print_question(question);
+        self.prompter.confirm(question, true)
+    }
+
+    /// Ask for a value that can also be passed as a flag, failing when it can't be asked
+    fn input_value(&self, question: &str, flag: &str) -> Result<String, Box<dyn Error>> {
+        crate::utils::get_input_mode().require(question, flag)?;
+
+        Self::print_question(question);
+        self.prompter.input(question, None)
+    }
+
+    /// Set the values passed as update flags, returning whether any were passed
+    fn update_from_options(&self, options: &[Option<String>]) -> Result<bool, Box<dyn Error>> {
+        let mut client_repositories = self.client_repositories.borrow_mut();
+        let passed = options.iter().skip(2).any(|option| option.is_some());
+
+        if let Some(namespace) = options[1].as_deref() {
+            let repository = client_repositories
+                .find_repository_mut(namespace)
+                .ok_or(format!("Repository '{}' not found under client", namespace))?;
+
+            if let Some(alias) = &options[12] {
+                repository.set_namespace_alias(alias.clone());
+            }
+            if let Some(path) = &options[13] {
+                repository
+                    .set_repo_path(path.clone())
+                    .find_repository_details_from()?;
+            }
+            if let Some(project_number) = &options[14] {
+                repository.set_project_number(project_number.clone());
+            }
+
+            return Ok(passed);
+        }
+
+        if let Some(locale) = options[2].as_deref() {
+            client_repositories.locale = Some(locale.parse()?);
+        }
+        if let Some(mine_reflog) = options[15].as_deref() {
+            client_repositories.mine_reflog = Some(mine_reflog == "true");
+        }
+        if let Some(approver_key) = options[16].as_deref() {
+            client_repositories.approver_signature =
+                Some(crate::utils::signing::validate_public_key(approver_key)?);
+            client_repositories.set_requires_approval(true);
+        }
+
+        let rule = AssignmentRule {
+            remote_org: options[3].clone(),
+            path: options[4].clone(),
+        };
+        if !rule.is_empty() {
+            client_repositories.add_assignment_rule(rule);
+        }
+
+        if let Some(approvers_name) = &options[5] {
+            client_repositories
+                .set_approvers_name(approvers_name.clone())
+                .set_requires_approval(true);
+        }
+        if let Some(approvers_email) = &options[6] {
+            client_repositories
+                .set_approvers_email(Self::validate_email(approvers_email)?)
+                .set_requires_approval(true);
+        }
+        if let Some(client_name) = &options[7] {
+            client_repositories.update_client_name(client_name.clone());
+        }
+        if let Some(contact_person) = &options[8] {
+            client_repositories.update_client_contact_person(contact_person.clone());
+        }
+        if let Some(address) = &options[9] {
+            client_repositories.update_client_address(address.clone());
+        }
+        if let Some(user_name) = &options[10] {
+            client_repositories
+                .set_user_name(user_name.clone())
+                .set_is_user_alias(true);
+        }
+        if let Some(user_email) = &options[11] {
+            client_repositories
+                .set_user_email(Self::validate_email(user_email)?)
+                .set_is_user_alias(true);
+        }
+
+        Ok(passed)
     }
 
     pub fn prompt_for_update(
         &mut self,
         options: Vec<Option<String>>,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        // values passed as flags are set without prompting
+        if self.update_from_options(&options)? {
+            return Ok(());
+        }
+        crate::utils::get_input_mode()
+            .require("value to update", "an update flag, i.e. --contact-person,")?;
+
         let mut client_repositories = self.client_repositories.borrow_mut();
-        let mut repository = self.repository.borrow_mut();
-        let client = client_repositories.client.as_ref().unwrap();
-        let user = client_repositories.user.as_ref().unwrap();
 
-        if options[1].is_some() {
+        if let Some(namespace) = options[1].as_deref() {
             Self::print_question(&format!(
                 "Updating project '{}' for client '{}'. What would you like to update?",
-                &options[1].as_ref().unwrap(),
+                namespace,
                 &options[0].as_ref().unwrap()
             ));
 
-            let opt = vec!["Namespace", "Repository path"];
-            let selection: usize = Select::new().items(&opt).interact()?;
-            let value = opt[selection];
+            let opt = ["Namespace", "Repository path", "Project number"].map(String::from);
+            let selection = self.prompter.select("Value to update", &opt, None)?;
+            let value = opt[selection].as_str();
+            let repository = client_repositories
+                .find_repository_mut(namespace)
+                .ok_or(format!("Repository '{}' not found under client", namespace))?;
 
             // changing the repo path will automatically update the repo username/email and namespace
             // but the namespace alias can be updated separately here
             match value {
                 "Namespace" => {
-                    let input: String = Input::new()
-                        .with_initial_text(repository.namespace.as_ref().unwrap())
-                        .interact_text()?;
+                    let input = self
+                        .prompter
+                        .input("Namespace", repository.namespace.as_deref())?;
                     repository.set_namespace_alias(input);
                 }
                 "Repository path" => {
-                    let input: String = Input::new()
-                        .with_initial_text(repository.repo_path.as_ref().unwrap())
-                        .interact_text()?;
+                    let input = self
+                        .prompter
+                        .input("Repository path", repository.repo_path.as_deref())?;
                     repository
                         .set_repo_path(input)
                         .find_repository_details_from()?;
                 }
+                "Project number" => {
+                    let input = self
+                        .prompter
+                        .input("Project number", repository.project_number.as_deref())?;
+                    repository.set_project_number(input);
+                }
                 _ => {}
             };
         } else {
+            let client = client_repositories.client.clone().unwrap();
+            let user = client_repositories.user.clone().unwrap();
+
             Self::print_question(&format!(
                 "Updating client '{}'. What would you like to update?",
                 &options[0].as_ref().unwrap()
             ));
 
-            let opt = vec![
+            let opt = [
                 "Approver name",
                 "Approver email",
                 "Client company name",
@@ -229,9 +779,10 @@ impl HelpPrompt {
                 "Client address",
                 "User name",
                 "User email",
-            ];
-            let selection: usize = Select::new().items(&opt).interact()?;
-            let value = opt[selection];
+            ]
+            .map(String::from);
+            let selection = self.prompter.select("Value to update", &opt, None)?;
+            let value = opt[selection].as_str();
 
             match value {
                 "Approver name" => {
@@ -244,9 +795,9 @@ impl HelpPrompt {
                         },
                     };
 
-                    let input: String = Input::new()
-                        .with_initial_text(approvers_name)
-                        .interact_text()?;
+                    let input = self
+                        .prompter
+                        .input("Approver's name", Some(&approvers_name))?;
                     client_repositories.set_approvers_name(input);
                     client_repositories.set_requires_approval(true);
                 }
@@ -260,41 +811,46 @@ impl HelpPrompt {
                         },
                     };
 
-                    let input: String =
-                        Self::take_and_validate_email(Option::Some(&*approvers_email))?;
+                    let input = self.take_and_validate_email(
+                        "Approver's email",
+                        Option::Some(&approvers_email),
+                    )?;
                     client_repositories.set_approvers_email(input);
                     client_repositories.set_requires_approval(true);
                 }
                 "Client company name" => {
                     println!("Client company name");
-                    let input: String = Input::new()
-                        .with_initial_text(&client.client_name)
-                        .interact_text()?;
+                    let input = self
+                        .prompter
+                        .input("Client company name", Some(&client.client_name))?;
                     client_repositories.update_client_name(input);
                 }
                 "Client contact person" => {
                     println!("Client contact person");
-                    let input: String = Input::new()
-                        .with_initial_text(&client.client_contact_person)
-                        .interact_text()?;
+                    let input = self
+                        .prompter
+                        .input("Client contact person", Some(&client.client_contact_person))?;
                     client_repositories.update_client_contact_person(input);
                 }
                 "Client address" => {
                     println!("Client address");
-                    if let Some(input) = Editor::new().edit(&client.client_address)? {
+                    if let Some(input) = self
+                        .prompter
+                        .edit("Client address", &client.client_address)?
+                    {
                         client_repositories.update_client_address(input);
                     }
                 }
                 "User name" => {
                     println!("User name");
-                    let input: String =
-                        Input::new().with_initial_text(&user.name).interact_text()?;
+                    let input = self.prompter.input("User name", Some(&user.name))?;
                     client_repositories.set_user_name(input);
                     client_repositories.set_is_user_alias(true);
                 }
                 "User email" => {
                     println!("User email");
-                    let input: String = Self::take_and_validate_email(Option::Some(&user.email))?;
+                    let input =
+                        self.take_and_validate_email("User email", Option::Some(&user.email))?;
                     client_repositories.set_user_email(input);
                     client_repositories.set_is_user_alias(true);
                 }
@@ -319,14 +875,27 @@ impl HelpPrompt {
         // If the clients array is empty, lets just onboard
         if clients.is_empty() {
             self.onboarding(false)?;
+            return Ok(());
         }
 
         let no_client_value = "Create a new client".to_string();
-        clients.push(no_client_value.clone());
+        let passed_client_name = self.repository.borrow().client_name.clone();
+
+        // a passed client is used when it exists, otherwise it's created
+        let client_name = &match passed_client_name {
+            Some(passed_client_name) => clients
+                .into_iter()
+                .find(|client_name| client_name.to_lowercase() == passed_client_name.to_lowercase())
+                .unwrap_or(no_client_value.clone()),
+            None => {
+                crate::utils::get_input_mode().require("Client", "--client")?;
+                clients.push(no_client_value.clone());
 
-        Self::print_question("Would you like to add it to any of these existing clients?");
-        let selection: usize = Select::new().items(&clients).interact()?;
-        let client_name = &clients[selection];
+                Self::print_question("Would you like to add it to any of these existing clients?");
+                let selection = self.prompter.select("Client", &clients, None)?;
+                clients.swap_remove(selection)
+            }
+        };
 
         // if this is a new client, onboard as normal
         if client_name == &no_client_value {
@@ -388,13 +957,13 @@ impl HelpPrompt {
         }
 
         let current_repo_path = file_reader::get_canonical_path(".");
-        if path == current_repo_path {
-            Self::print_question("Initialise for current repository?");
+        let question = if path == current_repo_path {
+            "Initialise for current repository?".to_string()
         } else {
-            Self::print_question(&format!("With the project at this path {}?", path));
+            format!("With the project at this path {}?", path)
         };
 
-        if Confirm::new().default(true).interact()? {
+        if self.ask(&question, true)? {
             borrow.set_repo_path(path);
         } else {
             Self::print_question("Give a path to the repository you would like to use");
@@ -404,7 +973,7 @@ impl HelpPrompt {
                 .unwrap()
                 .to_string();
 
-            let input: String = Input::new().with_initial_text(path).interact_text()?;
+            let input = self.prompter.input("Repository path", Some(&path))?;
 
             borrow.set_repo_path(input);
         }
@@ -417,6 +986,12 @@ impl HelpPrompt {
         name: String,
         email: String,
     ) -> Result<&Self, Box<dyn std::error::Error>> {
+        // without input the git config details are used, and an alias can be set later
+        // with autolog update --user-name/--user-email
+        if !crate::utils::is_interactive() {
+            return Ok(self);
+        }
+
         let mut client_borrow = self.client_repositories.borrow_mut();
 
         println!("\nThe git config name or email found for this repository differs from the one being used for your user details.");
@@ -443,13 +1018,16 @@ impl HelpPrompt {
             Self::dim_text("Note: These can be updated by running autolog update.")
         );
 
-        if Confirm::new().default(true).interact()? {
+        if self
+            .prompter
+            .confirm("Set an alias for this client?", true)?
+        {
             Self::print_question("User name");
-            let name: String = Input::new().interact_text()?;
+            let name = self.prompter.input("User name", None)?;
             client_borrow.set_user_name(name);
 
             Self::print_question("User email");
-            let email: String = Input::new().interact_text()?;
+            let email = self.take_and_validate_email("User email", None)?;
             client_borrow.set_user_email(email);
 
             client_borrow.set_is_user_alias(true);
@@ -496,24 +1074,28 @@ impl HelpPrompt {
         Ok(self)
     }
 
-    pub fn add_client_details(&self) -> Result<&Self, std::io::Error> {
-        Self::print_question("Client company name");
-        let input: String = Input::new().interact_text()?;
-        self.repository.borrow_mut().set_client_name(input);
+    pub fn add_client_details(&self) -> Result<&Self, Box<dyn Error>> {
+        let mut repository = self.repository.borrow_mut();
 
-        Self::print_question("Client contact person");
-        let input: String = Input::new().interact_text()?;
-        self.repository
-            .borrow_mut()
-            .set_client_contact_person(input);
+        // details passed to init aren't asked for again
+        if repository.client_name.is_none() {
+            let input = self.input_value("Client company name", "--client")?;
+            repository.set_client_name(input);
+        }
+
+        if repository.client_contact_person.is_none() {
+            let input = self.input_value("Client contact person", "--contact-person")?;
+            repository.set_client_contact_person(input);
+        }
 
-        Self::print_question("Would you like to add a Client address?");
-        if Confirm::new().default(true).interact()? {
-            if let Some(input) = Editor::new().edit("Enter an address").unwrap() {
-                self.repository.borrow_mut().set_client_address(input);
+        if repository.client_address.is_none()
+            && self.ask("Would you like to add a Client address?", false)?
+        {
+            if let Some(input) = self.prompter.edit("Client address", "Enter an address")? {
+                repository.set_client_address(input);
             }
         }
-        self.repository.borrow_mut().set_client_id(nanoid!());
+        repository.set_client_id(nanoid!());
 
         Ok(self)
     }
@@ -526,19 +1108,23 @@ impl HelpPrompt {
             Some(requires_approval) => !requires_approval,
         };
 
-        if prompt_for_approver {
+        // without input, approvers are set with autolog update --approver-name/--approver-email
+        if prompt_for_approver && crate::utils::is_interactive() {
             Self::print_question("Do timesheets under this client require approval?");
             println!("{}", Self::dim_text(
             "(This will enable signing functionality, see https://autolog.dev/docs/signing)",
             ));
 
-            if Confirm::new().default(true).interact()? {
+            if self
+                .prompter
+                .confirm("Do timesheets under this client require approval?", true)?
+            {
                 Self::print_question("Approvers name");
-                let input: String = Input::new().interact_text()?;
+                let input = self.prompter.input("Approvers name", None)?;
                 client_repositories.set_approvers_name(input);
 
                 Self::print_question("Approvers email");
-                let input: String = Input::new().interact_text()?;
+                let input = self.take_and_validate_email("Approvers email", None)?;
                 client_repositories.set_approvers_email(input);
 
                 // TODO - check the above are set before setting this
@@ -552,6 +1138,11 @@ impl HelpPrompt {
     }
 
     pub fn add_project_numbers(&self) -> Result<&Self, Box<dyn Error>> {
+        // without input, project numbers are set with autolog update --project-number
+        if !crate::utils::is_interactive() {
+            return Ok(self);
+        }
+
         let mut client_repositories = self.client_repositories.borrow_mut();
 
         println!(
@@ -563,16 +1154,17 @@ impl HelpPrompt {
         );
 
         for i in 0..client_repositories.repositories.as_ref().unwrap().len() {
-            Self::print_question(&format!(
+            let question = format!(
                 "Does '{}' require a project/PO number?",
                 client_repositories.repositories.as_ref().unwrap()[i]
                     .namespace
                     .as_ref()
                     .unwrap()
-            ));
-            if Confirm::new().default(true).interact()? {
+            );
+            Self::print_question(&question);
+            if self.prompter.confirm(&question, true)? {
                 Self::print_question("Project number");
-                let input: String = Input::new().interact_text()?;
+                let input = self.prompter.input("Project number", None)?;
                 client_repositories
                     .repositories
                     .as_mut()
@@ -589,13 +1181,12 @@ impl HelpPrompt {
         deserialized_config: &mut ConfigurationDoc,
     ) -> Result<&Self, Box<dyn Error>> {
         if options[1].is_some() {
-            Self::print_question(&format!(
+            // remove the namespace from a client
+            if self.confirm(&format!(
                 "Remove '{}' from client '{}'?",
                 &options[1].as_ref().unwrap(),
                 &options[0].as_ref().unwrap()
-            ));
-            // remove the namespace from a client
-            if crate::utils::confirm()? {
+            ))? {
                 for i in 0..deserialized_config.len() {
                     if deserialized_config[i]
                         .client
@@ -624,12 +1215,11 @@ impl HelpPrompt {
                 }
             }
         } else {
-            Self::print_question(&format!(
+            // client is required and will be set, so remove from deserialized config
+            if self.confirm(&format!(
                 "Remove client '{}'?",
                 &options[0].as_ref().unwrap()
-            ));
-            // client is required and will be set, so remove from deserialized config
-            if crate::utils::confirm()? {
+            ))? {
                 let config_len = deserialized_config.len();
                 deserialized_config.retain(|client_repo| {
                     client_repo
@@ -729,3 +1319,92 @@ impl HelpPrompt {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::mocks;
+    use crate::helpers::mocks::MockPrompter;
+
+    fn create_prompt(prompter: &Rc<MockPrompter>) -> HelpPrompt {
+        let client_repositories = Rc::new(RefCell::new(ClientRepositories::default()));
+        mocks::create_mock_client_repository(&mut client_repositories.borrow_mut());
+
+        HelpPrompt::new(
+            Rc::new(RefCell::new(Repository::default())),
+            client_repositories,
+        )
+        .with_prompter(prompter.clone())
+    }
+
+    #[test]
+    fn it_updates_a_project_number_from_the_prompter() {
+        let prompter = Rc::new(MockPrompter::new(&["Project number", "PO-9"]));
+        let mut prompt = create_prompt(&prompter);
+        let mut options = vec![None; 17];
+        options[0] = Some("alphabet".to_string());
+        options[1] = Some("autolog".to_string());
+
+        prompt.prompt_for_update(options).unwrap();
+
+        let client_repositories = prompt.client_repositories.borrow();
+        assert_eq!(
+            client_repositories.repositories.as_ref().unwrap()[0].project_number,
+            Some("PO-9".to_string())
+        );
+        assert_eq!(
+            *prompter.questions.borrow(),
+            vec!["Value to update", "Project number"]
+        );
+    }
+
+    #[test]
+    fn it_asks_again_for_an_invalid_email() {
+        let prompter = Rc::new(MockPrompter::new(&["User email", "jim", "jim@jones.dev"]));
+        let mut prompt = create_prompt(&prompter);
+        let mut options = vec![None; 17];
+        options[0] = Some("alphabet".to_string());
+
+        prompt.prompt_for_update(options).unwrap();
+
+        let client_repositories = prompt.client_repositories.borrow();
+        let user = client_repositories.user.as_ref().unwrap();
+        assert_eq!(user.email, "jim@jones.dev");
+        assert!(user.is_alias);
+    }
+
+    #[test]
+    fn it_adds_client_details_from_the_prompter() {
+        let prompter = Rc::new(MockPrompter::new(&[
+            "Acme",
+            "Jo Bloggs",
+            "y",
+            "1 Main Street\\nSpringfield",
+        ]));
+        let prompt = create_prompt(&prompter);
+
+        prompt.add_client_details().unwrap();
+
+        let repository = prompt.repository.borrow();
+        assert_eq!(repository.client_name, Some("Acme".to_string()));
+        assert_eq!(
+            repository.client_contact_person,
+            Some("Jo Bloggs".to_string())
+        );
+        assert_eq!(
+            repository.client_address,
+            Some("1 Main Street\nSpringfield".to_string())
+        );
+    }
+
+    #[test]
+    fn it_fails_when_the_prompter_runs_out_of_answers() {
+        let prompter = Rc::new(MockPrompter::new(&["Acme"]));
+        let prompt = create_prompt(&prompter);
+
+        assert_eq!(
+            prompt.add_client_details().unwrap_err().to_string(),
+            "No scripted answer for 'Client contact person'"
+        );
+    }
+}
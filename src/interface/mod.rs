@@ -1,2 +1,4 @@
 pub mod cli;
 pub mod help_prompt;
+pub mod prompter;
+pub mod timesheet_editor;
@@ -0,0 +1,219 @@
+use dialoguer::{Confirm, Editor, Input, Select};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::Debug;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Prompter takes the answers to the questions HelpPrompt asks. The question is
+/// printed by HelpPrompt, so prompters only use it to describe a missing answer.
+pub trait Prompter: Debug {
+    fn confirm(&self, question: &str, default: bool) -> Result<bool, Box<dyn Error>>;
+    fn input(&self, question: &str, initial_text: Option<&str>) -> Result<String, Box<dyn Error>>;
+    fn select(
+        &self,
+        question: &str,
+        items: &[String],
+        default: Option<usize>,
+    ) -> Result<usize, Box<dyn Error>>;
+    /// Returns None when the edit is discarded
+    fn edit(&self, question: &str, initial_text: &str) -> Result<Option<String>, Box<dyn Error>>;
+}
+
+pub type RcPrompter = Rc<dyn Prompter>;
+
+/// Asks in the terminal
+#[derive(Debug, Default)]
+pub struct TerminalPrompter {}
+
+impl Prompter for TerminalPrompter {
+    fn confirm(&self, _question: &str, default: bool) -> Result<bool, Box<dyn Error>> {
+        Ok(Confirm::new().default(default).interact()?)
+    }
+
+    fn input(&self, _question: &str, initial_text: Option<&str>) -> Result<String, Box<dyn Error>> {
+        let mut input = Input::<String>::new();
+        if let Some(initial_text) = initial_text {
+            input.with_initial_text(initial_text);
+        }
+
+        Ok(input.interact_text()?)
+    }
+
+    fn select(
+        &self,
+        _question: &str,
+        items: &[String],
+        default: Option<usize>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut select = Select::new();
+        select.items(items);
+        if let Some(default) = default {
+            select.default(default);
+        }
+
+        Ok(select.interact()?)
+    }
+
+    fn edit(&self, _question: &str, initial_text: &str) -> Result<Option<String>, Box<dyn Error>> {
+        Ok(Editor::new().edit(initial_text)?)
+    }
+}
+
+/// Takes answers from a file, one per line, in the order the questions are asked.
+/// An empty line takes the default. Selections can be given by index or by item,
+/// confirmations as y/yes/n/no, and edited text can contain '\n' for new lines.
+#[derive(Debug, Default)]
+pub struct ScriptedPrompter {
+    answers: RefCell<VecDeque<String>>,
+}
+
+impl ScriptedPrompter {
+    pub fn new(answers: &str) -> Self {
+        ScriptedPrompter {
+            answers: RefCell::new(answers.lines().map(String::from).collect()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let answers = std::fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read answers from {}: {}", path.display(), err))?;
+
+        Ok(Self::new(&answers))
+    }
+
+    fn next_answer(&self, question: &str) -> Result<String, Box<dyn Error>> {
+        self.answers
+            .borrow_mut()
+            .pop_front()
+            .map(|answer| answer.trim().to_string())
+            .ok_or_else(|| format!("No scripted answer for '{}'", question).into())
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn confirm(&self, question: &str, default: bool) -> Result<bool, Box<dyn Error>> {
+        let answer = self.next_answer(question)?;
+
+        match answer.to_lowercase().as_str() {
+            "" => Ok(default),
+            "y" | "yes" => Ok(true),
+            "n" | "no" => Ok(false),
+            _ => Err(format!("'{}' is not a yes/no answer for '{}'", answer, question).into()),
+        }
+    }
+
+    fn input(&self, question: &str, initial_text: Option<&str>) -> Result<String, Box<dyn Error>> {
+        let answer = self.next_answer(question)?;
+
+        match (answer.is_empty(), initial_text) {
+            (true, Some(initial_text)) => Ok(initial_text.to_string()),
+            _ => Ok(answer),
+        }
+    }
+
+    fn select(
+        &self,
+        question: &str,
+        items: &[String],
+        default: Option<usize>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let answer = self.next_answer(question)?;
+
+        if answer.is_empty() {
+            if let Some(default) = default {
+                return Ok(default);
+            }
+        }
+
+        answer
+            .parse::<usize>()
+            .ok()
+            .filter(|index| index < &items.len())
+            .or_else(|| {
+                items
+                    .iter()
+                    .position(|item| item.eq_ignore_ascii_case(&answer))
+            })
+            .ok_or_else(|| {
+                format!(
+                    "'{}' is not one of the options for '{}': {}",
+                    answer,
+                    question,
+                    items.join(", ")
+                )
+                .into()
+            })
+    }
+
+    fn edit(&self, question: &str, initial_text: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let answer = self.next_answer(question)?;
+
+        match answer.is_empty() {
+            true => Ok(Some(initial_text.to_string())),
+            false => Ok(Some(answer.replace("\\n", "\n"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<String> {
+        vec!["Apple".to_string(), "Create a new client".to_string()]
+    }
+
+    #[test]
+    fn it_confirms_from_scripted_answers() {
+        let prompter = ScriptedPrompter::new("y\nNo\n\n");
+
+        assert!(prompter.confirm("Add?", false).unwrap());
+        assert!(!prompter.confirm("Add?", true).unwrap());
+        assert!(prompter.confirm("Add?", true).unwrap());
+        assert_eq!(
+            prompter.confirm("Add?", true).unwrap_err().to_string(),
+            "No scripted answer for 'Add?'"
+        );
+    }
+
+    #[test]
+    fn it_rejects_an_answer_that_isnt_yes_or_no() {
+        let prompter = ScriptedPrompter::new("maybe");
+
+        assert!(prompter.confirm("Add?", true).is_err());
+    }
+
+    #[test]
+    fn it_takes_input_or_the_initial_text() {
+        let prompter = ScriptedPrompter::new(" Jim Jones \n\n");
+
+        assert_eq!(prompter.input("Name", None).unwrap(), "Jim Jones");
+        assert_eq!(prompter.input("Path", Some("/home")).unwrap(), "/home");
+    }
+
+    #[test]
+    fn it_selects_by_index_or_item() {
+        let prompter = ScriptedPrompter::new("1\napple\n\nPear");
+
+        assert_eq!(prompter.select("Client", &items(), None).unwrap(), 1);
+        assert_eq!(prompter.select("Client", &items(), None).unwrap(), 0);
+        assert_eq!(prompter.select("Client", &items(), Some(1)).unwrap(), 1);
+        assert!(prompter.select("Client", &items(), None).is_err());
+    }
+
+    #[test]
+    fn it_edits_with_new_lines() {
+        let prompter = ScriptedPrompter::new("1 Infinite Loop\\nCupertino\n\n");
+
+        assert_eq!(
+            prompter.edit("Address", "").unwrap(),
+            Some("1 Infinite Loop\nCupertino".to_string())
+        );
+        assert_eq!(
+            prompter.edit("Address", "Kept").unwrap(),
+            Some("Kept".to_string())
+        );
+    }
+}
@@ -0,0 +1,641 @@
+use crate::data::client_repositories::ClientRepositories;
+use crate::data::repository::Repository;
+use crate::utils::date::date_parser::get_days_from_month;
+use chrono::{Datelike, Month, NaiveDate};
+use num_traits::cast::FromPrimitive;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState, Tabs, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+
+const HOUR_STEP: f64 = 0.5;
+pub const MAX_HOURS: f64 = 24.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditorMode {
+    Navigate,
+    Note(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorOutcome {
+    Save,
+    Discard,
+}
+
+/// Timesheet editor is a full-screen terminal UI for navigating a month day by day
+/// across all the repositories under a client. It operates directly on the timesheets held
+/// by ClientRepositories, so saving is left to Config in the same way as any other edit.
+pub struct TimesheetEditor<'a> {
+    client_repositories: &'a mut ClientRepositories,
+    year: i32,
+    month: u32,
+    repository_index: usize,
+    day: u32,
+    mode: EditorMode,
+    commits: HashMap<(usize, i32, u32, u32), Vec<String>>,
+    edited_months: BTreeSet<(i32, u32)>,
+}
+
+impl<'a> TimesheetEditor<'a> {
+    pub fn new(client_repositories: &'a mut ClientRepositories, year: i32, month: u32) -> Self {
+        Self {
+            client_repositories,
+            year,
+            month,
+            repository_index: 0,
+            day: 1,
+            mode: EditorMode::Navigate,
+            commits: HashMap::new(),
+            edited_months: BTreeSet::new(),
+        }
+    }
+
+    pub fn run(&mut self) -> Result<EditorOutcome, Box<dyn Error>> {
+        if self.repositories().is_empty() {
+            return Err("No repositories found for this client".into());
+        }
+
+        let mut terminal = ratatui::try_init()?;
+        let outcome = self.event_loop(&mut terminal);
+        ratatui::try_restore()?;
+
+        outcome
+    }
+
+    fn event_loop(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+    ) -> Result<EditorOutcome, Box<dyn Error>> {
+        loop {
+            self.load_commits_for_day();
+            terminal.draw(|frame| self.render(frame))?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                if let Some(outcome) = self.handle_key(key.code) {
+                    return Ok(outcome);
+                }
+            }
+        }
+    }
+
+    pub fn is_edited(&self) -> bool {
+        !self.edited_months.is_empty()
+    }
+
+    /// Every month with a change, as (year, month), so each can be checked before saving
+    pub fn edited_months(&self) -> Vec<(i32, u32)> {
+        self.edited_months.iter().copied().collect()
+    }
+
+    fn mark_edited(&mut self) {
+        self.edited_months.insert((self.year, self.month));
+    }
+
+    fn repositories(&self) -> &[Repository] {
+        self.client_repositories
+            .repositories
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    fn days_in_month(&self) -> u32 {
+        get_days_from_month(self.year, self.month)
+    }
+
+    fn day_entries<'r>(&self, repository: &'r Repository) -> Option<&'r Vec<Map<String, Value>>> {
+        repository
+            .timesheet
+            .as_ref()
+            .and_then(|timesheet| timesheet.get(&self.year.to_string()))
+            .and_then(|year| year.get(&self.month.to_string()))
+    }
+
+    fn current_day_entry(&self) -> Option<&Map<String, Value>> {
+        let repository = self.repositories().get(self.repository_index)?;
+        self.day_entries(repository)
+            .and_then(|days| days.get(self.day as usize - 1))
+    }
+
+    fn current_day_entry_mut(&mut self) -> Option<&mut Map<String, Value>> {
+        let (year, month, day) = (self.year, self.month, self.day);
+        let repository = self
+            .client_repositories
+            .repositories
+            .as_mut()?
+            .get_mut(self.repository_index)?;
+
+        repository
+            .ensure_timesheet_month(year, month)
+            .timesheet
+            .as_mut()?
+            .get_mut(&year.to_string())?
+            .get_mut(&month.to_string())?
+            .get_mut(day as usize - 1)
+    }
+
+    fn current_hours(&self) -> f64 {
+        self.current_day_entry()
+            .and_then(|day| day.get("hours"))
+            .and_then(Value::as_f64)
+            .unwrap_or_default()
+    }
+
+    fn current_note(&self) -> String {
+        self.current_day_entry()
+            .and_then(|day| day.get("note"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    pub fn next_day(&mut self) {
+        if self.day < self.days_in_month() {
+            self.day += 1;
+        }
+    }
+
+    pub fn previous_day(&mut self) {
+        if self.day > 1 {
+            self.day -= 1;
+        }
+    }
+
+    pub fn next_repository(&mut self) {
+        let len = self.repositories().len().max(1);
+        self.repository_index = (self.repository_index + 1) % len;
+    }
+
+    pub fn previous_repository(&mut self) {
+        let len = self.repositories().len().max(1);
+        self.repository_index = (self.repository_index + len - 1) % len;
+    }
+
+    pub fn next_month(&mut self) {
+        if self.month == 12 {
+            self.month = 1;
+            self.year += 1;
+        } else {
+            self.month += 1;
+        }
+        self.day = self.day.min(self.days_in_month());
+    }
+
+    pub fn previous_month(&mut self) {
+        if self.month == 1 {
+            self.month = 12;
+            self.year -= 1;
+        } else {
+            self.month -= 1;
+        }
+        self.day = self.day.min(self.days_in_month());
+    }
+
+    pub fn adjust_hours(&mut self, delta: f64) {
+        let hours = (self.current_hours() + delta).clamp(0.0, MAX_HOURS);
+
+        if let Some(day) = self.current_day_entry_mut() {
+            day.insert("hours".to_string(), Value::from(hours));
+            day.insert("user_edited".to_string(), Value::Bool(true));
+            if hours > 0.0 {
+                day.insert("absence".to_string(), Value::Bool(false));
+            }
+            self.mark_edited();
+        }
+    }
+
+    pub fn toggle_absence(&mut self) {
+        if let Some(day) = self.current_day_entry_mut() {
+            let is_absent = !day
+                .get("absence")
+                .and_then(Value::as_bool)
+                .unwrap_or_default();
+
+            day.insert("absence".to_string(), Value::Bool(is_absent));
+            day.insert("user_edited".to_string(), Value::Bool(true));
+            if is_absent {
+                day.insert("hours".to_string(), Value::from(0.0));
+            }
+            self.mark_edited();
+        }
+    }
+
+    pub fn set_note(&mut self, note: String) {
+        if let Some(day) = self.current_day_entry_mut() {
+            if note.trim().is_empty() {
+                day.remove("note");
+            } else {
+                day.insert("note".to_string(), Value::String(note.trim().to_string()));
+            }
+            day.insert("user_edited".to_string(), Value::Bool(true));
+            self.mark_edited();
+        }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> Option<EditorOutcome> {
+        match &mut self.mode {
+            EditorMode::Note(text) => match code {
+                KeyCode::Enter => {
+                    let note = text.clone();
+                    self.set_note(note);
+                    self.mode = EditorMode::Navigate;
+                }
+                KeyCode::Esc => self.mode = EditorMode::Navigate,
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            },
+            EditorMode::Navigate => match code {
+                KeyCode::Down | KeyCode::Char('j') => self.next_day(),
+                KeyCode::Up | KeyCode::Char('k') => self.previous_day(),
+                KeyCode::Right | KeyCode::Tab | KeyCode::Char('l') => self.next_repository(),
+                KeyCode::Left | KeyCode::BackTab | KeyCode::Char('h') => self.previous_repository(),
+                KeyCode::Char(']') => self.next_month(),
+                KeyCode::Char('[') => self.previous_month(),
+                KeyCode::Char('+') | KeyCode::Char('=') => self.adjust_hours(HOUR_STEP),
+                KeyCode::Char('-') => self.adjust_hours(-HOUR_STEP),
+                KeyCode::Char('a') => self.toggle_absence(),
+                KeyCode::Char('n') => self.mode = EditorMode::Note(self.current_note()),
+                KeyCode::Char('s') => return Some(EditorOutcome::Save),
+                KeyCode::Char('q') | KeyCode::Esc => return Some(EditorOutcome::Discard),
+                _ => {}
+            },
+        }
+
+        None
+    }
+
+    fn load_commits_for_day(&mut self) {
+        let key = (self.repository_index, self.year, self.month, self.day);
+        if self.commits.contains_key(&key) {
+            return;
+        }
+
+        let commits = match self.repositories().get(self.repository_index) {
+            Some(repository) => repository
+                .find_commits_for_day(self.year, self.month, self.day)
+                .unwrap_or_else(|err| vec![format!("Unable to read git log: {}", err)]),
+            None => vec![],
+        };
+
+        self.commits.insert(key, commits);
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(8),
+                Constraint::Length(3),
+            ])
+            .split(frame.area());
+
+        let body = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(areas[1]);
+
+        let side = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(4), Constraint::Length(5)])
+            .split(body[1]);
+
+        let month_name = Month::from_u32(self.month)
+            .map(|month| month.name())
+            .unwrap_or_default();
+        let title = format!(
+            " {} - {} {}{} ",
+            self.client_repositories.get_client_name(),
+            month_name,
+            self.year,
+            if self.is_edited() { " [modified]" } else { "" }
+        );
+
+        let namespaces: Vec<String> = self
+            .repositories()
+            .iter()
+            .map(|repository| repository.namespace.clone().unwrap_or_default())
+            .collect();
+        let tabs = Tabs::new(namespaces)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .select(self.repository_index)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED));
+        frame.render_widget(tabs, areas[0]);
+
+        let (rows, total_hours) = self.day_rows();
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Length(7),
+                Constraint::Length(8),
+                Constraint::Min(10),
+            ],
+        )
+        .header(
+            Row::new(["Day", "Hours", "Absent", "Note"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" Total hours: {} ", total_hours)),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut table_state = TableState::default().with_selected(Some(self.day as usize - 1));
+        frame.render_stateful_widget(table, body[0], &mut table_state);
+
+        let commits: Vec<Line> = self
+            .commits
+            .get(&(self.repository_index, self.year, self.month, self.day))
+            .map(|commits| {
+                commits
+                    .iter()
+                    .map(|commit| Line::from(commit.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let commits = Paragraph::new(if commits.is_empty() {
+            vec![Line::from("No commits found for this day")]
+        } else {
+            commits
+        })
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL).title(" Commits "));
+        frame.render_widget(commits, side[0]);
+
+        let (note_title, note) = match &self.mode {
+            EditorMode::Note(text) => (
+                " Note (enter to save, esc to cancel) ",
+                format!("{}_", text),
+            ),
+            EditorMode::Navigate => (" Note ", self.current_note()),
+        };
+        let note = Paragraph::new(note)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title(note_title));
+        frame.render_widget(note, side[1]);
+
+        let help = Paragraph::new(
+            "up/down day  left/right repository  [ ] month  +/- hours  a absence  n note  s save  q quit",
+        )
+        .style(Style::default().add_modifier(Modifier::DIM))
+        .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(help, areas[2]);
+    }
+
+    fn day_rows(&self) -> (Vec<Row<'static>>, f64) {
+        let entries = self
+            .repositories()
+            .get(self.repository_index)
+            .and_then(|repository| self.day_entries(repository));
+        let mut total_hours = 0.0;
+
+        let rows = (1..self.days_in_month() + 1)
+            .map(|day| {
+                let entry = entries.and_then(|days| days.get(day as usize - 1));
+                let hours = entry
+                    .and_then(|entry| entry.get("hours"))
+                    .and_then(Value::as_f64)
+                    .unwrap_or_default();
+                let is_absent = entry
+                    .and_then(|entry| entry.get("absence"))
+                    .and_then(Value::as_bool)
+                    .unwrap_or_default();
+                let is_edited = entry
+                    .and_then(|entry| entry.get("user_edited"))
+                    .and_then(Value::as_bool)
+                    .unwrap_or_default();
+                let note = entry
+                    .and_then(|entry| entry.get("note"))
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+                total_hours += hours;
+
+                let weekday = NaiveDate::from_ymd_opt(self.year, self.month, day)
+                    .map(|date| date.weekday().to_string())
+                    .unwrap_or_default();
+
+                let row = Row::new([
+                    format!("{} {:02}", weekday, day),
+                    format!("{}{}", hours, if is_edited { "*" } else { "" }),
+                    if is_absent {
+                        "yes".to_string()
+                    } else {
+                        String::new()
+                    },
+                    note,
+                ]);
+
+                match weekday.as_str() {
+                    "Sat" | "Sun" => row.style(Style::default().add_modifier(Modifier::DIM)),
+                    _ => row,
+                }
+            })
+            .collect();
+
+        (rows, total_hours)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::mocks;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn create_mock_client_repositories() -> ClientRepositories {
+        let mut client_repositories = ClientRepositories {
+            ..Default::default()
+        };
+
+        mocks::create_mock_client_repository(&mut client_repositories);
+        client_repositories.repositories = Option::from(vec![
+            mocks::create_mock_repository(),
+            Repository {
+                namespace: Option::from("autolog-web".to_string()),
+                ..Default::default()
+            },
+        ]);
+
+        client_repositories
+    }
+
+    #[test]
+    fn it_navigates_days_within_the_month() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 11);
+
+        editor.previous_day();
+        assert_eq!(editor.day, 1);
+
+        for _ in 0..40 {
+            editor.next_day();
+        }
+        assert_eq!(editor.day, 30);
+    }
+
+    #[test]
+    fn it_navigates_months_and_clamps_the_day() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 12);
+        editor.day = 31;
+
+        editor.next_month();
+        assert_eq!((editor.year, editor.month, editor.day), (2022, 1, 31));
+
+        editor.next_month();
+        assert_eq!((editor.year, editor.month, editor.day), (2022, 2, 28));
+
+        editor.previous_month();
+        editor.previous_month();
+        assert_eq!((editor.year, editor.month), (2021, 12));
+    }
+
+    #[test]
+    fn it_wraps_around_repositories() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 10);
+
+        editor.previous_repository();
+        assert_eq!(editor.repository_index, 1);
+        editor.next_repository();
+        assert_eq!(editor.repository_index, 0);
+    }
+
+    #[test]
+    fn it_adjusts_hours_and_marks_the_day_as_edited() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 10);
+        editor.day = 20;
+
+        editor.adjust_hours(-HOUR_STEP);
+        assert_eq!(editor.current_hours(), 7.5);
+        assert!(editor.is_edited());
+
+        for _ in 0..40 {
+            editor.adjust_hours(HOUR_STEP);
+        }
+        assert_eq!(editor.current_hours(), MAX_HOURS);
+        assert_eq!(
+            editor.current_day_entry().unwrap().get("user_edited"),
+            Some(&Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn it_tracks_every_month_that_was_edited() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 10);
+
+        editor.next_month();
+        assert!(!editor.is_edited());
+
+        editor.adjust_hours(HOUR_STEP);
+        editor.previous_month();
+        editor.previous_month();
+        editor.toggle_absence();
+
+        assert_eq!(editor.edited_months(), vec![(2021, 9), (2021, 11)]);
+    }
+
+    #[test]
+    fn it_creates_a_month_when_editing_a_repository_without_one() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 10);
+        editor.next_repository();
+        assert!(editor.current_day_entry().is_none());
+
+        editor.adjust_hours(HOUR_STEP);
+        assert_eq!(editor.current_hours(), 0.5);
+    }
+
+    #[test]
+    fn it_toggles_absence_and_clears_hours() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 10);
+        editor.day = 20;
+
+        editor.toggle_absence();
+        assert_eq!(editor.current_hours(), 0.0);
+        assert_eq!(
+            editor.current_day_entry().unwrap().get("absence"),
+            Some(&Value::Bool(true))
+        );
+
+        editor.adjust_hours(HOUR_STEP);
+        assert_eq!(
+            editor.current_day_entry().unwrap().get("absence"),
+            Some(&Value::Bool(false))
+        );
+    }
+
+    #[test]
+    fn it_adds_a_note_through_key_input() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 10);
+
+        editor.handle_key(KeyCode::Char('n'));
+        for c in "Planning".chars() {
+            editor.handle_key(KeyCode::Char(c));
+        }
+        editor.handle_key(KeyCode::Backspace);
+        assert_eq!(editor.handle_key(KeyCode::Enter), None);
+
+        assert_eq!(editor.mode, EditorMode::Navigate);
+        assert_eq!(editor.current_note(), "Plannin".to_string());
+    }
+
+    #[test]
+    fn it_returns_an_outcome_when_saving_or_quitting() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 10);
+
+        assert_eq!(
+            editor.handle_key(KeyCode::Char('s')),
+            Some(EditorOutcome::Save)
+        );
+        assert_eq!(
+            editor.handle_key(KeyCode::Char('q')),
+            Some(EditorOutcome::Discard)
+        );
+    }
+
+    #[test]
+    fn it_renders_the_month_for_the_selected_repository() {
+        let mut client_repositories = create_mock_client_repositories();
+        let mut editor = TimesheetEditor::new(&mut client_repositories, 2021, 10);
+        editor
+            .commits
+            .insert((0, 2021, 10, 1), vec!["abc1234 Fix timesheet".to_string()]);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 45)).unwrap();
+        terminal.draw(|frame| editor.render(frame)).unwrap();
+
+        let content: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        assert!(content.contains("alphabet - October 2021"));
+        assert!(content.contains("Total hours: 24"));
+        assert!(content.contains("abc1234 Fix timesheet"));
+    }
+}
@@ -0,0 +1,496 @@
+use crate::data::repository::GitLogDates;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+
+pub const DEFAULT_IDLE_MINUTES: i64 = 15;
+
+/// A stretch of file changes in a repository with no gap longer than the idle timeout.
+/// Sessions are recorded by the daemon, one JSON record per line, and count as worked
+/// days alongside git history.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Session {
+    pub git_path: String,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+}
+
+impl Session {
+    /// Every day the session touches, usually just the one
+    pub fn days(&self) -> Vec<NaiveDate> {
+        self.start
+            .date_naive()
+            .iter_days()
+            .take_while(|day| day <= &self.end.date_naive())
+            .collect()
+    }
+}
+
+/// Turns file changes into sessions, closing a repository's session once it's been
+/// idle for longer than the timeout
+#[derive(Debug)]
+pub struct SessionTracker {
+    idle_timeout: Duration,
+    open: HashMap<String, (DateTime<FixedOffset>, DateTime<FixedOffset>)>,
+}
+
+impl SessionTracker {
+    pub fn new(idle_timeout: Duration) -> Self {
+        SessionTracker {
+            idle_timeout,
+            open: HashMap::new(),
+        }
+    }
+
+    /// Record a change, returning the previous session if the repository had gone idle
+    pub fn touch(&mut self, git_path: &str, at: DateTime<FixedOffset>) -> Option<Session> {
+        let closed = match self.open.get(git_path) {
+            Some((start, last)) if at - *last > self.idle_timeout => Some(Session {
+                git_path: git_path.to_string(),
+                start: *start,
+                end: *last,
+            }),
+            _ => None,
+        };
+
+        self.open
+            .entry(git_path.to_string())
+            .and_modify(|(start, last)| {
+                if closed.is_some() {
+                    *start = at;
+                }
+                *last = at;
+            })
+            .or_insert((at, at));
+
+        closed
+    }
+
+    /// Close the sessions that have been idle for longer than the timeout at now
+    pub fn close_idle(&mut self, now: DateTime<FixedOffset>) -> Vec<Session> {
+        let idle_timeout = self.idle_timeout;
+        self.close_where(|last| now - last > idle_timeout)
+    }
+
+    /// Close every open session, i.e. when the daemon stops
+    pub fn close_all(&mut self) -> Vec<Session> {
+        self.close_where(|_| true)
+    }
+
+    fn close_where(
+        &mut self,
+        should_close: impl Fn(DateTime<FixedOffset>) -> bool,
+    ) -> Vec<Session> {
+        let closing: Vec<String> = self
+            .open
+            .iter()
+            .filter(|(_, (_, last))| should_close(*last))
+            .map(|(git_path, _)| git_path.clone())
+            .collect();
+
+        let mut sessions: Vec<Session> = closing
+            .into_iter()
+            .filter_map(|git_path| {
+                self.open.remove(&git_path).map(|(start, end)| Session {
+                    git_path,
+                    start,
+                    end,
+                })
+            })
+            .collect();
+        sessions.sort_by_key(|session| session.start);
+        sessions
+    }
+}
+
+pub fn append(path: &Path, sessions: &[Session]) -> Result<(), Box<dyn Error>> {
+    if sessions.is_empty() {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for session in sessions {
+        writeln!(file, "{}", serde_json::to_string(session)?)?;
+    }
+
+    Ok(())
+}
+
+/// Read every session in the activity log, skipping lines that can't be read
+pub fn read(path: &Path) -> Result<Vec<Session>, Box<dyn Error>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let contents = std::fs::read_to_string(path)?;
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Days with a session for a repository, in the same shape as dates parsed from git log
+pub fn git_log_dates_for(sessions: &[Session], git_path: &str) -> GitLogDates {
+    let mut git_log_dates = GitLogDates::new();
+
+    for session in sessions
+        .iter()
+        .filter(|session| session.git_path == git_path)
+    {
+        for day in session.days() {
+            git_log_dates
+                .entry(day.year())
+                .or_default()
+                .entry(day.month())
+                .or_default()
+                .insert(day.day());
+        }
+    }
+
+    git_log_dates
+}
+
+/// A repository being watched, by its worktree and the git path it's recorded under
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchedRepository {
+    pub repo_path: PathBuf,
+    pub git_path: String,
+}
+
+/// Find the repository a changed file belongs to. Changes inside .git are made by
+/// git itself, and are recorded by the hooks instead
+pub fn find_watched_repository<'a>(
+    repositories: &'a [WatchedRepository],
+    changed_path: &Path,
+) -> Option<&'a WatchedRepository> {
+    repositories
+        .iter()
+        .filter(|repository| changed_path.starts_with(&repository.repo_path))
+        .max_by_key(|repository| repository.repo_path.components().count())
+        .filter(|repository| {
+            !changed_path
+                .strip_prefix(&repository.repo_path)
+                .map(|relative| relative.components().any(|part| part.as_os_str() == ".git"))
+                .unwrap_or(false)
+        })
+}
+
+/// Editor swap, backup and lock files, which change while a file is only open
+fn is_editor_file(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    name.ends_with('~')
+        || name.starts_with(".#")
+        || name == "4913"
+        || [".swp", ".swo", ".swx"]
+            .iter()
+            .any(|extension| name.ends_with(extension))
+}
+
+/// Paths git ignores, i.e. build output and installed dependencies, change without
+/// anyone working on the repository. Ignored directories are remembered, so a build
+/// doesn't ask git about every file it writes.
+#[derive(Debug, Default)]
+pub struct IgnoredPaths {
+    directories: HashSet<PathBuf>,
+}
+
+impl IgnoredPaths {
+    pub fn is_ignored(&mut self, repository: &WatchedRepository, changed_path: &Path) -> bool {
+        if is_editor_file(changed_path)
+            || changed_path
+                .ancestors()
+                .any(|ancestor| self.directories.contains(ancestor))
+        {
+            return true;
+        }
+        let relative = match changed_path.strip_prefix(&repository.repo_path) {
+            Ok(relative) if relative.components().count() > 0 => relative,
+            _ => return false,
+        };
+
+        // ask about each directory on the way down too, to find the one that's ignored
+        let mut candidates: Vec<&Path> = relative
+            .ancestors()
+            .filter(|ancestor| ancestor.components().count() > 0)
+            .collect();
+        candidates.reverse();
+
+        let ignored = match check_ignore(&repository.repo_path, &candidates) {
+            Ok(ignored) => ignored,
+            Err(_) => return false,
+        };
+        for path in &ignored {
+            if path.as_path() != relative {
+                self.directories.insert(repository.repo_path.join(path));
+            }
+        }
+
+        !ignored.is_empty()
+    }
+}
+
+/// The paths, relative to the worktree, that git check-ignore reports as ignored
+fn check_ignore(repo_path: &Path, paths: &[&Path]) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["check-ignore", "--stdin", "-z"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        for path in paths {
+            stdin.write_all(path.to_string_lossy().as_bytes())?;
+            stdin.write_all(b"\0")?;
+        }
+    }
+
+    let output = child.wait_with_output()?;
+    // 1 means nothing was ignored, anything else that isn't 0 is an error
+    match output.status.code() {
+        Some(0) | Some(1) => Ok(output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(String::from_utf8_lossy(path).to_string()))
+            .collect()),
+        _ => Err(format!("git check-ignore failed in {}", repo_path.display()).into()),
+    }
+}
+
+enum Message {
+    Changed(notify::Result<notify::Event>),
+    Stop,
+}
+
+/// Watch the repositories for file changes until interrupted, writing each session
+/// to the activity log once it goes idle
+pub fn watch(
+    repositories: &[WatchedRepository],
+    idle_timeout: Duration,
+    log_path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let (sender, receiver) = mpsc::channel();
+
+    let changed_sender = sender.clone();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = changed_sender.send(Message::Changed(event));
+    })?;
+    for repository in repositories {
+        watcher.watch(&repository.repo_path, RecursiveMode::Recursive)?;
+    }
+
+    // open sessions are written when the daemon is stopped
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build();
+        if let Ok(runtime) = runtime {
+            let _ = runtime.block_on(tokio::signal::ctrl_c());
+            let _ = sender.send(Message::Stop);
+        }
+    });
+
+    let mut tracker = SessionTracker::new(idle_timeout);
+    let mut ignored_paths = IgnoredPaths::default();
+    // check for idle sessions well within the timeout
+    let check_every = (idle_timeout / 4)
+        .to_std()
+        .unwrap_or(std::time::Duration::from_secs(60));
+
+    loop {
+        match receiver.recv_timeout(check_every) {
+            Ok(Message::Changed(Ok(event))) => {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    continue;
+                }
+
+                let now = Local::now().fixed_offset();
+                for path in &event.paths {
+                    if let Some(repository) = find_watched_repository(repositories, path) {
+                        if ignored_paths.is_ignored(repository, path) {
+                            continue;
+                        }
+                        if let Some(session) = tracker.touch(&repository.git_path, now) {
+                            append(log_path, &[session])?;
+                        }
+                    }
+                }
+            }
+            Ok(Message::Changed(Err(err))) => eprintln!("Error watching files: {}", err),
+            Ok(Message::Stop) | Err(mpsc::RecvTimeoutError::Disconnected) => {
+                append(log_path, &tracker.close_all())?;
+                return Ok(());
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+        }
+
+        append(log_path, &tracker.close_idle(Local::now().fixed_offset()))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn at(timestamp: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(timestamp).unwrap()
+    }
+
+    fn new_session(start: &str, end: &str) -> Session {
+        Session {
+            git_path: "/work/api/.git/".to_string(),
+            start: at(start),
+            end: at(end),
+        }
+    }
+
+    #[test]
+    fn it_extends_a_session_until_it_goes_idle() {
+        let mut tracker = SessionTracker::new(Duration::minutes(15));
+
+        assert_eq!(
+            tracker.touch("/work/api/.git/", at("2021-11-02T09:00:00+00:00")),
+            None
+        );
+        assert_eq!(
+            tracker.touch("/work/api/.git/", at("2021-11-02T09:10:00+00:00")),
+            None
+        );
+        assert_eq!(
+            tracker.touch("/work/api/.git/", at("2021-11-02T11:00:00+00:00")),
+            Some(new_session(
+                "2021-11-02T09:00:00+00:00",
+                "2021-11-02T09:10:00+00:00"
+            ))
+        );
+        assert_eq!(
+            tracker.close_all(),
+            vec![new_session(
+                "2021-11-02T11:00:00+00:00",
+                "2021-11-02T11:00:00+00:00"
+            )]
+        );
+    }
+
+    #[test]
+    fn it_closes_idle_sessions() {
+        let mut tracker = SessionTracker::new(Duration::minutes(15));
+        tracker.touch("/work/api/.git/", at("2021-11-02T09:00:00+00:00"));
+        tracker.touch("/work/web/.git/", at("2021-11-02T09:20:00+00:00"));
+
+        let closed = tracker.close_idle(at("2021-11-02T09:30:00+00:00"));
+
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].git_path, "/work/api/.git/");
+        assert_eq!(tracker.close_all().len(), 1);
+    }
+
+    #[test]
+    fn it_appends_and_reads_sessions() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("autolog/activity.jsonl");
+        let session = new_session("2021-11-02T09:00:00+00:00", "2021-11-02T10:00:00+00:00");
+
+        append(&path, std::slice::from_ref(&session)).unwrap();
+        append(&path, &[]).unwrap();
+
+        assert_eq!(read(&path).unwrap(), vec![session]);
+    }
+
+    #[test]
+    fn it_finds_days_with_sessions_for_a_repository() {
+        let sessions = vec![
+            new_session("2021-11-02T23:00:00+00:00", "2021-11-03T01:00:00+00:00"),
+            Session {
+                git_path: "/work/web/.git/".to_string(),
+                ..new_session("2021-11-09T10:00:00+00:00", "2021-11-09T11:00:00+00:00")
+            },
+        ];
+
+        let git_log_dates = git_log_dates_for(&sessions, "/work/api/.git/");
+
+        let mut days: Vec<u32> = git_log_dates[&2021][&11].iter().copied().collect();
+        days.sort();
+        assert_eq!(days, vec![2, 3]);
+    }
+
+    #[test]
+    fn it_finds_the_repository_for_a_changed_file() {
+        let repositories = vec![
+            WatchedRepository {
+                repo_path: PathBuf::from("/work/api"),
+                git_path: "/work/api/.git/".to_string(),
+            },
+            WatchedRepository {
+                repo_path: PathBuf::from("/work/api/vendor/lib"),
+                git_path: "/work/api/vendor/lib/.git/".to_string(),
+            },
+        ];
+
+        assert_eq!(
+            find_watched_repository(&repositories, Path::new("/work/api/src/main.rs")),
+            Some(&repositories[0])
+        );
+        assert_eq!(
+            find_watched_repository(&repositories, Path::new("/work/api/vendor/lib/a.rs")),
+            Some(&repositories[1])
+        );
+        assert_eq!(
+            find_watched_repository(&repositories, Path::new("/work/api/.git/index")),
+            None
+        );
+        assert_eq!(
+            find_watched_repository(&repositories, Path::new("/work/web/index.js")),
+            None
+        );
+    }
+
+    #[test]
+    fn it_ignores_build_output_and_editor_files() {
+        let dir = tempdir().unwrap();
+        Command::new("git")
+            .arg("init")
+            .arg(dir.path())
+            .output()
+            .unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\nnode_modules/\n").unwrap();
+        std::fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("target/debug/autolog.o"), "").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+
+        let repository = WatchedRepository {
+            repo_path: dir.path().to_path_buf(),
+            git_path: dir.path().join(".git/").to_string_lossy().to_string(),
+        };
+        let mut ignored_paths = IgnoredPaths::default();
+
+        assert!(ignored_paths.is_ignored(&repository, &dir.path().join("target/debug/autolog.o")));
+        assert!(ignored_paths
+            .directories
+            .contains(&dir.path().join("target")));
+        assert!(ignored_paths.is_ignored(&repository, &dir.path().join("target/release/new.o")));
+        assert!(ignored_paths.is_ignored(&repository, &dir.path().join("src/.main.rs.swp")));
+        assert!(!ignored_paths.is_ignored(&repository, &dir.path().join("src/main.rs")));
+        assert!(!ignored_paths.is_ignored(&repository, &dir.path().join(".gitignore")));
+    }
+}
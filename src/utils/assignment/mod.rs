@@ -0,0 +1,141 @@
+use crate::interface::help_prompt::ConfigurationDoc;
+use crate::utils::file::file_reader;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Repositories are assigned to a client automatically when they match one of the
+/// client's rules, i.e. a remote under the 'acme-corp' org or a path under ~/clients/foo.
+/// A rule matches when every criteria set on it matches.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct AssignmentRule {
+    pub remote_org: Option<String>,
+    pub path: Option<String>,
+}
+
+/// Expand a leading '~' to the home directory
+pub fn expand_path(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) => file_reader::get_home_path().join(rest.trim_start_matches('/')),
+        None => PathBuf::from(path),
+    }
+}
+
+impl AssignmentRule {
+    pub fn is_empty(&self) -> bool {
+        self.remote_org.is_none() && self.path.is_none()
+    }
+
+    pub fn matches(&self, repo_path: &Path, remote_org: Option<&str>) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        if let Some(org) = &self.remote_org {
+            // nested groups (acme/team) are matched by their top level org
+            let matches_org = remote_org
+                .and_then(|remote_org| remote_org.split('/').next())
+                .is_some_and(|remote_org| remote_org.eq_ignore_ascii_case(org));
+            if !matches_org {
+                return false;
+            }
+        }
+
+        if let Some(path) = &self.path {
+            let path = expand_path(path);
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            if !repo_path.starts_with(path) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Find the first client with a rule matching the repository
+pub fn find_client(
+    config: &ConfigurationDoc,
+    repo_path: &Path,
+    remote_org: Option<&str>,
+) -> Option<String> {
+    config
+        .iter()
+        .find(|client| {
+            client
+                .assignment_rules
+                .iter()
+                .flatten()
+                .any(|rule| rule.matches(repo_path, remote_org))
+        })
+        .map(|client| client.get_client_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::client_repositories::ClientRepositories;
+    use crate::helpers::mocks;
+
+    fn new_rule(remote_org: Option<&str>, path: Option<&str>) -> AssignmentRule {
+        AssignmentRule {
+            remote_org: remote_org.map(String::from),
+            path: path.map(String::from),
+        }
+    }
+
+    #[test]
+    fn it_matches_a_remote_org() {
+        let rule = new_rule(Some("acme-corp"), None);
+        let path = Path::new("/work/api");
+
+        assert!(rule.matches(path, Some("Acme-Corp")));
+        assert!(rule.matches(path, Some("acme-corp/platform")));
+        assert!(!rule.matches(path, Some("globex")));
+        assert!(!rule.matches(path, None));
+    }
+
+    #[test]
+    fn it_matches_a_path() {
+        let rule = new_rule(None, Some("/clients/foo"));
+
+        assert!(rule.matches(Path::new("/clients/foo/api"), None));
+        assert!(!rule.matches(Path::new("/clients/foobar/api"), None));
+        assert!(!rule.matches(Path::new("/work/api"), Some("foo")));
+    }
+
+    #[test]
+    fn it_matches_every_criteria_set_on_a_rule() {
+        let rule = new_rule(Some("acme-corp"), Some("/clients/acme"));
+
+        assert!(rule.matches(Path::new("/clients/acme/api"), Some("acme-corp")));
+        assert!(!rule.matches(Path::new("/clients/acme/api"), Some("globex")));
+        assert!(!rule.matches(Path::new("/work/api"), Some("acme-corp")));
+        assert!(!new_rule(None, None).matches(Path::new("/work/api"), Some("acme-corp")));
+    }
+
+    #[test]
+    fn it_expands_the_home_directory() {
+        assert_eq!(
+            expand_path("~/clients/foo"),
+            file_reader::get_home_path().join("clients/foo")
+        );
+        assert_eq!(expand_path("/clients/foo"), PathBuf::from("/clients/foo"));
+    }
+
+    #[test]
+    fn it_finds_the_client_for_a_repository() {
+        let mut client_repositories = ClientRepositories::default();
+        mocks::create_mock_client_repository(&mut client_repositories);
+        client_repositories.add_assignment_rule(new_rule(Some("alphabet-inc"), None));
+        let config = vec![client_repositories];
+
+        assert_eq!(
+            find_client(&config, Path::new("/work/api"), Some("alphabet-inc")),
+            Some("alphabet".to_string())
+        );
+        assert_eq!(
+            find_client(&config, Path::new("/work/api"), Some("globex")),
+            None
+        );
+    }
+}
@@ -0,0 +1,205 @@
+extern crate google_calendar3 as calendar3;
+use crate::utils::calendar::{split_all_day_event, CalendarEvent, CalendarService};
+use async_trait::async_trait;
+use calendar3::api::{Event, EventDateTime};
+use calendar3::{hyper, hyper_rustls, CalendarHub};
+use chrono::{DateTime, Local, Utc};
+use std::error::Error;
+
+type HttpsConnector = hyper_rustls::HttpsConnector<hyper::client::HttpConnector>;
+
+/// Reads events from a linked Google calendar using the stored OAuth token
+pub struct GoogleCalendar {
+    hub: CalendarHub<HttpsConnector>,
+    calendar_id: String,
+}
+
+/// Build a client for Google's APIs. Plain http is allowed so tests can use a local server
+pub fn create_https_client() -> Result<hyper::Client<HttpsConnector>, Box<dyn Error>> {
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_native_roots()?
+        .https_or_http()
+        .enable_http1()
+        .build();
+
+    Ok(hyper::Client::builder().build(connector))
+}
+
+impl GoogleCalendar {
+    pub fn new(access_token: String, calendar_id: String) -> Result<Self, Box<dyn Error>> {
+        let client = create_https_client()?;
+
+        Ok(GoogleCalendar {
+            hub: CalendarHub::new(client, access_token),
+            calendar_id,
+        })
+    }
+
+    /// Point requests at a different server, i.e. a local mock in tests
+    #[cfg(test)]
+    pub fn set_base_url(&mut self, base_url: String) -> &mut Self {
+        self.hub.base_url(base_url);
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl CalendarService for GoogleCalendar {
+    async fn list_events(
+        &self,
+        time_min: DateTime<Utc>,
+        time_max: DateTime<Utc>,
+    ) -> Result<Vec<CalendarEvent>, Box<dyn Error>> {
+        let mut events = vec![];
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut call = self
+                .hub
+                .events()
+                .list(&self.calendar_id)
+                .time_min(time_min)
+                .time_max(time_max)
+                .single_events(true);
+
+            if let Some(token) = &page_token {
+                call = call.page_token(token);
+            }
+
+            let (_response, page) = call.doit().await?;
+
+            events.extend(
+                page.items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flat_map(|event| events_from_google_event(&self.calendar_id, event)),
+            );
+
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+fn events_from_google_event(calendar_id: &str, event: Event) -> Vec<CalendarEvent> {
+    if event.status.as_deref() == Some("cancelled") {
+        return vec![];
+    }
+
+    let start: EventDateTime = match event.start {
+        Some(start) => start,
+        None => return vec![],
+    };
+    let end: Option<EventDateTime> = event.end;
+
+    let (date, hours, all_day) = match (start.date_time, start.date) {
+        (Some(start_time), _) => {
+            let hours = end
+                .as_ref()
+                .and_then(|end| end.date_time)
+                .map(|end_time| (end_time - start_time).num_minutes() as f64 / 60.0)
+                .unwrap_or_default();
+            (start_time.with_timezone(&Local).date_naive(), hours, false)
+        }
+        (None, Some(date)) => (date, 0.0, true),
+        (None, None) => return vec![],
+    };
+
+    let calendar_event = CalendarEvent {
+        calendar_id: calendar_id.to_string(),
+        title: event.summary.unwrap_or_default(),
+        attendees: event
+            .attendees
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|attendee| attendee.email)
+            .collect(),
+        date,
+        hours,
+        all_day,
+    };
+
+    match all_day {
+        true => split_all_day_event(calendar_event, end.and_then(|end| end.date)),
+        false => vec![calendar_event],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::mocks;
+    use chrono::{NaiveDate, TimeZone};
+
+    #[tokio::test]
+    async fn it_lists_events_from_a_calendar() {
+        let body = r#"{
+            "kind": "calendar#events",
+            "items": [
+                {
+                    "id": "1",
+                    "status": "confirmed",
+                    "summary": "Alphabet planning",
+                    "attendees": [{ "email": "jim@jones.com" }, { "email": "john@alphabet.com" }],
+                    "start": { "dateTime": "2021-10-20T10:00:00Z" },
+                    "end": { "dateTime": "2021-10-20T11:30:00Z" }
+                },
+                {
+                    "id": "2",
+                    "status": "cancelled",
+                    "summary": "Cancelled meeting",
+                    "start": { "dateTime": "2021-10-21T10:00:00Z" },
+                    "end": { "dateTime": "2021-10-21T11:00:00Z" }
+                },
+                {
+                    "id": "3",
+                    "summary": "Offsite",
+                    "start": { "date": "2021-10-22" },
+                    "end": { "date": "2021-10-24" }
+                }
+            ]
+        }"#;
+
+        let (base_url, request) = mocks::serve_mock_http_response(body.to_string());
+
+        let mut calendar = GoogleCalendar::new("token".to_string(), "primary".to_string()).unwrap();
+        calendar.set_base_url(base_url);
+
+        let events = calendar
+            .list_events(
+                Utc.with_ymd_and_hms(2021, 10, 1, 0, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2021, 11, 1, 0, 0, 0).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let request = request.join().unwrap();
+        assert!(request.starts_with("GET /calendars/primary/events?"));
+        assert!(request.contains("singleEvents=true"));
+        assert!(request
+            .to_lowercase()
+            .contains("authorization: bearer token"));
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].title, "Alphabet planning".to_string());
+        assert_eq!(
+            events[0].attendees,
+            vec!["jim@jones.com".to_string(), "john@alphabet.com".to_string()]
+        );
+        assert_eq!(events[0].hours, 1.5);
+        assert!(!events[0].all_day);
+        assert_eq!(
+            events[1].date,
+            NaiveDate::from_ymd_opt(2021, 10, 22).unwrap()
+        );
+        assert!(events[1].all_day);
+        assert_eq!(
+            events[2].date,
+            NaiveDate::from_ymd_opt(2021, 10, 23).unwrap()
+        );
+    }
+}
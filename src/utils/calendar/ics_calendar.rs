@@ -0,0 +1,685 @@
+use crate::utils::calendar::{split_all_day_event, CalendarEvent, CalendarService};
+use async_trait::async_trait;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday,
+};
+use ical::parser::ical::component::IcalEvent;
+use ical::IcalParser;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Reads events from a local .ics file, or every .ics file within a directory.
+/// Times with a TZID are read as local time. Recurring events are expanded within the
+/// requested range, leaving out EXDATEs and instances moved by a RECURRENCE-ID.
+pub struct IcsCalendar {
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum IcsTime {
+    Date(NaiveDate),
+    DateTime(DateTime<Local>),
+}
+
+impl IcsTime {
+    fn date(&self) -> NaiveDate {
+        match self {
+            IcsTime::Date(date) => *date,
+            IcsTime::DateTime(date_time) => date_time.date_naive(),
+        }
+    }
+
+    /// The same instance of a recurring event. A date matches any time on that day
+    fn is_same_instance(&self, other: &IcsTime) -> bool {
+        match (self, other) {
+            (IcsTime::DateTime(a), IcsTime::DateTime(b)) => a == b,
+            _ => self.date() == other.date(),
+        }
+    }
+
+    fn is_after(&self, until: &IcsTime) -> bool {
+        match (self, until) {
+            (IcsTime::DateTime(a), IcsTime::DateTime(b)) => a > b,
+            _ => self.date() > until.date(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// An RRULE. Rules with parts other than these, i.e. BYMONTHDAY or BYSETPOS,
+/// aren't supported
+#[derive(Debug, Clone, PartialEq)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<IcsTime>,
+    by_day: Vec<Weekday>,
+    week_start: Weekday,
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    match value.to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Days from the week start to the weekday
+fn days_into_week(weekday: Weekday, week_start: Weekday) -> i64 {
+    (7 + weekday.num_days_from_monday() as i64 - week_start.num_days_from_monday() as i64) % 7
+}
+
+impl Recurrence {
+    fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        let mut recurrence = Recurrence {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: vec![],
+            week_start: Weekday::Mon,
+        };
+        let mut frequency = None;
+
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("{} isn't a valid RRULE part", part))?;
+            match name.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("FREQ={} isn't supported", value).into()),
+                    })
+                }
+                "INTERVAL" => recurrence.interval = value.parse::<u32>()?.max(1),
+                "COUNT" => recurrence.count = Some(value.parse()?),
+                "UNTIL" => recurrence.until = Some(parse_ics_time(value)?),
+                "WKST" => {
+                    recurrence.week_start = parse_weekday(value)
+                        .ok_or_else(|| format!("WKST={} isn't a weekday", value))?
+                }
+                "BYDAY" => {
+                    recurrence.by_day = value
+                        .split(',')
+                        .map(|day| {
+                            parse_weekday(day)
+                                .ok_or_else(|| format!("BYDAY={} isn't supported", day))
+                        })
+                        .collect::<Result<Vec<Weekday>, String>>()?
+                }
+                _ => return Err(format!("{} isn't supported", name).into()),
+            }
+        }
+
+        recurrence.frequency = frequency.ok_or("The RRULE has no FREQ")?;
+        if !recurrence.by_day.is_empty()
+            && matches!(recurrence.frequency, Frequency::Monthly | Frequency::Yearly)
+        {
+            return Err("BYDAY isn't supported for monthly or yearly events".into());
+        }
+
+        Ok(recurrence)
+    }
+
+    /// Dates the rule could fall on from start until last_day, before COUNT, UNTIL
+    /// and EXDATE are applied. Start is always the first one.
+    fn dates(&self, start: NaiveDate, last_day: NaiveDate) -> Vec<NaiveDate> {
+        let interval = self.interval as i64;
+        let mut dates = vec![start];
+
+        for period in 0_i64.. {
+            let (period_start, candidates) = match self.frequency {
+                Frequency::Daily => {
+                    let date = start + Duration::days(period * interval);
+                    let matches = self.by_day.is_empty() || self.by_day.contains(&date.weekday());
+                    (date, if matches { vec![date] } else { vec![] })
+                }
+                Frequency::Weekly => {
+                    let week = start
+                        - Duration::days(days_into_week(start.weekday(), self.week_start))
+                        + Duration::weeks(period * interval);
+                    let weekdays = if self.by_day.is_empty() {
+                        vec![start.weekday()]
+                    } else {
+                        self.by_day.clone()
+                    };
+                    let mut candidates: Vec<NaiveDate> = weekdays
+                        .iter()
+                        .map(|weekday| {
+                            week + Duration::days(days_into_week(*weekday, self.week_start))
+                        })
+                        .collect();
+                    candidates.sort();
+                    (week, candidates)
+                }
+                Frequency::Monthly => {
+                    let months = start.month0() as i64 + period * interval;
+                    let year = start.year() + (months / 12) as i32;
+                    let month = (months % 12) as u32 + 1;
+                    let period_start = match NaiveDate::from_ymd_opt(year, month, 1) {
+                        Some(period_start) => period_start,
+                        None => break,
+                    };
+                    // months without the day, i.e. the 31st, are skipped
+                    let candidates = NaiveDate::from_ymd_opt(year, month, start.day())
+                        .into_iter()
+                        .collect();
+                    (period_start, candidates)
+                }
+                Frequency::Yearly => {
+                    let year = start.year() + (period * interval) as i32;
+                    let period_start = match NaiveDate::from_ymd_opt(year, 1, 1) {
+                        Some(period_start) => period_start,
+                        None => break,
+                    };
+                    let candidates = NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                        .into_iter()
+                        .collect();
+                    (period_start, candidates)
+                }
+            };
+
+            if period_start > last_day {
+                break;
+            }
+            dates.extend(
+                candidates
+                    .into_iter()
+                    .filter(|date| date > &start && date <= &last_day),
+            );
+        }
+
+        dates
+    }
+}
+
+impl IcsCalendar {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        IcsCalendar {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    fn find_ics_files(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        if self.path.is_file() {
+            return Ok(vec![self.path.clone()]);
+        }
+
+        let mut files: Vec<PathBuf> = fs::read_dir(&self.path)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("ics"))
+            })
+            .collect();
+        files.sort();
+
+        Ok(files)
+    }
+
+    fn read_events(
+        &self,
+        file: &Path,
+        last_day: NaiveDate,
+    ) -> Result<Vec<CalendarEvent>, Box<dyn Error>> {
+        let calendar_id = file.to_string_lossy().to_string();
+        let parser = IcalParser::new(BufReader::new(fs::File::open(file)?));
+        let mut ics_events = vec![];
+        for calendar in parser {
+            ics_events.extend(calendar?.events);
+        }
+
+        // instances of a recurring event that were moved or cancelled have their own VEVENT
+        let mut moved: HashMap<&str, Vec<IcsTime>> = HashMap::new();
+        for event in &ics_events {
+            if let (Some(uid), Some(recurrence_id)) = (
+                get_property(event, "UID"),
+                get_property(event, "RECURRENCE-ID"),
+            ) {
+                moved
+                    .entry(uid)
+                    .or_default()
+                    .push(parse_ics_time(recurrence_id)?);
+            }
+        }
+
+        let mut events = vec![];
+        for event in &ics_events {
+            let moved = get_property(event, "UID")
+                .and_then(|uid| moved.get(uid))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            events.extend(events_from_ics_event(&calendar_id, event, last_day, moved)?);
+        }
+
+        Ok(events)
+    }
+}
+
+#[async_trait(?Send)]
+impl CalendarService for IcsCalendar {
+    async fn list_events(
+        &self,
+        time_min: DateTime<Utc>,
+        time_max: DateTime<Utc>,
+    ) -> Result<Vec<CalendarEvent>, Box<dyn Error>> {
+        let first_day = time_min.with_timezone(&Local).date_naive();
+        let last_day = time_max.with_timezone(&Local).date_naive();
+        let mut events = vec![];
+
+        for file in self.find_ics_files()? {
+            events.extend(
+                self.read_events(&file, last_day)?
+                    .into_iter()
+                    .filter(|event| event.date >= first_day && event.date < last_day),
+            );
+        }
+
+        Ok(events)
+    }
+}
+
+fn get_property<'a>(event: &'a IcalEvent, name: &str) -> Option<&'a str> {
+    event
+        .properties
+        .iter()
+        .find(|property| property.name.eq_ignore_ascii_case(name))
+        .and_then(|property| property.value.as_deref())
+}
+
+/// Every value of a property that can repeat and hold a list, i.e. EXDATE
+fn get_property_values<'a>(event: &'a IcalEvent, name: &'a str) -> impl Iterator<Item = &'a str> {
+    event
+        .properties
+        .iter()
+        .filter(move |property| property.name.eq_ignore_ascii_case(name))
+        .filter_map(|property| property.value.as_deref())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn parse_ics_time(value: &str) -> Result<IcsTime, Box<dyn Error>> {
+    if let Some(utc_value) = value.strip_suffix('Z') {
+        let date_time = NaiveDateTime::parse_from_str(utc_value, "%Y%m%dT%H%M%S")?;
+        return Ok(IcsTime::DateTime(
+            Utc.from_utc_datetime(&date_time).with_timezone(&Local),
+        ));
+    }
+
+    if value.contains('T') {
+        let date_time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?;
+        return Local
+            .from_local_datetime(&date_time)
+            .earliest()
+            .map(IcsTime::DateTime)
+            .ok_or_else(|| format!("{} can't be represented in local time", value).into());
+    }
+
+    Ok(IcsTime::Date(NaiveDate::parse_from_str(value, "%Y%m%d")?))
+}
+
+fn unescape_ics_text(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// The start of each instance of a recurring event up to last_day. Instances keep
+/// the time of day of DTSTART, so only its date is moved.
+fn recurrence_starts(
+    event: &IcalEvent,
+    start_value: &str,
+    recurrence: &Recurrence,
+    last_day: NaiveDate,
+) -> Result<Vec<IcsTime>, Box<dyn Error>> {
+    let start_date = NaiveDate::parse_from_str(start_value.get(..8).unwrap_or_default(), "%Y%m%d")?;
+    let mut starts = vec![];
+
+    for date in recurrence.dates(start_date, last_day) {
+        if recurrence.count.is_some_and(|count| starts.len() >= count) {
+            break;
+        }
+        let start = parse_ics_time(&format!("{}{}", date.format("%Y%m%d"), &start_value[8..]))?;
+        if recurrence
+            .until
+            .as_ref()
+            .is_some_and(|until| start.is_after(until))
+        {
+            break;
+        }
+        starts.push(start);
+    }
+
+    for value in get_property_values(event, "RDATE") {
+        // periods carry their own end, which isn't supported
+        if !value.contains('/') {
+            starts.push(parse_ics_time(value)?);
+        }
+    }
+
+    Ok(starts)
+}
+
+fn events_from_ics_event(
+    calendar_id: &str,
+    event: &IcalEvent,
+    last_day: NaiveDate,
+    moved: &[IcsTime],
+) -> Result<Vec<CalendarEvent>, Box<dyn Error>> {
+    if get_property(event, "STATUS").is_some_and(|status| status.eq_ignore_ascii_case("cancelled"))
+    {
+        return Ok(vec![]);
+    }
+
+    let start_value = match get_property(event, "DTSTART") {
+        Some(start_value) => start_value,
+        None => return Ok(vec![]),
+    };
+    let start = parse_ics_time(start_value)?;
+    let end = get_property(event, "DTEND")
+        .map(parse_ics_time)
+        .transpose()?;
+
+    let calendar_event = CalendarEvent {
+        calendar_id: calendar_id.to_string(),
+        title: unescape_ics_text(get_property(event, "SUMMARY").unwrap_or_default()),
+        attendees: event
+            .properties
+            .iter()
+            .filter(|property| property.name.eq_ignore_ascii_case("ATTENDEE"))
+            .filter_map(|property| property.value.as_deref())
+            .map(|value| {
+                let email = value.trim();
+                match email.get(..7) {
+                    Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => {
+                        email[7..].to_string()
+                    }
+                    _ => email.to_string(),
+                }
+            })
+            .collect(),
+        date: NaiveDate::MIN,
+        hours: 0.0,
+        all_day: false,
+    };
+
+    let starts = match get_property(event, "RRULE") {
+        Some(rule) => match Recurrence::parse(rule) {
+            Ok(recurrence) => recurrence_starts(event, start_value, &recurrence, last_day)?,
+            Err(err) => {
+                eprintln!(
+                    "Skipping recurring event {} in {}: {}",
+                    calendar_event.title, calendar_id, err
+                );
+                return Ok(vec![]);
+            }
+        },
+        None => vec![start],
+    };
+    let excluded = get_property_values(event, "EXDATE")
+        .map(parse_ics_time)
+        .collect::<Result<Vec<IcsTime>, Box<dyn Error>>>()?;
+
+    let mut events = vec![];
+    for instance in starts {
+        if excluded
+            .iter()
+            .chain(moved)
+            .any(|time| time.is_same_instance(&instance))
+        {
+            continue;
+        }
+
+        // every instance lasts as long as the first
+        match (instance, start, end) {
+            (IcsTime::DateTime(instance_time), IcsTime::DateTime(start_time), end) => {
+                events.push(CalendarEvent {
+                    date: instance_time.date_naive(),
+                    hours: match end {
+                        Some(IcsTime::DateTime(end_time)) => {
+                            (end_time - start_time).num_minutes() as f64 / 60.0
+                        }
+                        _ => 0.0,
+                    },
+                    ..calendar_event.clone()
+                });
+            }
+            (instance, start, end) => {
+                let end_date = match end {
+                    Some(IcsTime::Date(end_date)) => {
+                        Some(instance.date() + (end_date - start.date()))
+                    }
+                    _ => None,
+                };
+                events.extend(split_all_day_event(
+                    CalendarEvent {
+                        date: instance.date(),
+                        all_day: true,
+                        ..calendar_event.clone()
+                    },
+                    end_date,
+                ));
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    const ICS_FILE: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//autolog//tests//EN\r
+BEGIN:VEVENT\r
+UID:1\r
+SUMMARY:Alphabet planning\\, Q4\r
+ATTENDEE;CN=John Smith:mailto:john@alphabet.com\r
+DTSTART:20211020T100000Z\r
+DTEND:20211020T113000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:2\r
+SUMMARY:Holiday\r
+DTSTART;VALUE=DATE:20211025\r
+DTEND;VALUE=DATE:20211027\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:3\r
+SUMMARY:Cancelled\r
+STATUS:CANCELLED\r
+DTSTART:20211021T100000Z\r
+DTEND:20211021T110000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:4\r
+SUMMARY:Next month\r
+DTSTART;VALUE=DATE:20211115\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    const RECURRING_ICS_FILE: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//autolog//tests//EN\r
+BEGIN:VEVENT\r
+UID:standup\r
+SUMMARY:Standup\r
+DTSTART:20211004T090000Z\r
+DTEND:20211004T093000Z\r
+RRULE:FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20211027T235959Z\r
+EXDATE:20211013T090000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup\r
+SUMMARY:Standup\r
+RECURRENCE-ID:20211020T090000Z\r
+DTSTART:20211021T090000Z\r
+DTEND:20211021T100000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:retro\r
+SUMMARY:Retro\r
+DTSTART:20210920T140000Z\r
+DTEND:20210920T150000Z\r
+RRULE:FREQ=WEEKLY;INTERVAL=2;COUNT=3\r
+RDATE:20211027T140000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:remote\r
+SUMMARY:Remote day\r
+DTSTART;VALUE=DATE:20211029\r
+DTEND;VALUE=DATE:20211030\r
+RRULE:FREQ=WEEKLY;COUNT=2\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:payday\r
+SUMMARY:Payday\r
+DTSTART;VALUE=DATE:20211001\r
+RRULE:FREQ=MONTHLY;BYMONTHDAY=1,15\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[tokio::test]
+    async fn it_lists_events_from_an_ics_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("work.ics");
+        fs::File::create(&path)
+            .unwrap()
+            .write_all(ICS_FILE.as_bytes())
+            .unwrap();
+
+        let (time_min, time_max) = crate::utils::calendar::get_month_range(2021, 10).unwrap();
+        let events = IcsCalendar::new(&path)
+            .list_events(time_min, time_max)
+            .await
+            .unwrap();
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].title, "Alphabet planning, Q4".to_string());
+        assert_eq!(events[0].attendees, vec!["john@alphabet.com".to_string()]);
+        assert_eq!(events[0].hours, 1.5);
+        assert_eq!(
+            events[1].date,
+            NaiveDate::from_ymd_opt(2021, 10, 25).unwrap()
+        );
+        assert_eq!(
+            events[2].date,
+            NaiveDate::from_ymd_opt(2021, 10, 26).unwrap()
+        );
+        assert!(events[2].all_day);
+    }
+
+    #[tokio::test]
+    async fn it_lists_events_from_a_directory_of_ics_files() {
+        let dir = tempdir().unwrap();
+        for name in ["a.ics", "b.ICS", "notes.txt"] {
+            fs::File::create(dir.path().join(name))
+                .unwrap()
+                .write_all(ICS_FILE.as_bytes())
+                .unwrap();
+        }
+
+        let (time_min, time_max) = crate::utils::calendar::get_month_range(2021, 11).unwrap();
+        let events = IcsCalendar::new(dir.path())
+            .list_events(time_min, time_max)
+            .await
+            .unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].title, "Next month".to_string());
+    }
+
+    #[tokio::test]
+    async fn it_expands_recurring_events_within_the_month() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("recurring.ics");
+        fs::write(&path, RECURRING_ICS_FILE).unwrap();
+
+        let (time_min, time_max) = crate::utils::calendar::get_month_range(2021, 10).unwrap();
+        let mut events = IcsCalendar::new(&path)
+            .list_events(time_min, time_max)
+            .await
+            .unwrap();
+        events.sort_by_key(|event| (event.date, event.title.clone()));
+
+        let days = |title: &str| -> Vec<u32> {
+            events
+                .iter()
+                .filter(|event| event.title == title)
+                .map(|event| event.date.day())
+                .collect()
+        };
+        assert_eq!(days("Standup"), vec![4, 6, 11, 18, 21, 25, 27]);
+        assert_eq!(days("Retro"), vec![4, 18, 27]);
+        assert_eq!(days("Remote day"), vec![29]);
+        assert!(days("Payday").is_empty());
+
+        let moved = events.iter().find(|event| event.date.day() == 21).unwrap();
+        assert_eq!(moved.hours, 1.0);
+        assert!(events
+            .iter()
+            .filter(|event| event.title == "Standup" && event.date.day() != 21)
+            .all(|event| event.hours == 0.5));
+        assert!(events
+            .iter()
+            .any(|event| event.title == "Remote day" && event.all_day));
+    }
+
+    #[test]
+    fn it_parses_a_recurrence_rule() {
+        let recurrence = Recurrence::parse("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,FR;COUNT=4").unwrap();
+        assert_eq!(recurrence.frequency, Frequency::Weekly);
+        assert_eq!(recurrence.interval, 2);
+        assert_eq!(recurrence.by_day, vec![Weekday::Tue, Weekday::Fri]);
+        assert_eq!(recurrence.count, Some(4));
+
+        assert!(Recurrence::parse("FREQ=MONTHLY;BYDAY=1MO").is_err());
+        assert!(Recurrence::parse("FREQ=MONTHLY;BYSETPOS=-1").is_err());
+        assert!(Recurrence::parse("INTERVAL=2").is_err());
+
+        // months without the 31st are skipped
+        let monthly = Recurrence::parse("FREQ=MONTHLY").unwrap();
+        let start = NaiveDate::from_ymd_opt(2021, 8, 31).unwrap();
+        let dates = monthly.dates(start, NaiveDate::from_ymd_opt(2021, 12, 31).unwrap());
+        assert_eq!(
+            dates.iter().map(|date| date.month()).collect::<Vec<u32>>(),
+            vec![8, 10, 12]
+        );
+    }
+
+    #[test]
+    fn it_throws_an_error_for_an_invalid_ics_time() {
+        assert!(parse_ics_time("2021-10-20").is_err());
+    }
+}
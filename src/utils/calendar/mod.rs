@@ -0,0 +1,222 @@
+pub mod google_calendar;
+pub mod ics_calendar;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub calendar_id: String,
+    pub title: String,
+    pub attendees: Vec<String>,
+    pub date: NaiveDate,
+    pub hours: f64,
+    pub all_day: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarAction {
+    Hours,
+    Note,
+    Absence,
+}
+
+/// Calendar events are matched against per-client rules. A matching event contributes
+/// hours or a note to the day it starts on, against the rule's repository namespace.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct CalendarRule {
+    pub service: String,
+    pub calendar_id: Option<String>,
+    pub attendee_domain: Option<String>,
+    pub title_regex: Option<String>,
+    pub namespace: Option<String>,
+    pub path: Option<String>,
+    pub action: CalendarAction,
+}
+
+/// A source of calendar events, i.e. a linked Google calendar or a local ICS file
+#[async_trait(?Send)]
+pub trait CalendarService {
+    async fn list_events(
+        &self,
+        time_min: DateTime<Utc>,
+        time_max: DateTime<Utc>,
+    ) -> Result<Vec<CalendarEvent>, Box<dyn Error>>;
+}
+
+impl CalendarRule {
+    pub fn get_calendar_id(&self) -> &str {
+        self.calendar_id.as_deref().unwrap_or("primary")
+    }
+
+    /// An event matches when it passes every criteria set on the rule
+    pub fn matches(&self, event: &CalendarEvent) -> Result<bool, Box<dyn Error>> {
+        if let Some(domain) = &self.attendee_domain {
+            let domain = format!("@{}", domain.trim_start_matches('@').to_lowercase());
+            if !event
+                .attendees
+                .iter()
+                .any(|email| email.to_lowercase().ends_with(&domain))
+            {
+                return Ok(false);
+            }
+        }
+
+        if let Some(title_regex) = &self.title_regex {
+            if !Regex::new(title_regex)?.is_match(&event.title) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// All-day events can span several days, with an exclusive end date. Split them
+/// into an event for each day so that every day can be marked
+pub fn split_all_day_event(
+    event: CalendarEvent,
+    end_date: Option<NaiveDate>,
+) -> Vec<CalendarEvent> {
+    let end_date = end_date
+        .filter(|end_date| end_date > &event.date)
+        .unwrap_or(event.date + chrono::Duration::days(1));
+
+    event
+        .date
+        .iter_days()
+        .take_while(|date| date < &end_date)
+        .map(|date| CalendarEvent {
+            date,
+            ..event.clone()
+        })
+        .collect()
+}
+
+/// Find the first and last moments of a month in local time, as UTC
+pub fn get_month_range(
+    year: i32,
+    month: u32,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), Box<dyn Error>> {
+    let (next_year, next_month) = match month {
+        12 => (year + 1, 1),
+        _ => (year, month + 1),
+    };
+
+    let start = Local
+        .with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .earliest()
+        .ok_or("Start of month can't be represented in local time")?;
+    let end = Local
+        .with_ymd_and_hms(next_year, next_month, 1, 0, 0, 0)
+        .earliest()
+        .ok_or("End of month can't be represented in local time")?;
+
+    Ok((start.with_timezone(&Utc), end.with_timezone(&Utc)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_mock_event(title: &str, attendees: Vec<&str>) -> CalendarEvent {
+        CalendarEvent {
+            calendar_id: "primary".to_string(),
+            title: title.to_string(),
+            attendees: attendees.into_iter().map(String::from).collect(),
+            date: NaiveDate::from_ymd_opt(2021, 10, 20).unwrap(),
+            hours: 1.5,
+            all_day: false,
+        }
+    }
+
+    fn create_mock_rule() -> CalendarRule {
+        CalendarRule {
+            service: "gcal".to_string(),
+            calendar_id: None,
+            attendee_domain: None,
+            title_regex: None,
+            namespace: None,
+            path: None,
+            action: CalendarAction::Hours,
+        }
+    }
+
+    #[test]
+    fn it_defaults_to_the_primary_calendar() {
+        assert_eq!(create_mock_rule().get_calendar_id(), "primary");
+    }
+
+    #[test]
+    fn it_matches_every_event_without_criteria() {
+        let event = create_mock_event("Standup", vec![]);
+        assert!(create_mock_rule().matches(&event).unwrap());
+    }
+
+    #[test]
+    fn it_matches_events_by_attendee_domain() {
+        let rule = CalendarRule {
+            attendee_domain: Option::from("@Alphabet.com".to_string()),
+            ..create_mock_rule()
+        };
+
+        let event = create_mock_event("Standup", vec!["jim@jones.com", "john@alphabet.com"]);
+        assert!(rule.matches(&event).unwrap());
+
+        let event = create_mock_event("Standup", vec!["jim@jones.com"]);
+        assert!(!rule.matches(&event).unwrap());
+    }
+
+    #[test]
+    fn it_matches_events_by_title_regex() {
+        let rule = CalendarRule {
+            title_regex: Option::from("(?i)^alphabet".to_string()),
+            ..create_mock_rule()
+        };
+
+        assert!(rule
+            .matches(&create_mock_event("Alphabet planning", vec![]))
+            .unwrap());
+        assert!(!rule
+            .matches(&create_mock_event("Lunch with Alphabet", vec![]))
+            .unwrap());
+    }
+
+    #[test]
+    fn it_throws_an_error_for_an_invalid_title_regex() {
+        let rule = CalendarRule {
+            title_regex: Option::from("(".to_string()),
+            ..create_mock_rule()
+        };
+
+        assert!(rule.matches(&create_mock_event("Standup", vec![])).is_err());
+    }
+
+    #[test]
+    fn it_splits_an_all_day_event_into_days() {
+        let event = CalendarEvent {
+            all_day: true,
+            ..create_mock_event("Holiday", vec![])
+        };
+
+        let events = split_all_day_event(event.clone(), NaiveDate::from_ymd_opt(2021, 10, 23));
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[2].date,
+            NaiveDate::from_ymd_opt(2021, 10, 22).unwrap()
+        );
+
+        assert_eq!(split_all_day_event(event, None).len(), 1);
+    }
+
+    #[test]
+    fn it_gets_the_range_of_a_month() {
+        let (start, end) = get_month_range(2021, 12).unwrap();
+        assert!(start < end);
+        assert_eq!((end - start).num_days(), 31);
+    }
+}
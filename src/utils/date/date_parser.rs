@@ -51,6 +51,9 @@ pub fn create_single_day_object(weekend: bool, hours: f64, edited: bool) -> DayM
     ]
 }
 
+/// Keys that annotate a day entry alongside its hours
+pub const DAY_ANNOTATION_KEYS: [&str; 2] = ["note", "absence"];
+
 fn set_day_map(weekend: bool, hours: f64, edited: bool, day_map: &mut Map<String, Value>) {
     day_map.extend(create_single_day_object(weekend, hours, edited));
 }
@@ -121,6 +124,18 @@ fn parse_hours_from_date(
                         true,
                         &mut day_map,
                     );
+
+                    // notes and absences are only ever set by the user, so carry them over too
+                    for key in DAY_ANNOTATION_KEYS {
+                        if let Ok(Some(value)) = repository.get_timesheet_entry(
+                            &date_tuple.0.to_string(),
+                            &date_tuple.1,
+                            day_index,
+                            key.to_string(),
+                        ) {
+                            day_map.insert(key.to_string(), value.clone());
+                        }
+                    }
                 }
             }
         }
@@ -188,6 +203,40 @@ pub fn get_timesheet_map_from_date_hashmap(
     timesheet_years
 }
 
+/// Create an empty month of day entries, used when a month is edited before
+/// any git history exists for it
+pub fn create_month_of_days(year: i32, month: u32) -> Vec<Map<String, Value>> {
+    let date_tuple = (year, month, get_days_from_month(year, month));
+
+    (1..date_tuple.2 + 1)
+        .map(|day| {
+            let mut day_map = Map::new();
+            set_day_map(is_weekend(&date_tuple, day), 0.0, false, &mut day_map);
+            day_map
+        })
+        .collect()
+}
+
+/// Months are only generated from git history, so a month that has been edited by the user
+/// without any commits would be lost on the next sync. Carry these over from the previous timesheet.
+pub fn merge_user_edited_months(timesheet: &mut TimesheetYears, previous: &TimesheetYears) {
+    for (year, months) in previous {
+        for (month, days) in months {
+            let is_user_edited = days
+                .iter()
+                .any(|day| day.get("user_edited") == Some(&Value::Bool(true)));
+
+            if is_user_edited {
+                timesheet
+                    .entry(year.clone())
+                    .or_default()
+                    .entry(month.clone())
+                    .or_insert_with(|| days.clone());
+            }
+        }
+    }
+}
+
 pub fn get_days_from_month(year: i32, month: u32) -> u32 {
     NaiveDate::from_ymd(
         match month {
@@ -368,6 +417,70 @@ mod tests {
         )
     }
 
+    #[test]
+    fn it_carries_over_notes_and_absences_for_user_edited_days() {
+        let mut repository = crate::data::repository::Repository {
+            ..Default::default()
+        };
+
+        let mut month = create_month_of_days(2021, 10);
+        month[0].extend(create_single_day_object(false, 0.0, true));
+        month[0].insert("absence".to_string(), Value::Bool(true));
+        month[0].insert("note".to_string(), Value::String("Holiday".to_string()));
+        repository.set_timesheet(HashMap::from([(
+            "2021".to_string(),
+            HashMap::from([("10".to_string(), month)]),
+        )]));
+
+        let day_vec = parse_hours_from_date((2021, 10, 31), vec![1], &mut repository, vec![]);
+
+        assert_eq!(*day_vec[0].get("hours").unwrap(), Value::from(0.0));
+        assert_eq!(*day_vec[0].get("absence").unwrap(), Value::Bool(true));
+        assert_eq!(
+            *day_vec[0].get("note").unwrap(),
+            Value::String("Holiday".to_string())
+        );
+    }
+
+    #[test]
+    fn it_creates_an_empty_month_of_days() {
+        let month = create_month_of_days(2021, 11);
+
+        assert_eq!(month.len(), 30);
+        assert_eq!(*month[5].get("weekend").unwrap(), Value::Bool(true));
+        assert_eq!(*month[7].get("weekend").unwrap(), Value::Bool(false));
+        assert!(month
+            .iter()
+            .all(|day| day.get("hours").unwrap().as_f64() == Some(0.0)));
+    }
+
+    #[test]
+    fn it_merges_user_edited_months_missing_from_a_new_timesheet() {
+        let mut edited_month = create_month_of_days(2021, 11);
+        edited_month[2].extend(create_single_day_object(false, 4.0, true));
+
+        let previous: TimesheetYears = HashMap::from([(
+            "2021".to_string(),
+            HashMap::from([
+                ("11".to_string(), edited_month),
+                ("12".to_string(), create_month_of_days(2021, 12)),
+            ]),
+        )]);
+
+        let mut timesheet: TimesheetYears = HashMap::from([(
+            "2021".to_string(),
+            HashMap::from([("10".to_string(), create_month_of_days(2021, 10))]),
+        )]);
+
+        merge_user_edited_months(&mut timesheet, &previous);
+
+        let year = timesheet.get("2021").unwrap();
+        assert!(year.contains_key("10"));
+        assert!(year.contains_key("11"));
+        assert!(!year.contains_key("12"));
+        assert_eq!(year.get("11").unwrap()[2].get("hours").unwrap(), 4.0);
+    }
+
     #[test]
     fn it_finds_the_number_of_days_for_a_specific_month_and_year() {
         assert_eq!(get_days_from_month(2021, 10), 31);
@@ -0,0 +1,76 @@
+use crate::utils::export::template::TemplateSet;
+use crate::utils::link::link_builder::TimesheetDocument;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+pub const TEMPLATE_FILE_NAME: &str = "timesheet.html.hbs";
+
+/// Mirrors the layout of the hosted timesheet page, with all styles inline so
+/// the file can be opened offline, printed or attached to an email
+pub const DEFAULT_TEMPLATE: &str = include_str!("templates/timesheet.html.hbs");
+
+pub fn render(
+    document: &TimesheetDocument,
+    templates: &TemplateSet,
+) -> Result<String, Box<dyn Error>> {
+    templates.render(TEMPLATE_FILE_NAME, DEFAULT_TEMPLATE, document)
+}
+
+pub fn export(document: &TimesheetDocument, path: &Path) -> Result<(), Box<dyn Error>> {
+    let templates = TemplateSet::for_client(
+        document
+            .client
+            .as_ref()
+            .map(|client| client.client_name.as_str()),
+    )?;
+
+    fs::write(path, render(document, &templates)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::export::tests::create_mock_document;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_renders_a_self_contained_html_timesheet() {
+        let html = render(&create_mock_document(), &TemplateSet::default()).unwrap();
+
+        assert!(html.contains("<title>Timesheet October, 2021 - Alphabet Inc.</title>"));
+        assert!(html.contains("Planning &amp; &lt;review&gt;"));
+        assert!(html.contains("<tr class=\"weekend\"><td>2</td><td class=\"weekday\">Sat</td>"));
+        assert!(html.contains("Total hours: 12.5"));
+        assert!(!html.contains("<link"));
+        assert!(!html.contains("<script"));
+    }
+
+    #[test]
+    fn it_renders_a_localised_html_timesheet() {
+        let mut document = create_mock_document();
+        document.locale = crate::utils::locale::Locale::De;
+        let html = render(&document, &TemplateSet::default()).unwrap();
+
+        assert!(html.contains("<html lang=\"de\">"));
+        assert!(html.contains("<td class=\"weekday\">Sa</td>"));
+        assert!(html.contains("Gesamtstunden: 12,5"));
+    }
+
+    #[test]
+    fn it_renders_a_client_template() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("alphabet-inc")).unwrap();
+        fs::write(
+            dir.path().join("alphabet-inc").join(TEMPLATE_FILE_NAME),
+            "{{user.name}}: {{#each timesheets}}{{namespace}}={{total_hours}} {{/each}}",
+        )
+        .unwrap();
+
+        let templates = TemplateSet::load(dir.path(), Some("Alphabet Inc.")).unwrap();
+        let html = render(&create_mock_document(), &templates).unwrap();
+
+        assert_eq!(html, "Jim Jones: autolog=8.0 autolog=4.5 ");
+    }
+}
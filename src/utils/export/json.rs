@@ -0,0 +1,46 @@
+use crate::utils::link::link_builder::TimesheetDocument;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// The document as autolog reads it back, i.e. to sign it or verify its signatures
+pub fn export(document: &TimesheetDocument, path: &Path) -> Result<(), Box<dyn Error>> {
+    fs::write(path, serde_json::to_string_pretty(document)? + "\n")?;
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<TimesheetDocument, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+
+    serde_json::from_str(&contents).map_err(|err| {
+        format!(
+            "{} isn't a timesheet exported as json: {}",
+            path.display(),
+            err
+        )
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::export::tests::create_mock_document;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_exports_and_reads_a_json_timesheet() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timesheet.json");
+        let document = create_mock_document();
+
+        export(&document, &path).unwrap();
+        let read_document = read(&path).unwrap();
+
+        assert_eq!(read_document.month_year, document.month_year);
+        assert_eq!(read_document.creation_date, document.creation_date);
+        assert_eq!(read_document.timesheets.len(), 2);
+        assert!(read(&dir.path().join("missing.json")).is_err());
+    }
+}
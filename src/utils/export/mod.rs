@@ -0,0 +1,310 @@
+pub mod html;
+pub mod json;
+pub mod ods;
+pub mod pdf;
+pub mod template;
+pub mod xlsx;
+
+use crate::utils::link::link_builder::{Timesheet, TimesheetDocument};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Formats a month's TimesheetDocument can be exported to. The spreadsheet
+/// formats lay each namespace out on its own sheet, with a header block, one
+/// row per day and a total_hours formula. Json keeps the document as it is, so
+/// it can be signed and verified. Html and pdf are rendered with the client's templates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Xlsx,
+    Ods,
+    Html,
+    Pdf,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "xlsx" => Ok(ExportFormat::Xlsx),
+            "ods" => Ok(ExportFormat::Ods),
+            "html" => Ok(ExportFormat::Html),
+            "pdf" => Ok(ExportFormat::Pdf),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(format!(
+                "Unsupported export format: {}. Supported formats are xlsx, ods, html, pdf, json",
+                value
+            )),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Ods => "ods",
+            ExportFormat::Html => "html",
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn export(&self, document: &TimesheetDocument, path: &Path) -> Result<(), Box<dyn Error>> {
+        match self {
+            ExportFormat::Xlsx => xlsx::export(document, path),
+            ExportFormat::Ods => ods::export(document, path),
+            ExportFormat::Html => html::export(document, path),
+            ExportFormat::Pdf => pdf::export(document, path),
+            ExportFormat::Json => json::export(document, path),
+        }
+    }
+
+    /// e.g. timesheet-alphabet-october-2021.xlsx
+    pub fn default_file_name(&self, document: &TimesheetDocument) -> String {
+        let client_name = document
+            .client
+            .as_ref()
+            .map(|client| client.client_name.as_str())
+            .unwrap_or("client");
+
+        format!(
+            "timesheet-{}-{}.{}",
+            slugify(client_name),
+            slugify(&document.month_year),
+            self.extension()
+        )
+    }
+}
+
+/// The zero-based row of the Day/Hours/Note heading. Day rows follow it.
+pub(crate) const HEADING_ROW: u32 = 6;
+
+pub(crate) struct DayRow {
+    pub day: u32,
+    pub hours: f64,
+    pub weekend: bool,
+    pub absence: bool,
+    pub note: String,
+}
+
+impl DayRow {
+    /// Marks a day of absence, so it isn't read as a day without work
+    pub fn absence_label(&self, labels: &BTreeMap<String, String>) -> String {
+        if self.absence {
+            labels["absent"].clone()
+        } else {
+            String::new()
+        }
+    }
+}
+
+pub(crate) fn slugify(value: &str) -> String {
+    value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// Label/value pairs shown above the day rows of each sheet, in the document's locale
+pub(crate) fn header_rows(
+    document: &TimesheetDocument,
+    timesheet: &Timesheet,
+) -> Vec<(String, String)> {
+    let labels = document.locale.labels();
+    vec![
+        (
+            labels["client"].clone(),
+            document
+                .client
+                .as_ref()
+                .map(|client| client.client_name.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            labels["user"].clone(),
+            document
+                .user
+                .as_ref()
+                .map(|user| format!("{} <{}>", user.name, user.email))
+                .unwrap_or_default(),
+        ),
+        (
+            labels["approver"].clone(),
+            document
+                .approver
+                .as_ref()
+                .map(|approver| {
+                    format!(
+                        "{} <{}>",
+                        approver.approvers_name.clone().unwrap_or_default(),
+                        approver.approvers_email.clone().unwrap_or_default()
+                    )
+                })
+                .unwrap_or_default(),
+        ),
+        (
+            labels["project_number"].clone(),
+            timesheet.project_number.clone().unwrap_or_default(),
+        ),
+        (labels["month"].clone(), document.month_year.clone()),
+    ]
+}
+
+pub(crate) fn day_rows(timesheet: &Timesheet) -> Vec<DayRow> {
+    timesheet
+        .timesheet
+        .iter()
+        .enumerate()
+        .map(|(index, day)| DayRow {
+            day: index as u32 + 1,
+            hours: day.get("hours").and_then(|x| x.as_f64()).unwrap_or(0.0),
+            weekend: day
+                .get("weekend")
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+            absence: day
+                .get("absence")
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false),
+            note: day
+                .get("note")
+                .and_then(|x| x.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        })
+        .collect()
+}
+
+/// Sheet names are limited to 31 characters without []:*?/\ and must be unique,
+/// so namespaces are trimmed and numbered where needed
+pub(crate) fn sheet_names(document: &TimesheetDocument) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+
+    for timesheet in &document.timesheets {
+        let base: String = timesheet
+            .namespace
+            .chars()
+            .filter(|c| !"[]:*?/\\'".contains(*c))
+            .take(28)
+            .collect();
+        let base = if base.is_empty() {
+            "Timesheet".to_string()
+        } else {
+            base
+        };
+
+        let mut name = base.clone();
+        let mut count = 2;
+        while names.iter().any(|x| x.eq_ignore_ascii_case(&name)) {
+            name = format!("{} {}", base, count);
+            count += 1;
+        }
+        names.push(name);
+    }
+
+    names
+}
+
+/// The SUM range of hours for a sheet in A1 notation, e.g. B8:B38
+pub(crate) fn hours_range(days: usize) -> String {
+    let first_row = HEADING_ROW + 2;
+    let last_row = HEADING_ROW + 1 + (days.max(1) as u32);
+    format!("B{}:B{}", first_row, last_row)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::data::client_repositories::{Approver, Client, User};
+    use chrono::Utc;
+    use serde_json::{json, Map, Value};
+
+    pub fn create_mock_document() -> TimesheetDocument {
+        let day = |hours: f64, weekend: bool, note: &str| -> Map<String, Value> {
+            json!({"hours": hours, "weekend": weekend, "user_edited": false, "note": note})
+                .as_object()
+                .unwrap()
+                .clone()
+        };
+
+        TimesheetDocument {
+            creation_date: Utc::now(),
+            random_path: "".to_string(),
+            month_year: "October, 2021".to_string(),
+            client: Some(Client {
+                id: "id".to_string(),
+                client_name: "Alphabet Inc.".to_string(),
+                client_address: "Mountain View".to_string(),
+                client_contact_person: "John Smith".to_string(),
+            }),
+            user: Some(User {
+                id: "id".to_string(),
+                name: "Jim Jones".to_string(),
+                email: "jim@jones.com".to_string(),
+                is_alias: false,
+                thumbnail: None,
+            }),
+            approver: Some(Approver {
+                approvers_name: Some("Jane Jones".to_string()),
+                approvers_email: Some("jane@jones.com".to_string()),
+            }),
+            locale: Default::default(),
+            first_day: chrono::NaiveDate::from_ymd_opt(2021, 10, 1),
+            signatures: vec![],
+            timesheets: vec![
+                Timesheet {
+                    namespace: "autolog".to_string(),
+                    timesheet: vec![day(8.0, false, "Planning & <review>"), day(0.0, true, "")],
+                    total_hours: 8.0,
+                    project_number: Some("PX-1".to_string()),
+                },
+                Timesheet {
+                    namespace: "autolog".to_string(),
+                    timesheet: vec![day(4.5, false, "")],
+                    total_hours: 4.5,
+                    project_number: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn it_parses_an_export_format() {
+        assert_eq!("XLSX".parse::<ExportFormat>(), Ok(ExportFormat::Xlsx));
+        assert_eq!("ods".parse::<ExportFormat>(), Ok(ExportFormat::Ods));
+        assert_eq!("pdf".parse::<ExportFormat>(), Ok(ExportFormat::Pdf));
+        assert!("docx".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn it_builds_a_default_file_name() {
+        let document = create_mock_document();
+        assert_eq!(
+            ExportFormat::Ods.default_file_name(&document),
+            "timesheet-alphabet-inc-october-2021.ods"
+        );
+    }
+
+    #[test]
+    fn it_builds_unique_sheet_names() {
+        let document = create_mock_document();
+        assert_eq!(sheet_names(&document), vec!["autolog", "autolog 2"]);
+    }
+
+    #[test]
+    fn it_builds_day_rows_and_an_hours_range() {
+        let document = create_mock_document();
+        let rows = day_rows(&document.timesheets[0]);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].day, 2);
+        assert!(rows[1].weekend);
+        assert_eq!(hours_range(rows.len()), "B8:B9");
+    }
+}
@@ -0,0 +1,181 @@
+use crate::utils::export::{day_rows, header_rows, hours_range, sheet_names, HEADING_ROW};
+use crate::utils::link::link_builder::TimesheetDocument;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const MIMETYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+
+const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+ <manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.spreadsheet"/>
+ <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+const CONTENT_HEADER: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:of="urn:oasis:names:tc:opendocument:xmlns:of:1.2" office:version="1.2">
+<office:automatic-styles>
+<style:style style:name="bold" style:family="table-cell"><style:text-properties fo:font-weight="bold"/></style:style>
+<style:style style:name="weekend" style:family="table-cell"><style:table-cell-properties fo:background-color="#d9d9d9"/></style:style>
+</office:automatic-styles>
+<office:body>
+<office:spreadsheet>
+"##;
+
+const CONTENT_FOOTER: &str = "</office:spreadsheet>\n</office:body>\n</office:document-content>\n";
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn style_attribute(style: Option<&str>) -> String {
+    style
+        .map(|name| format!(" table:style-name=\"{}\"", name))
+        .unwrap_or_default()
+}
+
+fn string_cell(value: &str, style: Option<&str>) -> String {
+    format!(
+        "<table:table-cell office:value-type=\"string\"{}><text:p>{}</text:p></table:table-cell>",
+        style_attribute(style),
+        escape_xml(value)
+    )
+}
+
+fn float_cell(value: f64, style: Option<&str>) -> String {
+    format!(
+        "<table:table-cell office:value-type=\"float\" office:value=\"{}\"{}><text:p>{}</text:p></table:table-cell>",
+        value,
+        style_attribute(style),
+        value
+    )
+}
+
+fn row(cells: &[String]) -> String {
+    format!("<table:table-row>{}</table:table-row>\n", cells.concat())
+}
+
+/// Builds content.xml, with each namespace in its own table
+pub fn build_content(document: &TimesheetDocument) -> String {
+    let mut content = String::from(CONTENT_HEADER);
+    let labels = document.locale.labels();
+
+    for (timesheet, name) in document.timesheets.iter().zip(sheet_names(document)) {
+        content.push_str(&format!(
+            "<table:table table:name=\"{}\">\n",
+            escape_xml(&name)
+        ));
+
+        let header = header_rows(document, timesheet);
+        for (label, value) in &header {
+            content.push_str(&row(&[
+                string_cell(label, Some("bold")),
+                string_cell(value, None),
+            ]));
+        }
+        for _ in header.len() as u32..HEADING_ROW {
+            content.push_str(&row(&["<table:table-cell/>".to_string()]));
+        }
+
+        content.push_str(&row(&[
+            string_cell(&labels["day"], Some("bold")),
+            string_cell(&labels["hours"], Some("bold")),
+            string_cell(&labels["note"], Some("bold")),
+            string_cell(&labels["absence"], Some("bold")),
+        ]));
+
+        let days = day_rows(timesheet);
+        for day in &days {
+            let style = if day.weekend { Some("weekend") } else { None };
+            content.push_str(&row(&[
+                float_cell(day.day as f64, style),
+                float_cell(day.hours, style),
+                string_cell(&day.note, style),
+                string_cell(&day.absence_label(&labels), style),
+            ]));
+        }
+
+        let range = hours_range(days.len()).replace(':', ":.");
+        content.push_str(&row(&[
+            string_cell(&labels["total_hours"], Some("bold")),
+            format!(
+                "<table:table-cell table:style-name=\"bold\" table:formula=\"of:=SUM([.{}])\" office:value-type=\"float\" office:value=\"{}\"><text:p>{}</text:p></table:table-cell>",
+                range, timesheet.total_hours, timesheet.total_hours
+            ),
+        ]));
+
+        content.push_str("</table:table>\n");
+    }
+
+    content.push_str(CONTENT_FOOTER);
+    content
+}
+
+pub fn export(document: &TimesheetDocument, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+
+    // the mimetype has to be the first entry, and stored uncompressed
+    zip.start_file(
+        "mimetype",
+        SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    zip.write_all(MIMETYPE.as_bytes())?;
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("META-INF/manifest.xml", options)?;
+    zip.write_all(MANIFEST.as_bytes())?;
+    zip.start_file("content.xml", options)?;
+    zip.write_all(build_content(document).as_bytes())?;
+
+    zip.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::export::tests::create_mock_document;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_builds_content_with_a_table_per_namespace() {
+        let mut document = create_mock_document();
+        document.timesheets[1].timesheet[0].insert("absence".to_string(), true.into());
+        let content = build_content(&document);
+
+        assert!(content.contains("<table:table table:name=\"autolog\">"));
+        assert!(content.contains("<table:table table:name=\"autolog 2\">"));
+        assert!(content.contains("table:formula=\"of:=SUM([.B8:.B9])\""));
+        assert!(content.contains("table:style-name=\"weekend\""));
+        assert!(content.contains("Planning &amp; &lt;review&gt;"));
+        assert!(content.contains("<text:p>Absence</text:p>"));
+        assert!(content.contains("<text:p>Absent</text:p>"));
+    }
+
+    #[test]
+    fn it_exports_an_ods_with_the_mimetype_first() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timesheet.ods");
+        export(&create_mock_document(), &path).unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut mimetype = String::new();
+        archive
+            .by_index(0)
+            .unwrap()
+            .read_to_string(&mut mimetype)
+            .unwrap();
+
+        assert_eq!(mimetype, MIMETYPE);
+        assert!(archive.by_name("content.xml").is_ok());
+    }
+}
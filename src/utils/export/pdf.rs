@@ -0,0 +1,538 @@
+use crate::utils::export::template::TemplateSet;
+use crate::utils::link::link_builder::TimesheetDocument;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::Value;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 50.0;
+const ROW_HEIGHT: f64 = 14.0;
+const NOTE_CHARACTERS: usize = 58;
+
+/// x positions of the day, weekday, hours, note and absence columns
+const COLUMNS: [f64; 5] = [MARGIN, 72.0, 110.0, 160.0, 470.0];
+
+/// Fonts are the standard Helvetica ones every reader has, so nothing is embedded
+const FONTS: [&str; 2] = ["Helvetica", "Helvetica-Bold"];
+
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+/// A JPEG logo, which PDF can hold as it is. Other logo formats are left out of PDFs
+struct Logo {
+    data: Vec<u8>,
+    width: u16,
+    height: u16,
+    components: u8,
+}
+
+impl Logo {
+    fn from_data_uri(uri: &str) -> Option<Logo> {
+        let data = STANDARD
+            .decode(uri.strip_prefix("data:image/jpeg;base64,")?)
+            .ok()?;
+
+        // the size is in the first start of frame segment
+        let mut index = 2;
+        while index + 9 < data.len() && data[index] == 0xFF {
+            let marker = data[index + 1];
+            if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+                return Some(Logo {
+                    height: u16::from_be_bytes([data[index + 5], data[index + 6]]),
+                    width: u16::from_be_bytes([data[index + 7], data[index + 8]]),
+                    components: data[index + 9],
+                    data,
+                });
+            }
+            index += 2 + u16::from_be_bytes([data[index + 2], data[index + 3]]) as usize;
+        }
+        None
+    }
+}
+
+/// Lays out pages top to bottom, starting a new page when one is full
+struct Layout {
+    pages: Vec<String>,
+    y: f64,
+    accent: (f64, f64, f64),
+}
+
+impl Layout {
+    fn new(accent: (f64, f64, f64)) -> Self {
+        Layout {
+            pages: vec![String::new()],
+            y: PAGE_HEIGHT - MARGIN,
+            accent,
+        }
+    }
+
+    fn page(&mut self) -> &mut String {
+        self.pages.last_mut().unwrap()
+    }
+
+    /// Moves down a line of the given height, on a new page if it doesn't fit
+    fn line(&mut self, height: f64) -> f64 {
+        if self.y - height < MARGIN {
+            self.pages.push(String::new());
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+        self.y -= height;
+        self.y
+    }
+
+    fn text(
+        &mut self,
+        x: f64,
+        y: f64,
+        font: Font,
+        size: f64,
+        colour: (f64, f64, f64),
+        value: &str,
+    ) {
+        let font = match font {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        };
+        let text = format!(
+            "{} {} {} rg BT /{} {} Tf {} {} Td ({}) Tj ET\n",
+            colour.0,
+            colour.1,
+            colour.2,
+            font,
+            size,
+            x,
+            y,
+            escape_text(value)
+        );
+        self.page().push_str(&text);
+    }
+
+    fn rectangle(&mut self, x: f64, y: f64, width: f64, height: f64, colour: (f64, f64, f64)) {
+        let rectangle = format!(
+            "{} {} {} rg {} {} {} {} re f\n",
+            colour.0, colour.1, colour.2, x, y, width, height
+        );
+        self.page().push_str(&rectangle);
+    }
+
+    fn row(&mut self, cells: &[&str], font: Font, colour: (f64, f64, f64), shaded: bool) {
+        let y = self.line(ROW_HEIGHT);
+        if shaded {
+            self.rectangle(
+                MARGIN,
+                y - 4.0,
+                PAGE_WIDTH - 2.0 * MARGIN,
+                ROW_HEIGHT,
+                self.accent,
+            );
+        }
+        for (x, cell) in COLUMNS.iter().zip(cells) {
+            self.text(*x, y, font, 9.0, colour, cell);
+        }
+    }
+}
+
+const BLACK: (f64, f64, f64) = (0.0, 0.0, 0.0);
+const GREY: (f64, f64, f64) = (0.38, 0.43, 0.49);
+
+/// Reads a #rrggbb colour as PDF's 0 to 1 components. Other CSS colours fall back to black
+fn rgb(colour: &str) -> (f64, f64, f64) {
+    let hex = colour.trim_start_matches('#');
+    let component = |index: usize| {
+        hex.get(index..index + 2)
+            .and_then(|value| u8::from_str_radix(value, 16).ok())
+            .map(|value| (value as f64 / 255.0 * 1000.0).round() / 1000.0)
+    };
+
+    match (hex.len(), component(0), component(2), component(4)) {
+        (6, Some(red), Some(green), Some(blue)) => (red, green, blue),
+        _ => BLACK,
+    }
+}
+
+/// Encodes text as WinAnsi, the encoding of the standard fonts, replacing characters
+/// it doesn't have with ?, and escapes the delimiters of a PDF string
+fn escape_text(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            ' '..='~' => c.to_string(),
+            '\u{a0}'..='\u{ff}' => format!("\\{:03o}", c as u32),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
+
+fn truncate(value: &str, characters: usize) -> String {
+    if value.chars().count() <= characters {
+        value.to_string()
+    } else {
+        format!(
+            "{}...",
+            value.chars().take(characters - 3).collect::<String>()
+        )
+    }
+}
+
+fn field<'a>(value: &'a Value, pointer: &str) -> &'a str {
+    value.pointer(pointer).and_then(Value::as_str).unwrap_or("")
+}
+
+/// Writes the objects of a PDF with its cross-reference table. Object n is objects[n - 1]
+fn write_objects(objects: &[Vec<u8>]) -> Vec<u8> {
+    let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+    let mut offsets = vec![];
+
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+fn stream(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let mut object = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+    object.extend(data);
+    object.extend(b"\nendstream");
+    object
+}
+
+/// Renders the document from the same context as the html template, so a client's
+/// labels, colours and JPEG logo apply to PDFs too
+pub fn render(
+    document: &TimesheetDocument,
+    templates: &TemplateSet,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let context = templates.build_context(document)?;
+    let label = |key: &str| field(&context, &format!("/branding/labels/{}", key)).to_string();
+    let primary = rgb(field(&context, "/branding/colours/primary"));
+    let mut layout = Layout::new(rgb(field(&context, "/branding/colours/accent")));
+
+    let logo = context
+        .pointer("/branding/logo")
+        .and_then(Value::as_str)
+        .and_then(Logo::from_data_uri);
+    let title_y = layout.line(24.0);
+    layout.text(
+        MARGIN,
+        title_y,
+        Font::Bold,
+        18.0,
+        primary,
+        &format!("{} {}", label("title"), field(&context, "/month_year")),
+    );
+    if let Some(logo) = logo.as_ref() {
+        let height = 48.0;
+        let width = height * logo.width as f64 / logo.height.max(1) as f64;
+        let image = format!(
+            "q {} 0 0 {} {} {} cm /Logo Do Q\n",
+            width,
+            height,
+            PAGE_WIDTH - MARGIN - width,
+            title_y - 12.0
+        );
+        layout.page().push_str(&image);
+    }
+    let rule_y = layout.line(10.0);
+    layout.rectangle(MARGIN, rule_y, PAGE_WIDTH - 2.0 * MARGIN, 2.0, primary);
+    layout.line(6.0);
+
+    let mut header = vec![];
+    if context["client"].is_object() {
+        header.push((
+            label("client"),
+            field(&context, "/client/client_name").to_string(),
+        ));
+        header.push((
+            label("client_contact_person"),
+            field(&context, "/client/client_contact_person").to_string(),
+        ));
+        header.push((
+            label("client_address"),
+            field(&context, "/client/client_address").to_string(),
+        ));
+    }
+    if context["user"].is_object() {
+        header.push((
+            label("user"),
+            format!(
+                "{} <{}>",
+                field(&context, "/user/name"),
+                field(&context, "/user/email")
+            ),
+        ));
+    }
+    if context["approver"].is_object() {
+        header.push((
+            label("approver"),
+            format!(
+                "{} <{}>",
+                field(&context, "/approver/approvers_name"),
+                field(&context, "/approver/approvers_email")
+            ),
+        ));
+    }
+    for (name, value) in &header {
+        let y = layout.line(ROW_HEIGHT);
+        layout.text(MARGIN, y, Font::Regular, 10.0, GREY, name);
+        layout.text(180.0, y, Font::Regular, 10.0, BLACK, value);
+    }
+
+    let empty = vec![];
+    for timesheet in context["timesheets"].as_array().unwrap_or(&empty) {
+        layout.line(12.0);
+        let y = layout.line(18.0);
+        let project_number = field(timesheet, "/project_number");
+        let heading = if project_number.is_empty() {
+            field(timesheet, "/namespace").to_string()
+        } else {
+            format!(
+                "{} \u{b7} {}",
+                field(timesheet, "/namespace"),
+                project_number
+            )
+        };
+        layout.text(MARGIN, y, Font::Bold, 13.0, primary, &heading);
+
+        layout.row(
+            &[
+                &label("day"),
+                "",
+                &label("hours"),
+                &label("note"),
+                &label("absence"),
+            ],
+            Font::Bold,
+            GREY,
+            false,
+        );
+        for day in timesheet["days"].as_array().unwrap_or(&empty) {
+            let absence = if day["absence"].as_bool().unwrap_or(false) {
+                label("absent")
+            } else {
+                String::new()
+            };
+            layout.row(
+                &[
+                    &day["day"].to_string(),
+                    field(day, "/weekday"),
+                    field(day, "/hours_display"),
+                    &truncate(field(day, "/note"), NOTE_CHARACTERS),
+                    &absence,
+                ],
+                Font::Regular,
+                BLACK,
+                day["weekend"].as_bool().unwrap_or(false),
+            );
+        }
+        layout.row(
+            &[
+                &label("total"),
+                "",
+                field(timesheet, "/total_hours_display"),
+                &format!("{} {}", timesheet["days_worked"], label("days_worked")),
+            ],
+            Font::Bold,
+            BLACK,
+            false,
+        );
+    }
+
+    layout.line(12.0);
+    let y = layout.line(16.0);
+    layout.text(
+        MARGIN,
+        y,
+        Font::Bold,
+        12.0,
+        BLACK,
+        &format!(
+            "{}: {}",
+            label("total_hours"),
+            field(&context, "/total_hours_display")
+        ),
+    );
+
+    // catalog, page tree, fonts and logo come first, then each page and its contents
+    let first_page = 4 + FONTS.len() - 1 + logo.is_some() as usize;
+    let page_ids: Vec<usize> = (0..layout.pages.len())
+        .map(|index| first_page + index * 2)
+        .collect();
+    let mut objects = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{} 0 R", id))
+                .collect::<Vec<String>>()
+                .join(" "),
+            page_ids.len()
+        )
+        .into_bytes(),
+    ];
+    for font in FONTS {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font
+            )
+            .into_bytes(),
+        );
+    }
+    let mut resources = "/Font << /F1 3 0 R /F2 4 0 R >>".to_string();
+    if let Some(logo) = logo.as_ref() {
+        let colour_space = match logo.components {
+            1 => "/DeviceGray",
+            4 => "/DeviceCMYK",
+            _ => "/DeviceRGB",
+        };
+        objects.push(stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {} /BitsPerComponent 8 /Filter /DCTDecode",
+                logo.width, logo.height, colour_space
+            ),
+            &logo.data,
+        ));
+        resources.push_str(&format!(" /XObject << /Logo {} 0 R >>", objects.len()));
+    }
+    for (page, id) in layout.pages.iter().zip(&page_ids) {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << {} >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                resources,
+                id + 1
+            )
+            .into_bytes(),
+        );
+        objects.push(stream("", page.as_bytes()));
+    }
+
+    Ok(write_objects(&objects))
+}
+
+pub fn export(document: &TimesheetDocument, path: &Path) -> Result<(), Box<dyn Error>> {
+    let templates = TemplateSet::for_client(
+        document
+            .client
+            .as_ref()
+            .map(|client| client.client_name.as_str()),
+    )?;
+
+    fs::write(path, render(document, &templates)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::export::tests::create_mock_document;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn text(pdf: &[u8]) -> String {
+        String::from_utf8_lossy(pdf).to_string()
+    }
+
+    #[test]
+    fn it_renders_a_pdf_timesheet() {
+        let mut document = create_mock_document();
+        document.timesheets[1].timesheet[0].insert("absence".to_string(), true.into());
+        let pdf = render(&document, &TemplateSet::default()).unwrap();
+        let content = text(&pdf);
+
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(content.contains("(Timesheet October, 2021) Tj"));
+        assert!(content.contains("(Planning & <review>) Tj"));
+        assert!(content.contains("(Jim Jones <jim@jones.com>) Tj"));
+        assert!(content.contains("(Absent) Tj"));
+        assert!(content.contains("(Total hours: 12.5) Tj"));
+        assert!(content.contains("/Count 1"));
+
+        // startxref points at the cross-reference table
+        let start = content.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let offset: usize = content[start..].lines().next().unwrap().parse().unwrap();
+        assert!(pdf[offset..].starts_with(b"xref\n0 7\n"));
+    }
+
+    #[test]
+    fn it_renders_a_pdf_with_a_client_template() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("template.json"),
+            r##"{"colours": {"primary": "#ff0000"}, "labels": {"hours": "Std. (gesamt)"}}"##,
+        )
+        .unwrap();
+        let mut document = create_mock_document();
+        document.locale = crate::utils::locale::Locale::De;
+
+        let templates = TemplateSet::load(dir.path(), None).unwrap();
+        let content = text(&render(&document, &templates).unwrap());
+
+        assert!(content.contains("1 0 0 rg BT /F2 18 Tf"));
+        assert!(content.contains("(Std. \\(gesamt\\)) Tj"));
+        assert!(content.contains("(Gesamtstunden: 12,5) Tj"));
+    }
+
+    #[test]
+    fn it_starts_a_new_page_when_one_is_full() {
+        let mut document = create_mock_document();
+        let day = json!({"hours": 8.0, "weekend": false, "note": ""});
+        document.timesheets[0].timesheet = vec![day.as_object().unwrap().clone(); 31];
+        document.timesheets[1].timesheet = vec![day.as_object().unwrap().clone(); 31];
+
+        let content = text(&render(&document, &TemplateSet::default()).unwrap());
+
+        assert!(content.contains("/Count 2"));
+        assert!(content.contains("/Kids [5 0 R 7 0 R]"));
+    }
+
+    #[test]
+    fn it_embeds_a_jpeg_logo() {
+        // the start of a 2x1 rgb jpeg, enough for its size to be read
+        let jpeg = [
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00,
+            0x01, 0x00, 0x02, 0x03, 0x01, 0x11, 0x00, 0xFF, 0xD9,
+        ];
+        let logo =
+            Logo::from_data_uri(&format!("data:image/jpeg;base64,{}", STANDARD.encode(jpeg)))
+                .unwrap();
+
+        assert_eq!((logo.width, logo.height, logo.components), (2, 1, 3));
+        assert!(Logo::from_data_uri("data:image/png;base64,iVBORw0KGgo=").is_none());
+    }
+
+    #[test]
+    fn it_encodes_text_for_the_standard_fonts() {
+        assert_eq!(escape_text("Kühn (Büro)"), "K\\374hn \\(B\\374ro\\)");
+        assert_eq!(escape_text("\u{2014}"), "?");
+        assert_eq!(rgb("#1f2933"), (0.122, 0.161, 0.2));
+        assert_eq!(rgb("red"), BLACK);
+    }
+}
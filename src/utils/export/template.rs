@@ -0,0 +1,357 @@
+use crate::utils::export::{day_rows, slugify};
+use crate::utils::file::file_reader;
+use crate::utils::link::link_builder::TimesheetDocument;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Datelike;
+use handlebars::Handlebars;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const SETTINGS_FILE_NAME: &str = "template.json";
+
+/// Branding read from template.json in a client's template directory
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TemplateSettings {
+    /// A path relative to the template directory or a data URI. Remote logos aren't
+    /// supported, as rendered documents have to work offline
+    pub logo: Option<String>,
+    #[serde(default)]
+    pub colours: Colours,
+    /// Overrides of the labels of the client's locale, e.g. "hours": "Std."
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Colours {
+    pub primary: String,
+    pub accent: String,
+}
+
+impl Default for Colours {
+    fn default() -> Self {
+        Colours {
+            primary: "#1f2933".to_string(),
+            accent: "#f0f2f5".to_string(),
+        }
+    }
+}
+
+/// Templates for a single client. A file in ~/.config/autolog/templates/<client>/
+/// is used first, then one in ~/.config/autolog/templates/, then the built in default.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateSet {
+    dirs: Vec<PathBuf>,
+    client_dir: Option<PathBuf>,
+    pub settings: TemplateSettings,
+}
+
+impl TemplateSet {
+    pub fn for_client(client_name: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        Self::load(&file_reader::get_templates_dir(), client_name)
+    }
+
+    pub fn load(templates_dir: &Path, client_name: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let client_dir = client_name.map(|name| templates_dir.join(slugify(name)));
+        let mut dirs = vec![];
+        if let Some(dir) = client_dir.as_ref() {
+            dirs.push(dir.clone());
+        }
+        dirs.push(templates_dir.to_path_buf());
+
+        let mut settings = TemplateSettings::default();
+        for dir in dirs.iter().rev() {
+            let path = dir.join(SETTINGS_FILE_NAME);
+            if path.is_file() {
+                let found: TemplateSettings = serde_json::from_str(&fs::read_to_string(&path)?)
+                    .map_err(|err| format!("Error reading {}: {}", path.display(), err))?;
+                settings.logo = found.logo.or(settings.logo);
+                settings.colours = found.colours;
+                settings.labels.extend(found.labels);
+                settings.language = found.language.or(settings.language);
+            }
+        }
+
+        Ok(TemplateSet {
+            dirs,
+            client_dir,
+            settings,
+        })
+    }
+
+    /// The contents of the first overriding template, or the default
+    pub fn template(&self, file_name: &str, default: &str) -> Result<String, Box<dyn Error>> {
+        for dir in &self.dirs {
+            let path = dir.join(file_name);
+            if path.is_file() {
+                return Ok(fs::read_to_string(path)?);
+            }
+        }
+        Ok(default.to_string())
+    }
+
+    /// Logos on disk are inlined so rendered documents stay self-contained
+    fn logo_source(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let logo = match self.settings.logo.as_ref() {
+            Some(logo) => logo,
+            None => return Ok(None),
+        };
+        if logo.starts_with("data:") {
+            return Ok(Some(logo.clone()));
+        }
+        if logo.starts_with("http://") || logo.starts_with("https://") {
+            return Err(format!(
+                "Logo {} is a url, which would be fetched whenever the timesheet is opened. \
+                Save it next to {} and use its file name instead",
+                logo, SETTINGS_FILE_NAME
+            )
+            .into());
+        }
+
+        let path = self
+            .dirs
+            .iter()
+            .map(|dir| dir.join(logo))
+            .find(|path| path.is_file())
+            .ok_or_else(|| format!("Logo {} not found in {:?}", logo, self.client_dir))?;
+        let mime_type = match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase())
+            .as_deref()
+        {
+            Some("svg") => "image/svg+xml",
+            Some("jpg") | Some("jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            _ => "image/png",
+        };
+
+        Ok(Some(format!(
+            "data:{};base64,{}",
+            mime_type,
+            STANDARD.encode(fs::read(path)?)
+        )))
+    }
+
+    /// The data every template renders: each field of the document as it is sent
+    /// to the hosted page, the days of each timesheet, totals and the branding.
+    /// Fields ending in _display are formatted for the document's locale.
+    pub fn build_context(&self, document: &TimesheetDocument) -> Result<Value, Box<dyn Error>> {
+        let mut context = serde_json::to_value(document)?;
+        let locale = document.locale;
+
+        let mut days_worked_total = 0;
+        let timesheets: Vec<Value> = document
+            .timesheets
+            .iter()
+            .map(|timesheet| {
+                let days = day_rows(timesheet);
+                let days_worked = days.iter().filter(|day| day.hours > 0.0).count();
+                days_worked_total += days_worked;
+                json!({
+                    "namespace": timesheet.namespace,
+                    "project_number": timesheet.project_number,
+                    "timesheet": timesheet.timesheet,
+                    "total_hours": timesheet.total_hours,
+                    "total_hours_display": locale.format_number(timesheet.total_hours),
+                    "days_worked": days_worked,
+                    "days": days
+                        .iter()
+                        .map(|day| json!({
+                            "day": day.day,
+                            "hours": day.hours,
+                            "hours_display": locale.format_number(day.hours),
+                            "weekday": document
+                                .first_day
+                                .and_then(|first_day| first_day.with_day(day.day))
+                                .map(|date| locale.weekday_name(date.weekday())),
+                            "weekend": day.weekend,
+                            "absence": day.absence,
+                            "note": day.note,
+                        }))
+                        .collect::<Vec<Value>>(),
+                })
+            })
+            .collect();
+
+        let mut labels = locale.labels();
+        labels.extend(self.settings.labels.clone());
+        let total_hours = document
+            .timesheets
+            .iter()
+            .map(|timesheet| timesheet.total_hours)
+            .sum::<f64>();
+
+        context["timesheets"] = Value::from(timesheets);
+        context["total_hours"] = Value::from(total_hours);
+        context["total_hours_display"] = Value::from(locale.format_number(total_hours));
+        context["locale"] = Value::from(locale.code());
+        context["days_worked"] = Value::from(days_worked_total);
+        context["branding"] = json!({
+            "logo": self.logo_source()?,
+            "colours": self.settings.colours,
+            "labels": labels,
+            "language": self
+                .settings
+                .language
+                .clone()
+                .unwrap_or_else(|| locale.code().to_string()),
+        });
+
+        Ok(context)
+    }
+
+    pub fn render(
+        &self,
+        file_name: &str,
+        default: &str,
+        document: &TimesheetDocument,
+    ) -> Result<String, Box<dyn Error>> {
+        let template = self.template(file_name, default)?;
+        let handlebars = Handlebars::new();
+        Ok(handlebars.render_template(&template, &self.build_context(document)?)?)
+    }
+
+    /// Render plain text, i.e. an email, without escaping html. Values in extra are
+    /// added to the document's context
+    pub fn render_text(
+        &self,
+        file_name: &str,
+        default: &str,
+        document: &TimesheetDocument,
+        extra: Value,
+    ) -> Result<String, Box<dyn Error>> {
+        let template = self.template(file_name, default)?;
+        let mut context = self.build_context(document)?;
+        if let (Value::Object(context), Value::Object(extra)) = (&mut context, extra) {
+            context.extend(extra);
+        }
+
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        Ok(handlebars.render_template(&template, &context)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::export::tests::create_mock_document;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_prefers_a_client_template_over_a_shared_one() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("alphabet-inc")).unwrap();
+        fs::write(dir.path().join("shared.hbs"), "shared").unwrap();
+        fs::write(dir.path().join("alphabet-inc/client.hbs"), "client").unwrap();
+        fs::write(dir.path().join("client.hbs"), "not used").unwrap();
+
+        let templates = TemplateSet::load(dir.path(), Some("Alphabet Inc.")).unwrap();
+
+        assert_eq!(
+            templates.template("client.hbs", "default").unwrap(),
+            "client"
+        );
+        assert_eq!(
+            templates.template("shared.hbs", "default").unwrap(),
+            "shared"
+        );
+        assert_eq!(
+            templates.template("none.hbs", "default").unwrap(),
+            "default"
+        );
+    }
+
+    #[test]
+    fn it_merges_client_settings_over_shared_settings() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("alphabet-inc")).unwrap();
+        fs::write(
+            dir.path().join(SETTINGS_FILE_NAME),
+            r#"{"logo": "data:image/png;base64,iVBORw0K", "labels": {"hours": "Hrs", "note": "Notes"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("alphabet-inc").join(SETTINGS_FILE_NAME),
+            r##"{"colours": {"primary": "#ff0000"}, "labels": {"hours": "Stunden"}, "language": "de"}"##,
+        )
+        .unwrap();
+
+        let templates = TemplateSet::load(dir.path(), Some("Alphabet Inc.")).unwrap();
+        let context = templates.build_context(&create_mock_document()).unwrap();
+
+        assert_eq!(
+            context["branding"]["logo"],
+            "data:image/png;base64,iVBORw0K"
+        );
+        assert_eq!(context["branding"]["colours"]["primary"], "#ff0000");
+        assert_eq!(context["branding"]["colours"]["accent"], "#f0f2f5");
+        assert_eq!(context["branding"]["labels"]["hours"], "Stunden");
+        assert_eq!(context["branding"]["labels"]["note"], "Notes");
+        assert_eq!(context["branding"]["labels"]["day"], "Day");
+        assert_eq!(context["branding"]["language"], "de");
+    }
+
+    #[test]
+    fn it_inlines_a_logo_and_exposes_every_document_field() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("logo.svg"), "<svg/>").unwrap();
+        fs::write(
+            dir.path().join(SETTINGS_FILE_NAME),
+            r#"{"logo": "logo.svg"}"#,
+        )
+        .unwrap();
+
+        let templates = TemplateSet::load(dir.path(), None).unwrap();
+        let context = templates.build_context(&create_mock_document()).unwrap();
+
+        assert_eq!(
+            context["branding"]["logo"],
+            "data:image/svg+xml;base64,PHN2Zy8+"
+        );
+        assert_eq!(context["client"]["client_address"], "Mountain View");
+        assert_eq!(context["user"]["is_alias"], false);
+        assert_eq!(context["approver"]["approvers_email"], "jane@jones.com");
+        assert_eq!(context["timesheets"][0]["timesheet"][0]["hours"], 8.0);
+        assert_eq!(context["total_hours"], 12.5);
+        assert_eq!(context["days_worked"], 2);
+    }
+
+    #[test]
+    fn it_throws_an_error_for_a_missing_logo() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(SETTINGS_FILE_NAME),
+            r#"{"logo": "logo.png"}"#,
+        )
+        .unwrap();
+
+        let templates = TemplateSet::load(dir.path(), None).unwrap();
+        assert!(templates.build_context(&create_mock_document()).is_err());
+    }
+    #[test]
+    fn it_throws_an_error_for_a_remote_logo() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join(SETTINGS_FILE_NAME),
+            r#"{"logo": "https://example.com/logo.png"}"#,
+        )
+        .unwrap();
+
+        let templates = TemplateSet::load(dir.path(), None).unwrap();
+        let error = templates
+            .build_context(&create_mock_document())
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Logo https://example.com/logo.png is a url"));
+    }
+}
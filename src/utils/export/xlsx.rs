@@ -0,0 +1,115 @@
+use crate::utils::export::{day_rows, header_rows, hours_range, sheet_names, HEADING_ROW};
+use crate::utils::link::link_builder::TimesheetDocument;
+use rust_xlsxwriter::{Color, Format, Formula, Workbook};
+use std::error::Error;
+use std::path::Path;
+
+pub fn export(document: &TimesheetDocument, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let weekend = Format::new().set_background_color(Color::RGB(0xD9D9D9));
+    let labels = document.locale.labels();
+
+    for (timesheet, name) in document.timesheets.iter().zip(sheet_names(document)) {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(&name)?;
+        worksheet.set_column_width(0, 16)?;
+        worksheet.set_column_width(2, 48)?;
+        worksheet.set_column_width(3, 12)?;
+
+        for (row, (label, value)) in header_rows(document, timesheet).iter().enumerate() {
+            worksheet.write_string_with_format(row as u32, 0, label, &bold)?;
+            worksheet.write_string(row as u32, 1, value)?;
+        }
+
+        for (col, heading) in ["day", "hours", "note", "absence"].iter().enumerate() {
+            worksheet.write_string_with_format(
+                HEADING_ROW,
+                col as u16,
+                &labels[*heading],
+                &bold,
+            )?;
+        }
+
+        let days = day_rows(timesheet);
+        for (index, day) in days.iter().enumerate() {
+            let row = HEADING_ROW + 1 + index as u32;
+            if day.weekend {
+                worksheet.write_number_with_format(row, 0, day.day, &weekend)?;
+                worksheet.write_number_with_format(row, 1, day.hours, &weekend)?;
+                worksheet.write_string_with_format(row, 2, &day.note, &weekend)?;
+                worksheet.write_string_with_format(row, 3, day.absence_label(&labels), &weekend)?;
+            } else {
+                worksheet.write_number(row, 0, day.day)?;
+                worksheet.write_number(row, 1, day.hours)?;
+                worksheet.write_string(row, 2, &day.note)?;
+                worksheet.write_string(row, 3, day.absence_label(&labels))?;
+            }
+        }
+
+        let total_row = HEADING_ROW + 1 + days.len() as u32;
+        worksheet.write_string_with_format(total_row, 0, &labels["total_hours"], &bold)?;
+        worksheet.write_formula_with_format(
+            total_row,
+            1,
+            Formula::new(format!("=SUM({})", hours_range(days.len())))
+                .set_result(timesheet.total_hours.to_string()),
+            &bold,
+        )?;
+    }
+
+    workbook.save(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::export::tests::create_mock_document;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_exports_a_sheet_per_namespace_with_a_total_hours_formula() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timesheet.xlsx");
+        let mut document = create_mock_document();
+        document.timesheets[1].timesheet[0].insert("absence".to_string(), true.into());
+        export(&document, &path).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut workbook = String::new();
+        archive
+            .by_name("xl/workbook.xml")
+            .unwrap()
+            .read_to_string(&mut workbook)
+            .unwrap();
+        let mut sheet = String::new();
+        archive
+            .by_name("xl/worksheets/sheet1.xml")
+            .unwrap()
+            .read_to_string(&mut sheet)
+            .unwrap();
+
+        assert!(workbook.contains("name=\"autolog\""));
+        assert!(workbook.contains("name=\"autolog 2\""));
+        assert!(sheet.contains("<f>SUM(B8:B9)</f>"));
+
+        let mut strings = String::new();
+        archive
+            .by_name("xl/sharedStrings.xml")
+            .unwrap()
+            .read_to_string(&mut strings)
+            .unwrap();
+        let mut second_sheet = String::new();
+        archive
+            .by_name("xl/worksheets/sheet2.xml")
+            .unwrap()
+            .read_to_string(&mut second_sheet)
+            .unwrap();
+        assert!(strings.contains("<t>Absence</t>"));
+        assert!(strings.contains("<t>Absent</t>"));
+        assert!(second_sheet.contains("<c r=\"D8\""));
+        assert!(!sheet.contains("<c r=\"D8\""));
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::Local;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BACKUP_PREFIX: &str = "autolog-";
+const BACKUP_EXTENSION: &str = "json";
+/// Backups taken before the config file moved to config.json
+const LEGACY_BACKUP_EXTENSION: &str = "txt";
+
+/// How many backups are kept before the oldest are removed
+pub const KEEP_BACKUPS: usize = 10;
+
+/// Timestamped copies of the config file, taken before each write.
+/// Names sort chronologically, e.g. autolog-20211020T101500.123456.json
+pub struct BackupStore {
+    dir: PathBuf,
+    keep: usize,
+}
+
+impl BackupStore {
+    pub fn new<P: AsRef<Path>>(dir: P, keep: usize) -> Self {
+        BackupStore {
+            dir: dir.as_ref().to_path_buf(),
+            keep,
+        }
+    }
+
+    /// Copies the config file into the store, removing the oldest backups
+    /// beyond the limit. Nothing is copied if the config file doesn't exist yet.
+    pub fn create(&self, config_path: &Path) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        if !config_path.is_file() {
+            return Ok(None);
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        // names only sort chronologically without a suffix, so wait for a free one
+        let mut backup_path = self.backup_path_for_now();
+        while backup_path.exists() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            backup_path = self.backup_path_for_now();
+        }
+
+        fs::copy(config_path, &backup_path)?;
+        self.prune()?;
+
+        Ok(Some(backup_path))
+    }
+
+    fn backup_path_for_now(&self) -> PathBuf {
+        self.dir.join(format!(
+            "{}{}.{}",
+            BACKUP_PREFIX,
+            Local::now().format("%Y%m%dT%H%M%S%.6f"),
+            BACKUP_EXTENSION
+        ))
+    }
+
+    /// Backups, newest first
+    pub fn list(&self) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        if !self.dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut backups: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(BACKUP_PREFIX)
+                            && [BACKUP_EXTENSION, LEGACY_BACKUP_EXTENSION]
+                                .iter()
+                                .any(|extension| name.ends_with(&format!(".{}", extension)))
+                    })
+            })
+            .collect();
+        backups.sort();
+        backups.reverse();
+
+        Ok(backups)
+    }
+
+    fn prune(&self) -> Result<(), Box<dyn Error>> {
+        for backup in self.list()?.iter().skip(self.keep) {
+            fs::remove_file(backup)?;
+        }
+        Ok(())
+    }
+
+    /// Finds a backup by its file name, or by a path to it
+    pub fn find(&self, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let file_name = Path::new(name).file_name().unwrap_or_default();
+
+        self.list()?
+            .into_iter()
+            .find(|backup| backup.file_name() == Some(file_name))
+            .ok_or_else(|| {
+                format!(
+                    "Backup {} not found. Run 'autolog restore' to see every backup",
+                    name
+                )
+                .into()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_does_not_back_up_a_missing_config_file() {
+        let dir = tempdir().unwrap();
+        let store = BackupStore::new(dir.path().join("backups"), 2);
+
+        assert!(store
+            .create(&dir.path().join(".autolog.txt"))
+            .unwrap()
+            .is_none());
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_rotates_backups_and_lists_the_newest_first() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join(".autolog.txt");
+        let store = BackupStore::new(dir.path().join("backups"), 2);
+
+        for contents in ["first", "second", "third"] {
+            fs::write(&config_path, contents).unwrap();
+            store.create(&config_path).unwrap();
+        }
+
+        let backups = store.list().unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "third");
+        assert_eq!(fs::read_to_string(&backups[1]).unwrap(), "second");
+    }
+
+    #[test]
+    fn it_finds_a_backup_by_name() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join(".autolog.txt");
+        let store = BackupStore::new(dir.path().join("backups"), 2);
+        fs::write(&config_path, "[]").unwrap();
+        let backup = store.create(&config_path).unwrap().unwrap();
+
+        let name = backup.file_name().unwrap().to_str().unwrap();
+        assert_eq!(store.find(name).unwrap(), backup);
+        assert!(name.ends_with(".json"));
+        assert!(store.find("autolog-missing.json").is_err());
+    }
+
+    #[test]
+    fn it_keeps_finding_and_rotating_legacy_backups() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        let store = BackupStore::new(dir.path().join("backups"), 2);
+        fs::create_dir_all(dir.path().join("backups")).unwrap();
+        let legacy_backup = dir
+            .path()
+            .join("backups/autolog-20211020T101500.000000.txt");
+        fs::write(&legacy_backup, "legacy").unwrap();
+        fs::write(dir.path().join("backups/notes.txt"), "").unwrap();
+
+        assert_eq!(
+            store.find("autolog-20211020T101500.000000.txt").unwrap(),
+            legacy_backup
+        );
+
+        for contents in ["first", "second"] {
+            fs::write(&config_path, contents).unwrap();
+            store.create(&config_path).unwrap();
+        }
+
+        let backups = store.list().unwrap();
+        assert_eq!(backups.len(), 2);
+        assert!(!legacy_backup.exists());
+        assert!(dir.path().join("backups/notes.txt").exists());
+    }
+}
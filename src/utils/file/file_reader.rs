@@ -1,16 +1,34 @@
 use crate::data::client_repositories::ClientRepositories;
 use crate::interface::help_prompt::{ConfigurationDoc, Onboarding, RCClientRepositories};
+use crate::utils::file::backup::{BackupStore, KEEP_BACKUPS};
+use crate::utils::file::schema;
 use crate::utils::is_test_mode;
+use fs2::FileExt;
 use serde_json::json;
 use std::cell::RefCell;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use tempfile::tempfile;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tempfile::{tempfile, NamedTempFile};
 
-const CONFIG_FILE_NAME: &str = ".autolog.txt";
+/// Name of the config file in the home directory before it moved to the config directory
+const LEGACY_CONFIG_FILE_NAME: &str = ".autolog.txt";
+const LEGACY_CONFIG_DIR_NAME: &str = ".autolog";
+const CONFIG_DIR_NAME: &str = "autolog";
+const CONFIG_FILE_NAME: &str = "config.json";
+const CONFIG_ENV: &str = "AUTOLOG_CONFIG";
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Set from the --config flag, which wins over AUTOLOG_CONFIG
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Held from reading the config file until it has been written back, so concurrent
+/// autolog processes, i.e. from git hooks, can't overwrite each other's changes
+static CONFIG_LOCK: Mutex<Option<File>> = Mutex::new(None);
 
 /// Find the path to the users home directory
 pub fn get_home_path() -> PathBuf {
@@ -20,15 +38,188 @@ pub fn get_home_path() -> PathBuf {
     }
 }
 
-/// Create filepath to config file
-pub fn get_filepath(path: PathBuf) -> Result<String, Box<dyn std::error::Error>> {
-    return if is_test_mode() {
-        let path_string = &*format!("./testing-utils/{}", CONFIG_FILE_NAME);
-        Ok(path_string.to_owned())
+/// Find the directory for the config file and everything kept alongside it, i.e.
+/// credentials. This is $XDG_CONFIG_HOME/autolog, or ~/.config/autolog
+pub fn get_config_dir() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| get_home_path().join(".config"))
+        .join(CONFIG_DIR_NAME)
+}
+
+pub fn set_config_path(path: &str) {
+    CONFIG_PATH.get_or_init(|| PathBuf::from(path));
+}
+
+/// Whether the config file has been pointed elsewhere by --config or AUTOLOG_CONFIG
+pub fn is_config_path_overridden() -> bool {
+    CONFIG_PATH.get().is_some() || std::env::var_os(CONFIG_ENV).is_some()
+}
+
+/// Move the config file and directory used by older versions into the config
+/// directory, returning what was moved. Anything already in the new location is kept.
+pub fn migrate_legacy_config() -> Result<Vec<(PathBuf, PathBuf)>, Box<dyn std::error::Error>> {
+    migrate_legacy_config_from(&get_home_path(), &get_config_dir())
+}
+
+fn migrate_legacy_config_from(
+    home_dir: &Path,
+    config_dir: &Path,
+) -> Result<Vec<(PathBuf, PathBuf)>, Box<dyn std::error::Error>> {
+    let mut moves = vec![(
+        home_dir.join(LEGACY_CONFIG_FILE_NAME),
+        config_dir.join(CONFIG_FILE_NAME),
+    )];
+
+    let legacy_dir = home_dir.join(LEGACY_CONFIG_DIR_NAME);
+    if legacy_dir.is_dir() {
+        for entry in std::fs::read_dir(&legacy_dir)? {
+            let entry = entry?;
+            moves.push((entry.path(), config_dir.join(entry.file_name())));
+        }
+    }
+
+    let mut moved = vec![];
+    for (from, to) in moves {
+        if from.exists() && !to.exists() {
+            std::fs::create_dir_all(config_dir)?;
+            std::fs::rename(&from, &to)?;
+            moved.push((from, to));
+        }
+    }
+
+    // only remove the legacy directory once everything has been moved out of it
+    if legacy_dir.is_dir() && std::fs::read_dir(&legacy_dir)?.next().is_none() {
+        std::fs::remove_dir(&legacy_dir)?;
+    }
+
+    Ok(moved)
+}
+
+/// Find the path to settings that apply across every client
+pub fn get_settings_path() -> PathBuf {
+    get_config_dir().join("settings.json")
+}
+
+/// Find the directory of credentials kept by CredentialStore
+pub fn get_credentials_dir() -> PathBuf {
+    get_config_dir().join("credentials")
+}
+
+/// Find the directory of templates that override the built in renderers
+pub fn get_templates_dir() -> PathBuf {
+    get_config_dir().join("templates")
+}
+
+/// Find the directory of timestamped config file backups
+pub fn get_backups_dir() -> PathBuf {
+    get_config_dir().join("backups")
+}
+
+/// Find the journal of activity recorded by the git hooks
+pub fn get_journal_path() -> PathBuf {
+    if is_test_mode() {
+        PathBuf::from("./testing-utils/.journal.jsonl")
+    } else {
+        get_config_dir().join("journal.jsonl")
+    }
+}
+
+/// Find the key timesheets are signed with. It's kept out of the config file,
+/// which is backed up and can be shared
+pub fn get_signing_key_path() -> PathBuf {
+    if is_test_mode() {
+        PathBuf::from("./testing-utils/.signing_key")
+    } else {
+        get_config_dir().join("signing_key")
+    }
+}
+
+/// Find the log of active sessions recorded by the daemon
+pub fn get_activity_log_path() -> PathBuf {
+    if is_test_mode() {
+        PathBuf::from("./testing-utils/.activity.jsonl")
+    } else {
+        get_config_dir().join("activity.jsonl")
+    }
+}
+
+/// Take an advisory lock next to the config file, waiting for another process to
+/// release it. The lock is held by this process until unlock_config_file is called.
+pub fn lock_config_file(config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut lock = CONFIG_LOCK.lock().unwrap();
+    if lock.is_some() {
+        return Ok(());
+    }
+
+    // the default config directory won't exist before the first write
+    let config_dir = get_config_dir();
+    if Path::new(config_path).starts_with(&config_dir) {
+        std::fs::create_dir_all(config_dir)?;
+    }
+
+    let lock_path = format!("{}.lock", config_path);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+    let started = Instant::now();
+
+    while file.try_lock_exclusive().is_err() {
+        if started.elapsed() > LOCK_TIMEOUT {
+            return Err(format!(
+                "Another autolog process is using {}. Try again once it has finished",
+                config_path
+            )
+            .into());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    *lock = Some(file);
+    Ok(())
+}
+
+pub fn unlock_config_file() -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(file) = CONFIG_LOCK.lock().unwrap().take() {
+        FileExt::unlock(&file)?;
+    }
+    Ok(())
+}
+
+/// Write to a temporary file in the same directory and rename it over the
+/// destination, so a crash can't leave a half written file behind
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut file = NamedTempFile::new_in(dir)?;
+    file.write_all(contents)?;
+    file.as_file().sync_all()?;
+    file.persist(path)?;
+
+    Ok(())
+}
+
+/// Create filepath to config file. --config wins, then AUTOLOG_CONFIG, then the
+/// test config when TEST_MODE is set, falling back to config.json in the config directory
+pub fn get_config_file_path() -> Result<String, Box<dyn std::error::Error>> {
+    let path = if let Some(path) = CONFIG_PATH.get() {
+        path.clone()
+    } else if let Some(path) = std::env::var_os(CONFIG_ENV) {
+        PathBuf::from(path)
+    } else if is_test_mode() {
+        PathBuf::from(format!("./testing-utils/{}", LEGACY_CONFIG_FILE_NAME))
     } else {
-        let home_path = path.to_str();
-        Ok(home_path.unwrap().to_owned() + "/" + CONFIG_FILE_NAME)
+        get_config_dir().join(CONFIG_FILE_NAME)
     };
+
+    path.to_str()
+        .map(String::from)
+        .ok_or_else(|| format!("Config path {} isn't valid unicode", path.display()).into())
 }
 
 /// Read config file or throw error and call error function
@@ -59,9 +250,43 @@ pub fn read_data_from_config_file<T>(
 where
     T: Onboarding,
 {
-    let config_path = get_filepath(get_home_path())?;
+    let config_path = get_config_file_path()?;
     let path = &config_path;
-    read_file(buffer, path, prompt)?;
+    if !is_test_mode() {
+        lock_config_file(path)?;
+    }
+    let mut contents = String::new();
+    read_file(&mut contents, path, prompt)?;
+
+    // the buffer always holds the current schema's list of clients,
+    // so older files are upgraded in place outside of test mode
+    if !contents.trim().is_empty() {
+        let upgrade_path = if is_test_mode() {
+            None
+        } else {
+            Some(Path::new(path))
+        };
+        buffer.push_str(&schema::upgrade(&contents, upgrade_path)?);
+    }
+
+    Ok(())
+}
+
+/// Read the config file for a dry run. Older files are upgraded in the buffer only,
+/// and a missing file leaves it empty rather than starting onboarding
+pub fn read_data_from_config_file_in_memory(
+    buffer: &mut String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = get_config_file_path()?;
+    let contents = match std::fs::read_to_string(&config_path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    if !contents.trim().is_empty() {
+        buffer.push_str(&schema::upgrade(&contents, None)?);
+    }
 
     Ok(())
 }
@@ -71,7 +296,7 @@ pub fn delete_config_file() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    let config_path = get_filepath(get_home_path())?;
+    let config_path = get_config_file_path()?;
     std::fs::remove_file(config_path)?;
 
     Ok(())
@@ -81,19 +306,39 @@ pub fn write_json_to_config_file(
     json: String,
     config_path: String,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let json = schema::wrap_clients(&json)?;
+
     if is_test_mode() {
         let mut file = tempfile()?;
         file.write_all(json.as_bytes())?;
         return Ok(());
     }
 
-    let mut file = File::create(config_path)?;
-
-    file.write_all(json.as_bytes())?;
+    lock_config_file(&config_path)?;
+    let path = Path::new(&config_path);
+    BackupStore::new(get_backups_dir(), KEEP_BACKUPS).create(path)?;
+    write_atomically(path, json.as_bytes())?;
+    unlock_config_file()?;
 
     Ok(())
 }
 
+/// Replace the config file with a backup, after checking it can be read.
+/// The current file is backed up first so a restore can be undone.
+pub fn restore_config_file(backup_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(backup_path)?;
+    let clients = schema::upgrade(&contents, None)?;
+    serde_json::from_str::<ConfigurationDoc>(&clients).map_err(|err| {
+        format!(
+            "{} isn't a valid config file: {}",
+            backup_path.display(),
+            err
+        )
+    })?;
+
+    write_json_to_config_file(clients, get_config_file_path()?)
+}
+
 pub fn serialize_config(
     client_repositories: Option<RCClientRepositories>,
     deserialized_config: Option<&mut ConfigurationDoc>,
@@ -145,7 +390,8 @@ pub fn serialize_config(
                                             ]
                                             .concat(),
                                         ),
-                                        ..Default::default()
+                                        // keep the client's rules and settings
+                                        ..c.clone()
                                     };
                                 }
                                 c.clone()
@@ -267,12 +513,108 @@ mod tests {
     }
 
     #[test]
-    fn get_filepath_returns_path_with_file_name() {
+    fn get_config_dir_uses_xdg_config_home() {
+        let _lock = lock_test();
+        let _test = set_env(OsString::from("XDG_CONFIG_HOME"), "/path/to/config");
+
+        assert_eq!(get_config_dir(), PathBuf::from("/path/to/config/autolog"));
+    }
+
+    #[test]
+    fn get_config_dir_ignores_a_relative_xdg_config_home() {
+        let _lock = lock_test();
+        let _test = set_env(OsString::from("XDG_CONFIG_HOME"), "relative/config");
+
+        assert_eq!(
+            get_config_dir(),
+            get_home_path().join(".config").join("autolog")
+        );
+    }
+
+    #[test]
+    fn get_config_file_path_uses_autolog_config_over_test_mode() {
+        let _lock = lock_test();
+        let _test = set_env(OsString::from("TEST_MODE"), "true");
+        let _config = set_env(OsString::from("AUTOLOG_CONFIG"), "/path/to/autolog.json");
+
+        assert_eq!(get_config_file_path().unwrap(), "/path/to/autolog.json");
+    }
+
+    #[test]
+    fn it_lets_another_process_take_the_lock_once_a_read_has_unlocked() {
+        struct MockPrompt {}
+
+        impl Onboarding for MockPrompt {
+            fn onboarding(&self, _new_user: bool) -> Result<(), Box<dyn Error>> {
+                Ok(())
+            }
+        }
+
         let _lock = lock_test();
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        std::fs::write(&config_path, "[]").unwrap();
         let _test = set_env(OsString::from("TEST_MODE"), "false");
+        let _config = set_env(OsString::from("AUTOLOG_CONFIG"), config_path.as_os_str());
+        let prompt = Rc::new(RefCell::new(MockPrompt {}));
+        let other_process = || {
+            OpenOptions::new()
+                .write(true)
+                .open(dir.path().join("config.json.lock"))
+                .unwrap()
+        };
+
+        read_data_from_config_file(&mut String::new(), Rc::clone(&prompt)).unwrap();
+        assert!(other_process().try_lock_exclusive().is_err());
 
-        let path_buf = PathBuf::from("/path/to/usr");
-        assert_eq!(get_filepath(path_buf).unwrap(), "/path/to/usr/.autolog.txt");
+        unlock_config_file().unwrap();
+        let other = other_process();
+        assert!(other.try_lock_exclusive().is_ok());
+        FileExt::unlock(&other).unwrap();
+
+        let mut buffer = String::new();
+        read_data_from_config_file(&mut buffer, prompt).unwrap();
+        unlock_config_file().unwrap();
+        assert!(buffer.contains("[]"));
+    }
+
+    #[test]
+    fn it_migrates_a_legacy_config_file_and_directory() {
+        let home = tempfile::tempdir().unwrap();
+        let config_dir = home.path().join(".config").join("autolog");
+        std::fs::write(home.path().join(".autolog.txt"), "[]").unwrap();
+        std::fs::create_dir_all(home.path().join(".autolog").join("credentials")).unwrap();
+        std::fs::write(home.path().join(".autolog").join("settings.json"), "{}").unwrap();
+
+        let moved = migrate_legacy_config_from(home.path(), &config_dir).unwrap();
+
+        assert_eq!(moved.len(), 3);
+        assert_eq!(
+            std::fs::read_to_string(config_dir.join("config.json")).unwrap(),
+            "[]"
+        );
+        assert!(config_dir.join("credentials").is_dir());
+        assert!(config_dir.join("settings.json").is_file());
+        assert!(!home.path().join(".autolog.txt").exists());
+        assert!(!home.path().join(".autolog").exists());
+    }
+
+    #[test]
+    fn it_keeps_existing_files_when_migrating_a_legacy_config() {
+        let home = tempfile::tempdir().unwrap();
+        let config_dir = home.path().join("xdg").join("autolog");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(config_dir.join("config.json"), "new").unwrap();
+        std::fs::write(home.path().join(".autolog.txt"), "old").unwrap();
+
+        let moved = migrate_legacy_config_from(home.path(), &config_dir).unwrap();
+
+        assert!(moved.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(config_dir.join("config.json")).unwrap(),
+            "new"
+        );
+        assert!(home.path().join(".autolog.txt").exists());
     }
 
     #[test]
@@ -1 +1,3 @@
+pub mod backup;
 pub mod file_reader;
+pub mod schema;
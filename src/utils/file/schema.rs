@@ -0,0 +1,152 @@
+use crate::utils::file::file_reader;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The config file is an object of the schema version and the clients, e.g.
+/// {"version": 1, "clients": [...]}. Version 0 is the bare array of clients
+/// written before the schema was versioned.
+pub const CURRENT_VERSION: u64 = 1;
+
+type Migration = fn(Value) -> Result<Value, Box<dyn Error>>;
+
+/// Each migration upgrades the file by one version, so MIGRATIONS[0] moves
+/// version 0 to 1. Add new ones to the end and bump CURRENT_VERSION.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [migrate_v0_to_v1];
+
+fn migrate_v0_to_v1(value: Value) -> Result<Value, Box<dyn Error>> {
+    Ok(json!({ "version": 1, "clients": value }))
+}
+
+pub fn get_version(value: &Value) -> Result<u64, Box<dyn Error>> {
+    match value {
+        Value::Array(_) => Ok(0),
+        Value::Object(object) => object
+            .get("version")
+            .and_then(|version| version.as_u64())
+            .ok_or_else(|| "Config file has no schema version".into()),
+        _ => Err("Config file is neither a list of clients nor a versioned config".into()),
+    }
+}
+
+/// Runs every migration from the file's version up to CURRENT_VERSION
+pub fn migrate(mut value: Value) -> Result<Value, Box<dyn Error>> {
+    let version = get_version(&value)?;
+
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "Config file uses schema version {}, but this version of autolog only \
+            supports up to version {}. Please upgrade autolog",
+            version, CURRENT_VERSION
+        )
+        .into());
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        value = migration(value)?;
+    }
+
+    Ok(value)
+}
+
+/// Wraps a serialized list of clients in the current schema
+pub fn wrap_clients(clients_json: &str) -> Result<String, Box<dyn Error>> {
+    let clients: Value = serde_json::from_str(clients_json)?;
+    Ok(serde_json::to_string(&json!({
+        "version": CURRENT_VERSION,
+        "clients": clients,
+    }))?)
+}
+
+pub fn get_backup_path(config_path: &Path, version: u64) -> PathBuf {
+    let mut file_name = config_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".v{}.bak", version));
+    config_path.with_file_name(file_name)
+}
+
+/// Upgrades the contents of a config file, returning the list of clients as JSON.
+/// When a path is passed and the file was older, the original is copied to a
+/// backup next to it and the upgraded file is written in its place.
+pub fn upgrade(contents: &str, config_path: Option<&Path>) -> Result<String, Box<dyn Error>> {
+    let value: Value = serde_json::from_str(contents)?;
+    let version = get_version(&value)?;
+    let migrated = migrate(value)?;
+
+    if let Some(path) = config_path {
+        if version < CURRENT_VERSION {
+            fs::copy(path, get_backup_path(path, version))?;
+            file_reader::write_atomically(path, serde_json::to_string(&migrated)?.as_bytes())?;
+        }
+    }
+
+    Ok(serde_json::to_string(&migrated["clients"])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const LEGACY_CONFIG: &str = r#"[{"client":{"id":"1","client_name":"alphabet","client_address":"Moon","client_contact_person":"John"},"repositories":[]}]"#;
+
+    #[test]
+    fn it_gets_the_version_of_a_config() {
+        assert_eq!(get_version(&json!([])).unwrap(), 0);
+        assert_eq!(
+            get_version(&json!({"version": 3, "clients": []})).unwrap(),
+            3
+        );
+        assert!(get_version(&json!({"clients": []})).is_err());
+        assert!(get_version(&json!("clients")).is_err());
+    }
+
+    #[test]
+    fn it_migrates_a_legacy_config() {
+        let migrated = migrate(serde_json::from_str(LEGACY_CONFIG).unwrap()).unwrap();
+
+        assert_eq!(migrated["version"], CURRENT_VERSION);
+        assert_eq!(migrated["clients"][0]["client"]["client_name"], "alphabet");
+    }
+
+    #[test]
+    fn it_throws_an_error_for_a_config_newer_than_the_binary() {
+        let newer = json!({"version": CURRENT_VERSION + 1, "clients": []});
+        let error = migrate(newer).unwrap_err().to_string();
+
+        assert!(error.contains("Please upgrade autolog"));
+    }
+
+    #[test]
+    fn it_upgrades_a_legacy_config_file_in_place_with_a_backup() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".autolog.txt");
+        fs::write(&path, LEGACY_CONFIG).unwrap();
+
+        let clients = upgrade(LEGACY_CONFIG, Some(&path)).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&clients).unwrap(),
+            serde_json::from_str::<Value>(LEGACY_CONFIG).unwrap()
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join(".autolog.txt.v0.bak")).unwrap(),
+            LEGACY_CONFIG
+        );
+        let upgraded: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(upgraded["version"], CURRENT_VERSION);
+    }
+
+    #[test]
+    fn it_leaves_a_current_config_file_untouched() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(".autolog.txt");
+        let current = wrap_clients(LEGACY_CONFIG).unwrap();
+        fs::write(&path, &current).unwrap();
+
+        upgrade(&current, Some(&path)).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), current);
+        assert!(!dir.path().join(".autolog.txt.v1.bak").exists());
+    }
+}
@@ -0,0 +1,334 @@
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+/// Marks the lines autolog adds to a hook, so they're only added once and hooks
+/// that already exist keep working
+const HOOK_MARKER: &str = "# autolog: record activity to the journal";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookEvent {
+    PostCommit,
+    PostCheckout,
+}
+
+impl fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookEvent::PostCommit => write!(f, "post-commit"),
+            HookEvent::PostCheckout => write!(f, "post-checkout"),
+        }
+    }
+}
+
+impl FromStr for HookEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "post-commit" => Ok(HookEvent::PostCommit),
+            "post-checkout" => Ok(HookEvent::PostCheckout),
+            _ => Err(format!(
+                "'{}' isn't a hook autolog records. Use post-commit or post-checkout",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookStatus {
+    Installed,
+    AddedToExistingHook,
+    AlreadyInstalled,
+}
+
+impl fmt::Display for HookStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookStatus::Installed => write!(f, "installed"),
+            HookStatus::AddedToExistingHook => write!(f, "added to existing hook"),
+            HookStatus::AlreadyInstalled => write!(f, "already installed"),
+        }
+    }
+}
+
+/// Single quote a value for sh, so no character in it is expanded
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// The lines added to a hook. Recording never fails the git command it runs after
+pub fn hook_lines(event: HookEvent, executable: &Path) -> String {
+    format!(
+        "{}\n{} hooks record {} >/dev/null 2>&1 || true\n",
+        HOOK_MARKER,
+        shell_quote(&executable.to_string_lossy()),
+        event
+    )
+}
+
+/// Find the directory git runs hooks from, following core.hooksPath as set by tools like
+/// husky or lefthook
+pub fn hooks_dir(git_path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    if !git_path.is_dir() {
+        return Err(format!("No git directory found at {}", git_path.display()).into());
+    }
+    // relative paths are given from the worktree, or the git directory of a bare repository
+    let work_dir = match git_path.file_name() {
+        Some(name) if name == ".git" => git_path.parent().unwrap_or(git_path),
+        _ => git_path,
+    };
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(work_dir)
+        .args(["rev-parse", "--git-path", "hooks"])
+        .output()?;
+    if !output.status.success() {
+        return Err(format!("No git repository found at {}", work_dir.display()).into());
+    }
+
+    Ok(work_dir.join(crate::utils::trim_output_from_utf8(output)?))
+}
+
+/// Whether git tracks any file in the hooks directory, i.e. .husky. A hook written there
+/// would be committed along with the path to autolog on this machine
+fn is_tracked(git_path: &Path, hooks_dir: &Path) -> Result<bool, Box<dyn Error>> {
+    let work_dir = git_path.parent().unwrap_or(git_path);
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(work_dir)
+        .args(["ls-files", "--"])
+        .arg(hooks_dir)
+        .output()?;
+
+    // hooks outside the worktree can't be tracked
+    Ok(output.status.success() && !output.stdout.is_empty())
+}
+
+/// Add lines to an existing hook. They go before the first top level exit or exec, which
+/// would otherwise stop them from running
+fn add_to_hook(existing: &str, lines: &str) -> String {
+    let mut hook = String::new();
+    let mut added = false;
+
+    for line in existing.split_inclusive('\n') {
+        let is_exit = line == line.trim_start()
+            && ["exit", "exec"].iter().any(|command| {
+                line.trim_end() == *command || line.starts_with(&format!("{} ", command))
+            });
+        if is_exit && !added {
+            hook.push_str(lines);
+            hook.push('\n');
+            added = true;
+        }
+        hook.push_str(line);
+    }
+
+    if !added {
+        if !hook.ends_with('\n') {
+            hook.push('\n');
+        }
+        hook.push('\n');
+        hook.push_str(lines);
+    }
+
+    hook
+}
+
+/// Add the hook for event to the repository at git_path, keeping any hook already there
+pub fn install(
+    git_path: &Path,
+    event: HookEvent,
+    executable: &Path,
+) -> Result<HookStatus, Box<dyn Error>> {
+    let hooks_dir = hooks_dir(git_path)?;
+    if is_tracked(git_path, &hooks_dir)? {
+        return Err(format!(
+            "not installed, as {} is tracked by git. Add \"autolog hooks record {}\" to the hook yourself",
+            hooks_dir.display(),
+            event
+        )
+        .into());
+    }
+    std::fs::create_dir_all(&hooks_dir)?;
+
+    let hook_path = hooks_dir.join(event.to_string());
+    let lines = hook_lines(event, executable);
+
+    let status = match std::fs::read_to_string(&hook_path) {
+        Ok(existing) if existing.contains(HOOK_MARKER) => return Ok(HookStatus::AlreadyInstalled),
+        Ok(existing) => {
+            std::fs::write(&hook_path, add_to_hook(&existing, &lines))?;
+            HookStatus::AddedToExistingHook
+        }
+        Err(_) => {
+            std::fs::write(&hook_path, format!("#!/bin/sh\n\n{}", lines))?;
+            HookStatus::Installed
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut permissions = std::fs::metadata(&hook_path)?.permissions();
+        permissions.set_mode(permissions.mode() | 0o755);
+        std::fs::set_permissions(&hook_path, permissions)?;
+    }
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+
+    /// A new repository, returning its git directory
+    fn create_repository() -> (TempDir, PathBuf) {
+        let dir = tempdir().unwrap();
+        Command::new("git")
+            .arg("-C")
+            .arg(dir.path())
+            .args(["init", "-q"])
+            .output()
+            .unwrap();
+        let git_path = dir.path().join(".git");
+        (dir, git_path)
+    }
+
+    #[test]
+    fn it_parses_hook_events() {
+        assert_eq!(
+            "post-commit".parse::<HookEvent>().unwrap(),
+            HookEvent::PostCommit
+        );
+        assert_eq!(HookEvent::PostCheckout.to_string(), "post-checkout");
+        assert!("pre-push".parse::<HookEvent>().is_err());
+    }
+
+    #[test]
+    fn it_installs_a_new_hook_once() {
+        let (_dir, git_path) = create_repository();
+        let executable = Path::new("/usr/local/bin/autolog");
+
+        assert_eq!(
+            install(&git_path, HookEvent::PostCommit, executable).unwrap(),
+            HookStatus::Installed
+        );
+        assert_eq!(
+            install(&git_path, HookEvent::PostCommit, executable).unwrap(),
+            HookStatus::AlreadyInstalled
+        );
+
+        let hook = std::fs::read_to_string(git_path.join("hooks/post-commit")).unwrap();
+        assert!(hook.starts_with("#!/bin/sh\n"));
+        assert!(hook.contains("'/usr/local/bin/autolog' hooks record post-commit"));
+    }
+
+    #[test]
+    fn it_adds_to_an_existing_hook() {
+        let (_dir, git_path) = create_repository();
+        std::fs::create_dir_all(git_path.join("hooks")).unwrap();
+        std::fs::write(
+            git_path.join("hooks/post-checkout"),
+            "#!/bin/sh\nnpm install",
+        )
+        .unwrap();
+
+        assert_eq!(
+            install(
+                &git_path,
+                HookEvent::PostCheckout,
+                Path::new("/usr/local/bin/autolog")
+            )
+            .unwrap(),
+            HookStatus::AddedToExistingHook
+        );
+
+        let hook = std::fs::read_to_string(git_path.join("hooks/post-checkout")).unwrap();
+        assert!(hook.starts_with("#!/bin/sh\nnpm install\n\n# autolog"));
+    }
+
+    #[test]
+    fn it_adds_lines_before_the_hook_exits() {
+        let lines = hook_lines(HookEvent::PostCommit, Path::new("autolog"));
+        let existing =
+            "#!/bin/sh\nif [ -z \"$CI\" ]; then\n  exit 0\nfi\nexec lefthook run post-commit\n";
+
+        assert_eq!(
+            add_to_hook(existing, &lines),
+            format!(
+                "#!/bin/sh\nif [ -z \"$CI\" ]; then\n  exit 0\nfi\n{}\nexec lefthook run post-commit\n",
+                lines
+            )
+        );
+    }
+
+    #[test]
+    fn it_installs_into_the_configured_hooks_path() {
+        let (dir, git_path) = create_repository();
+        Command::new("git")
+            .arg("-C")
+            .arg(dir.path())
+            .args(["config", "core.hooksPath", ".husky"])
+            .output()
+            .unwrap();
+
+        assert_eq!(hooks_dir(&git_path).unwrap(), dir.path().join(".husky"));
+        install(&git_path, HookEvent::PostCommit, Path::new("autolog")).unwrap();
+        assert!(dir.path().join(".husky/post-commit").is_file());
+        assert!(!git_path.join("hooks/post-commit").exists());
+    }
+
+    #[test]
+    fn it_refuses_to_install_into_a_tracked_hooks_directory() {
+        let (dir, git_path) = create_repository();
+        std::fs::create_dir_all(dir.path().join(".husky")).unwrap();
+        std::fs::write(dir.path().join(".husky/pre-commit"), "npm test\n").unwrap();
+        for args in [
+            vec!["config", "core.hooksPath", ".husky"],
+            vec!["add", ".husky/pre-commit"],
+        ] {
+            Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(args)
+                .output()
+                .unwrap();
+        }
+
+        let result = install(&git_path, HookEvent::PostCommit, Path::new("autolog"));
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("is tracked by git"));
+        assert!(!dir.path().join(".husky/post-commit").exists());
+    }
+
+    #[test]
+    fn it_quotes_the_executable_for_the_shell() {
+        let lines = hook_lines(
+            HookEvent::PostCommit,
+            Path::new("/home/o'neil/$HOME/`id`/\"autolog\""),
+        );
+
+        assert!(lines.contains("'/home/o'\\''neil/$HOME/`id`/\"autolog\"' hooks record"));
+    }
+
+    #[test]
+    fn it_fails_without_a_git_directory() {
+        let dir = tempdir().unwrap();
+
+        assert!(install(
+            &dir.path().join("missing/.git"),
+            HookEvent::PostCommit,
+            Path::new("autolog")
+        )
+        .is_err());
+    }
+}
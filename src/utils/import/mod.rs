@@ -0,0 +1,641 @@
+use crate::interface::help_prompt::ConfigurationDoc;
+use crate::interface::timesheet_editor::MAX_HOURS;
+use chrono::{Datelike, NaiveDate};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::io::Read;
+
+/// Time tracking tools whose CSV exports can be imported
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    Toggl,
+    Clockify,
+    Harvest,
+}
+
+/// A single row from an export, reduced to what a timesheet needs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedEntry {
+    pub client: String,
+    pub project: String,
+    pub project_code: Option<String>,
+    pub date: NaiveDate,
+    pub hours: f64,
+    pub description: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub days: usize,
+    pub hours: f64,
+    pub skipped: Vec<String>,
+}
+
+/// The order of a date's day, month and year. Exports follow the account's locale, so
+/// dates with slashes can't be read without knowing which it is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateFormat {
+    Iso,
+    MonthFirst,
+    DayFirst,
+    Dotted,
+}
+
+impl std::str::FromStr for DateFormat {
+    type Err = Box<dyn Error>;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_uppercase().as_str() {
+            "YYYY-MM-DD" => Ok(DateFormat::Iso),
+            "MM/DD/YYYY" => Ok(DateFormat::MonthFirst),
+            "DD/MM/YYYY" => Ok(DateFormat::DayFirst),
+            "DD.MM.YYYY" => Ok(DateFormat::Dotted),
+            _ => Err(format!("Unsupported date format: {}", value).into()),
+        }
+    }
+}
+
+impl std::fmt::Display for DateFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateFormat::Iso => write!(f, "YYYY-MM-DD"),
+            DateFormat::MonthFirst => write!(f, "MM/DD/YYYY"),
+            DateFormat::DayFirst => write!(f, "DD/MM/YYYY"),
+            DateFormat::Dotted => write!(f, "DD.MM.YYYY"),
+        }
+    }
+}
+
+impl DateFormat {
+    fn pattern(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "%Y-%m-%d",
+            DateFormat::MonthFirst => "%m/%d/%Y",
+            DateFormat::DayFirst => "%d/%m/%Y",
+            DateFormat::Dotted => "%d.%m.%Y",
+        }
+    }
+}
+
+struct Columns {
+    client: &'static str,
+    project: &'static str,
+    project_code: Option<&'static str>,
+    date: &'static str,
+    hours: &'static [&'static str],
+    description: &'static str,
+}
+
+impl std::str::FromStr for ImportFormat {
+    type Err = Box<dyn Error>;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "toggl" => Ok(ImportFormat::Toggl),
+            "clockify" => Ok(ImportFormat::Clockify),
+            "harvest" => Ok(ImportFormat::Harvest),
+            _ => Err(format!("Unsupported import format: {}", value).into()),
+        }
+    }
+}
+
+impl ImportFormat {
+    /// Toggl and Harvest always export ISO dates. Clockify uses the workspace's format,
+    /// so its dates are only read when they can't be mistaken for another order
+    fn default_date_format(&self) -> Option<DateFormat> {
+        match self {
+            ImportFormat::Toggl | ImportFormat::Harvest => Some(DateFormat::Iso),
+            ImportFormat::Clockify => None,
+        }
+    }
+
+    fn columns(&self) -> Columns {
+        match self {
+            ImportFormat::Toggl => Columns {
+                client: "client",
+                project: "project",
+                project_code: None,
+                date: "start date",
+                hours: &["duration"],
+                description: "description",
+            },
+            ImportFormat::Clockify => Columns {
+                client: "client",
+                project: "project",
+                project_code: None,
+                date: "start date",
+                hours: &["duration (decimal)", "duration (h)"],
+                description: "description",
+            },
+            ImportFormat::Harvest => Columns {
+                client: "client",
+                project: "project",
+                project_code: Some("project code"),
+                date: "date",
+                hours: &["hours"],
+                description: "notes",
+            },
+        }
+    }
+
+    /// Read every row of an export. Any row that can't be read fails the whole import.
+    /// Dates are read in date_format, or the export's default
+    pub fn parse<R: Read>(
+        &self,
+        reader: R,
+        date_format: Option<DateFormat>,
+    ) -> Result<Vec<ImportedEntry>, Box<dyn Error>> {
+        let date_format = date_format.or_else(|| self.default_date_format());
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+
+        let headers: Vec<String> = csv_reader
+            .headers()?
+            .iter()
+            .map(|header| header.trim_start_matches('\u{feff}').to_lowercase())
+            .collect();
+
+        let find_column = |name: &str| -> Result<usize, Box<dyn Error>> {
+            headers
+                .iter()
+                .position(|header| header == name)
+                .ok_or_else(|| format!("Column '{}' not found in {:?} export", name, self).into())
+        };
+
+        let columns = self.columns();
+        let client = find_column(columns.client)?;
+        let project = find_column(columns.project)?;
+        let date = find_column(columns.date)?;
+        let description = find_column(columns.description)?;
+        let project_code = columns.project_code.and_then(|name| find_column(name).ok());
+        let hours = columns
+            .hours
+            .iter()
+            .find_map(|name| find_column(name).ok())
+            .ok_or_else(|| format!("Hours column not found in {:?} export", self))?;
+
+        let mut entries = vec![];
+        // without a format, every row has to be in the same order
+        let mut found_format: Option<DateFormat> = None;
+
+        for (index, record) in csv_reader.records().enumerate() {
+            let record = record?;
+            let field = |column: usize| record.get(column).unwrap_or_default().to_string();
+            let row = index + 2;
+
+            let (parsed_date, order) = parse_date(&field(date), date_format)
+                .map_err(|err| format!("Row {}: {}", row, err))?;
+            match (found_format, order) {
+                (Some(found), Some(order)) if found != order => {
+                    return Err(format!(
+                        "Row {}: '{}' is {} but earlier rows are {}. Pass --date-format",
+                        row,
+                        field(date),
+                        order,
+                        found
+                    )
+                    .into())
+                }
+                (None, Some(order)) => found_format = Some(order),
+                _ => {}
+            }
+
+            entries.push(ImportedEntry {
+                client: field(client),
+                project: field(project),
+                project_code: project_code
+                    .map(field)
+                    .filter(|project_code| !project_code.is_empty()),
+                date: parsed_date,
+                hours: parse_hours(&field(hours)).map_err(|err| format!("Row {}: {}", row, err))?,
+                description: field(description),
+            });
+        }
+
+        Ok(entries)
+    }
+}
+
+/// ISO dates are always read. Other dates are read in date_format when there is one.
+/// Otherwise a date with slashes is only read when just one order makes a real date, which
+/// is returned so the rest of the export can be checked against it
+fn parse_date(
+    value: &str,
+    date_format: Option<DateFormat>,
+) -> Result<(NaiveDate, Option<DateFormat>), Box<dyn Error>> {
+    let parse =
+        |date_format: DateFormat| NaiveDate::parse_from_str(value, date_format.pattern()).ok();
+
+    if let Some(date) = parse(DateFormat::Iso) {
+        return Ok((date, None));
+    }
+    if let Some(date_format) = date_format {
+        return parse(date_format)
+            .map(|date| (date, None))
+            .ok_or_else(|| format!("Couldn't read date '{}'", value).into());
+    }
+    if let Some(date) = parse(DateFormat::Dotted) {
+        return Ok((date, None));
+    }
+
+    match (parse(DateFormat::MonthFirst), parse(DateFormat::DayFirst)) {
+        (Some(month_first), Some(day_first)) if month_first != day_first => Err(format!(
+            "'{}' could be {} or {}. Pass --date-format=MM/DD/YYYY or --date-format=DD/MM/YYYY",
+            value,
+            month_first.format("%-d %B %Y"),
+            day_first.format("%-d %B %Y")
+        )
+        .into()),
+        (Some(date), Some(_)) => Ok((date, None)),
+        (Some(date), None) => Ok((date, Some(DateFormat::MonthFirst))),
+        (None, Some(date)) => Ok((date, Some(DateFormat::DayFirst))),
+        (None, None) => Err(format!("Couldn't read date '{}'", value).into()),
+    }
+}
+
+/// Hours are either decimal, or a duration of hours, minutes and optional seconds. A single
+/// entry can't be negative or longer than a day
+fn parse_hours(value: &str) -> Result<f64, Box<dyn Error>> {
+    let hours = if value.contains(':') {
+        let parts = value
+            .split(':')
+            .map(|part| part.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| format!("Couldn't read duration '{}'", value))?;
+
+        parts
+            .iter()
+            .zip([1.0, 60.0, 3600.0])
+            .map(|(part, divisor)| part / divisor)
+            .sum()
+    } else {
+        value
+            .parse::<f64>()
+            .map_err(|_| format!("Couldn't read hours '{}'", value))?
+    };
+
+    if !hours.is_finite() || !(0.0..=MAX_HOURS).contains(&hours) {
+        return Err(format!("Hours '{}' must be between 0 and {}", value, MAX_HOURS).into());
+    }
+
+    Ok(hours)
+}
+
+/// Add imported entries to the matching repositories as user edited days. A row matches the
+/// client by name and the repository by namespace, alias or project number, unless a client
+/// or namespace is passed to use instead. Imported hours replace the day's hours, so importing
+/// the same export twice gives the same timesheet. Nothing is merged if any of the months is
+/// approved, as approved timesheets are locked.
+pub fn merge_entries_into_config(
+    config: &mut ConfigurationDoc,
+    entries: &[ImportedEntry],
+    client_name: Option<&String>,
+    namespace: Option<&String>,
+) -> Result<ImportSummary, Box<dyn Error>> {
+    let mut summary = ImportSummary::default();
+    let mut days: BTreeMap<(usize, usize, NaiveDate), (f64, Vec<String>)> = BTreeMap::new();
+
+    for entry in entries {
+        let client = client_name.unwrap_or(&entry.client).to_lowercase();
+        let client_index = config.iter().position(|client_repositories| {
+            client_repositories.get_client_name().to_lowercase() == client
+        });
+
+        let repository_index = client_index.and_then(|client_index| {
+            let repositories = config[client_index].repositories.as_ref()?;
+            let candidates: Vec<String> = match namespace {
+                Some(namespace) => vec![namespace.to_lowercase()],
+                None => [Some(&entry.project), entry.project_code.as_ref()]
+                    .into_iter()
+                    .flatten()
+                    .map(|value| value.to_lowercase())
+                    .collect(),
+            };
+
+            repositories.iter().position(|repository| {
+                [
+                    &repository.namespace,
+                    &repository.namespace_alias,
+                    &repository.project_number,
+                ]
+                .into_iter()
+                .flatten()
+                .any(|value| candidates.contains(&value.to_lowercase()))
+            })
+        });
+
+        match (client_index, repository_index) {
+            (Some(client_index), Some(repository_index)) => {
+                let day = days
+                    .entry((client_index, repository_index, entry.date))
+                    .or_default();
+                day.0 += entry.hours;
+                if !entry.description.is_empty() && !day.1.contains(&entry.description) {
+                    day.1.push(entry.description.clone());
+                }
+            }
+            _ => summary.skipped.push(format!(
+                "{} {} / {}",
+                entry.date, entry.client, entry.project
+            )),
+        }
+    }
+
+    if let Some(((_, _, date), (hours, _))) = days.iter().find(|(_, (hours, _))| *hours > MAX_HOURS)
+    {
+        return Err(format!(
+            "{} hours were imported for {}, more than {} in a day",
+            hours, date, MAX_HOURS
+        )
+        .into());
+    }
+
+    let months: BTreeSet<(usize, i32, u32)> = days
+        .keys()
+        .map(|(client_index, _, date)| (*client_index, date.year(), date.month()))
+        .collect();
+    for (client_index, year, month) in months {
+        config[client_index].prepare_month_for_edit(year, month)?;
+    }
+
+    for ((client_index, repository_index, date), (hours, descriptions)) in days {
+        let repository = &mut config[client_index].repositories.as_mut().unwrap()[repository_index];
+        repository.set_imported_day(date, hours, descriptions.join("; "))?;
+
+        summary.days += 1;
+        summary.hours += hours;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::approval::ApprovalAction;
+    use crate::data::client_repositories::{Client, ClientRepositories};
+    use crate::data::repository::Repository;
+    use serde_json::json;
+
+    fn create_mock_config() -> ConfigurationDoc {
+        vec![ClientRepositories {
+            client: Option::from(Client {
+                id: "1".to_string(),
+                client_name: "Alphabet".to_string(),
+                client_address: "Spaghetti Way, USA".to_string(),
+                client_contact_person: "John Smith".to_string(),
+            }),
+            repositories: Option::from(vec![
+                Repository {
+                    namespace: Option::from("autolog".to_string()),
+                    ..Default::default()
+                },
+                Repository {
+                    namespace: Option::from("website".to_string()),
+                    project_number: Option::from("PRJ-42".to_string()),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn it_parses_a_toggl_export() {
+        let csv = "\u{feff}User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags\n\
+            Jim,jim@jones.com,Alphabet,autolog,,Planning,Yes,2021-10-20,09:00:00,2021-10-20,10:30:00,01:30:00,\n";
+
+        let entries = ImportFormat::Toggl.parse(csv.as_bytes(), None).unwrap();
+        assert_eq!(
+            entries,
+            vec![ImportedEntry {
+                client: "Alphabet".to_string(),
+                project: "autolog".to_string(),
+                project_code: None,
+                date: NaiveDate::from_ymd_opt(2021, 10, 20).unwrap(),
+                hours: 1.5,
+                description: "Planning".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_parses_a_clockify_export() {
+        let csv = "Project,Client,Description,Task,User,Email,Tags,Billable,Start Date,Start Time,End Date,End Time,Duration (h),Duration (decimal)\n\
+            autolog,Alphabet,Review,,Jim,jim@jones.com,,Yes,10/20/2021,09:00:00,10/20/2021,09:45:00,00:45:00,0.75\n";
+
+        let entries = ImportFormat::Clockify.parse(csv.as_bytes(), None).unwrap();
+        assert_eq!(entries[0].hours, 0.75);
+        assert_eq!(
+            entries[0].date,
+            NaiveDate::from_ymd_opt(2021, 10, 20).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_parses_a_harvest_export() {
+        let csv = "Date,Client,Project,Project Code,Task,Notes,Hours,Billable?\n\
+            2021-10-21,Alphabet,Website,PRJ-42,Design,Wireframes,2.25,Yes\n";
+
+        let entries = ImportFormat::Harvest.parse(csv.as_bytes(), None).unwrap();
+        assert_eq!(entries[0].project_code, Some("PRJ-42".to_string()));
+        assert_eq!(entries[0].hours, 2.25);
+    }
+
+    #[test]
+    fn it_throws_an_error_for_a_missing_column() {
+        let csv = "Date,Client,Project\n2021-10-21,Alphabet,Website\n";
+        assert!(ImportFormat::Harvest.parse(csv.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn it_throws_an_error_for_an_unreadable_row() {
+        let csv = "Date,Client,Project,Notes,Hours\n21st October,Alphabet,Website,,2\n";
+        let error = ImportFormat::Harvest
+            .parse(csv.as_bytes(), None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Row 2: Couldn't read date '21st October'"
+        );
+    }
+
+    #[test]
+    fn it_parses_hours_and_durations() {
+        assert_eq!(parse_hours("1.25").unwrap(), 1.25);
+        assert_eq!(parse_hours("01:30:00").unwrap(), 1.5);
+        assert_eq!(parse_hours("2:15").unwrap(), 2.25);
+        assert!(parse_hours("two").is_err());
+        for value in ["NaN", "inf", "1e400", "-2", "25", "1e400:00"] {
+            assert!(parse_hours(value).is_err(), "{} was read", value);
+        }
+    }
+
+    #[test]
+    fn it_rejects_dates_that_could_be_read_either_way() {
+        let csv = "Project,Client,Description,Start Date,Duration (decimal)\n\
+            autolog,Alphabet,Review,03/04/2021,1\n";
+        let error = ImportFormat::Clockify
+            .parse(csv.as_bytes(), None)
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("Row 2: '03/04/2021' could be 4 March 2021 or 3 April 2021"));
+
+        let entries = ImportFormat::Clockify
+            .parse(csv.as_bytes(), Some(DateFormat::DayFirst))
+            .unwrap();
+        assert_eq!(
+            entries[0].date,
+            NaiveDate::from_ymd_opt(2021, 4, 3).unwrap()
+        );
+        assert_eq!(parse_date("04/04/2021", None).unwrap().1, None);
+    }
+
+    #[test]
+    fn it_rejects_an_export_with_dates_in_both_orders() {
+        let csv = "Project,Client,Description,Start Date,Duration (decimal)\n\
+            autolog,Alphabet,Review,10/20/2021,1\n\
+            autolog,Alphabet,Review,21/10/2021,1\n";
+        let error = ImportFormat::Clockify
+            .parse(csv.as_bytes(), None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Row 3: '21/10/2021' is DD/MM/YYYY but earlier rows are MM/DD/YYYY. Pass --date-format"
+        );
+    }
+
+    #[test]
+    fn it_reads_iso_dates_only_for_toggl_and_harvest_by_default() {
+        let csv = "Date,Client,Project,Notes,Hours\n10/20/2021,Alphabet,Website,,2\n";
+        assert!(ImportFormat::Harvest.parse(csv.as_bytes(), None).is_err());
+        assert!(ImportFormat::Harvest
+            .parse(csv.as_bytes(), Some("mm/dd/yyyy".parse().unwrap()))
+            .is_ok());
+    }
+
+    #[test]
+    fn it_doesnt_merge_more_than_a_day_of_hours() {
+        let mut config = create_mock_config();
+        let entry = ImportedEntry {
+            client: "Alphabet".to_string(),
+            project: "autolog".to_string(),
+            project_code: None,
+            date: NaiveDate::from_ymd_opt(2021, 10, 21).unwrap(),
+            hours: 16.0,
+            description: String::new(),
+        };
+
+        let error = merge_entries_into_config(&mut config, &[entry.clone(), entry], None, None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "32 hours were imported for 2021-10-21, more than 24 in a day"
+        );
+        assert!(config[0].repositories.as_ref().unwrap()[0]
+            .timesheet
+            .is_none());
+    }
+
+    #[test]
+    fn it_merges_entries_into_matching_repositories() {
+        let mut config = create_mock_config();
+        let date = NaiveDate::from_ymd_opt(2021, 10, 21).unwrap();
+        let entry = ImportedEntry {
+            client: "alphabet".to_string(),
+            project: "Website".to_string(),
+            project_code: Some("PRJ-42".to_string()),
+            date,
+            hours: 2.0,
+            description: "Wireframes".to_string(),
+        };
+        let entries = vec![
+            entry.clone(),
+            ImportedEntry {
+                hours: 1.5,
+                description: "Review".to_string(),
+                ..entry.clone()
+            },
+            ImportedEntry {
+                client: "Initech".to_string(),
+                ..entry.clone()
+            },
+        ];
+
+        let summary = merge_entries_into_config(&mut config, &entries, None, None).unwrap();
+        assert_eq!(summary.days, 1);
+        assert_eq!(summary.hours, 3.5);
+        assert_eq!(summary.skipped, vec!["2021-10-21 Initech / Website"]);
+
+        // importing again replaces rather than adds to the hours
+        merge_entries_into_config(&mut config, &entries, None, None).unwrap();
+
+        let repositories = config[0].repositories.as_ref().unwrap();
+        let day = &repositories[1].timesheet.as_ref().unwrap()["2021"]["10"][20];
+        assert_eq!(day["hours"], json!(3.5));
+        assert_eq!(day["user_edited"], json!(true));
+        assert_eq!(day["note"], json!("Wireframes; Review"));
+        assert!(repositories[0].timesheet.is_none());
+    }
+
+    #[test]
+    fn it_doesnt_merge_entries_into_an_approved_month() {
+        let mut config = create_mock_config();
+        for action in [ApprovalAction::Submit, ApprovalAction::Approve] {
+            config[0]
+                .apply_approval_action(2021, 10, action, None)
+                .unwrap();
+        }
+        let entries = vec![ImportedEntry {
+            client: "Alphabet".to_string(),
+            project: "autolog".to_string(),
+            project_code: None,
+            date: NaiveDate::from_ymd_opt(2021, 10, 21).unwrap(),
+            hours: 1.0,
+            description: String::new(),
+        }];
+
+        let error = merge_entries_into_config(&mut config, &entries, None, None)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "The timesheet for 10/2021 has been approved and can't be edited"
+        );
+        assert!(config[0].repositories.as_ref().unwrap()[0]
+            .timesheet
+            .is_none());
+    }
+
+    #[test]
+    fn it_merges_entries_into_a_passed_client_and_namespace() {
+        let mut config = create_mock_config();
+        let entries = vec![ImportedEntry {
+            client: "Alphabet Inc".to_string(),
+            project: "Meetings".to_string(),
+            project_code: None,
+            date: NaiveDate::from_ymd_opt(2021, 10, 21).unwrap(),
+            hours: 1.0,
+            description: String::new(),
+        }];
+
+        let summary = merge_entries_into_config(
+            &mut config,
+            &entries,
+            Some(&"Alphabet".to_string()),
+            Some(&"autolog".to_string()),
+        )
+        .unwrap();
+        assert_eq!(summary.days, 1);
+        assert!(config[0].repositories.as_ref().unwrap()[0]
+            .timesheet
+            .is_some());
+    }
+}
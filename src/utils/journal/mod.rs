@@ -0,0 +1,154 @@
+use crate::data::repository::GitLogDates;
+use chrono::{DateTime, Datelike, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+/// Activity recorded by the git hooks as it happens, one JSON record per line.
+/// Days in the journal count as worked days even when their commits are later
+/// rebased or squashed away.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ActivityRecord {
+    pub timestamp: DateTime<FixedOffset>,
+    pub event: String,
+    pub git_path: String,
+    pub branch: Option<String>,
+    pub commit: Option<String>,
+}
+
+fn git_output(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| crate::utils::trim_output_from_utf8(output).ok())
+        .filter(|output| !output.is_empty())
+}
+
+impl ActivityRecord {
+    /// Record an event for the repository in the current directory, as the hooks run
+    pub fn from_current_repository(event: &str) -> Result<Self, Box<dyn Error>> {
+        let toplevel = git_output(&["rev-parse", "--show-toplevel"])
+            .ok_or("Activity can only be recorded inside a git repository")?;
+
+        Ok(ActivityRecord {
+            timestamp: Local::now().fixed_offset(),
+            event: event.to_string(),
+            // matches Repository.git_path
+            git_path: format!("{}/.git/", toplevel),
+            // a detached HEAD has no branch
+            branch: git_output(&["rev-parse", "--abbrev-ref", "HEAD"])
+                .filter(|branch| branch != "HEAD"),
+            commit: git_output(&["rev-parse", "HEAD"]),
+        })
+    }
+}
+
+pub fn append(path: &Path, record: &ActivityRecord) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+
+    Ok(())
+}
+
+/// Read every record in the journal. Lines that can't be read, i.e. one cut short
+/// by a crash, are skipped
+pub fn read(path: &Path) -> Result<Vec<ActivityRecord>, Box<dyn Error>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let contents = std::fs::read_to_string(path)?;
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Days with activity for a repository, in the same shape as dates parsed from git log
+pub fn git_log_dates_for(records: &[ActivityRecord], git_path: &str) -> GitLogDates {
+    let mut git_log_dates = GitLogDates::new();
+
+    for record in records.iter().filter(|record| record.git_path == git_path) {
+        let date = record.timestamp.date_naive();
+        git_log_dates
+            .entry(date.year())
+            .or_default()
+            .entry(date.month())
+            .or_default()
+            .insert(date.day());
+    }
+
+    git_log_dates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn new_record(timestamp: &str, git_path: &str) -> ActivityRecord {
+        ActivityRecord {
+            timestamp: DateTime::parse_from_rfc3339(timestamp).unwrap(),
+            event: "post-commit".to_string(),
+            git_path: git_path.to_string(),
+            branch: Some("main".to_string()),
+            commit: None,
+        }
+    }
+
+    #[test]
+    fn it_appends_and_reads_records() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("autolog").join("journal.jsonl");
+        let record = new_record("2021-11-02T09:30:00+01:00", "/work/api/.git/");
+
+        append(&path, &record).unwrap();
+        append(&path, &record).unwrap();
+
+        assert_eq!(read(&path).unwrap(), vec![record.clone(), record]);
+    }
+
+    #[test]
+    fn it_skips_lines_that_cant_be_read() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        let record = new_record("2021-11-02T09:30:00+01:00", "/work/api/.git/");
+
+        append(&path, &record).unwrap();
+        std::fs::write(
+            &path,
+            std::fs::read_to_string(&path).unwrap() + "{\"timestamp\":\n",
+        )
+        .unwrap();
+
+        assert_eq!(read(&path).unwrap(), vec![record]);
+        assert!(read(&dir.path().join("missing.jsonl")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_finds_days_with_activity_for_a_repository() {
+        let records = vec![
+            new_record("2021-11-02T09:30:00+01:00", "/work/api/.git/"),
+            // the day is taken in the offset it was recorded in
+            new_record("2021-11-03T23:30:00-05:00", "/work/api/.git/"),
+            new_record("2021-12-01T10:00:00+00:00", "/work/web/.git/"),
+        ];
+
+        let git_log_dates = git_log_dates_for(&records, "/work/api/.git/");
+
+        assert_eq!(git_log_dates.len(), 1);
+        let mut days: Vec<u32> = git_log_dates[&2021][&11].iter().copied().collect();
+        days.sort();
+        assert_eq!(days, vec![2, 3]);
+    }
+}
@@ -3,10 +3,13 @@ use crate::data::repository::Repository;
 use crate::db;
 use crate::interface::help_prompt::RCClientRepositories;
 use crate::utils::date::date_parser::{check_for_valid_month, check_for_valid_year};
-use chrono::{DateTime, Month, Utc};
+use crate::utils::email;
+use crate::utils::email::Delivery;
+use crate::utils::locale::Locale;
+use crate::utils::signing::DocumentSignature;
+use chrono::{DateTime, NaiveDate, Utc};
 use dotenv;
 use mongodb::bson::doc;
-use num_traits::cast::FromPrimitive;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::error::Error;
@@ -14,22 +17,30 @@ use std::process;
 use std::rc::Rc;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-struct Timesheet {
-    namespace: String,
-    timesheet: TimesheetHoursForMonth,
-    total_hours: f64,
-    project_number: Option<String>,
+pub struct Timesheet {
+    pub namespace: String,
+    pub timesheet: TimesheetHoursForMonth,
+    pub total_hours: f64,
+    pub project_number: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-struct TimesheetDocument {
-    creation_date: DateTime<Utc>,
-    random_path: String,
-    month_year: String,
-    client: Option<Client>,
-    user: Option<User>,
-    approver: Option<Approver>,
-    timesheets: Vec<Timesheet>,
+pub struct TimesheetDocument {
+    pub creation_date: DateTime<Utc>,
+    pub random_path: String,
+    pub month_year: String,
+    pub client: Option<Client>,
+    pub user: Option<User>,
+    pub approver: Option<Approver>,
+    pub timesheets: Vec<Timesheet>,
+    /// Only used by local renderers, the hosted page gets the localised month_year
+    #[serde(skip)]
+    pub locale: Locale,
+    #[serde(skip)]
+    pub first_day: Option<NaiveDate>,
+    /// Added by sign, and checked by verify
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<DocumentSignature>,
 }
 
 pub type TimesheetHoursForMonth = Vec<Map<String, Value>>;
@@ -37,15 +48,12 @@ pub type TimesheetHoursForMonth = Vec<Map<String, Value>>;
 fn get_string_month_year(
     month: &Option<String>,
     year: &Option<String>,
+    locale: Locale,
 ) -> Result<String, Box<dyn Error>> {
     let month_u32 = check_for_valid_month(month)?;
     let year_string = check_for_valid_year(year)?;
 
-    Ok(format!(
-        "{}, {}",
-        Month::from_u32(month_u32).unwrap().name(),
-        year_string
-    ))
+    Ok(locale.format_month_year(month_u32, year_string))
 }
 
 fn find_month_from_timesheet<'a>(
@@ -83,6 +91,9 @@ fn build_document<'a>(
         client: repos.client.clone(),
         approver: repos.approver.clone(),
         timesheets: timesheets.to_owned(),
+        locale: repos.locale.unwrap_or_default(),
+        first_day: None,
+        signatures: vec![],
     }
 }
 
@@ -141,12 +152,48 @@ fn generate_timesheet_vec(
     Ok(timesheets)
 }
 
+/// Assemble the document that would be sent by build_unique_uri, without
+/// generating a random path or touching the database
+pub fn build_document_preview(
+    client_repositories: RCClientRepositories,
+    options: Vec<Option<String>>,
+) -> Result<TimesheetDocument, Box<dyn Error>> {
+    let month_year_string = get_string_month_year(
+        &options[1],
+        &options[2],
+        client_repositories.borrow().locale.unwrap_or_default(),
+    )?;
+    let first_day = NaiveDate::from_ymd_opt(
+        check_for_valid_year(&options[2])?.parse()?,
+        check_for_valid_month(&options[1])?,
+        1,
+    );
+    let timesheets =
+        generate_timesheet_vec(Rc::clone(&client_repositories), options, &month_year_string)?;
+
+    let mut document = build_document(
+        Utc::now(),
+        "",
+        &month_year_string,
+        &timesheets,
+        &client_repositories.borrow(),
+    );
+    document.first_day = first_day;
+
+    Ok(document)
+}
+
 pub async fn build_unique_uri(
     client_repositories: RCClientRepositories,
     options: Vec<Option<String>>,
 ) -> Result<(), Box<dyn Error>> {
     dotenv::dotenv().ok();
-    let month_year_string = get_string_month_year(&options[1], &options[2])?;
+    let month_year_string = get_string_month_year(
+        &options[1],
+        &options[2],
+        client_repositories.borrow().locale.unwrap_or_default(),
+    )?;
+    let send = options[7].as_deref() == Some("true");
     let timesheets =
         generate_timesheet_vec(Rc::clone(&client_repositories), options, &month_year_string)?;
     let client_repos = client_repositories.borrow_mut();
@@ -210,6 +257,18 @@ pub async fn build_unique_uri(
         &timesheet_gen_uri,
     );
 
+    if send {
+        let recipient = email::send_timesheet(&document, Delivery::Link(timesheet_gen_uri))
+            .unwrap_or_else(|err| {
+                eprintln!("Error sending timesheet: {}", err);
+                process::exit(exitcode::UNAVAILABLE);
+            });
+        crate::interface::help_prompt::HelpPrompt::show_timesheet_sent(
+            &document.month_year,
+            &recipient,
+        );
+    }
+
     process::exit(exitcode::OK);
 }
 
@@ -219,10 +278,11 @@ mod test {
     use crate::data::repository::Repository;
     use crate::helpers::mocks;
     use crate::utils::link::link_builder::{
-        build_document, calculate_total_hours, find_month_from_timesheet, generate_timesheet_vec,
-        get_string_month_year, Timesheet, TimesheetDocument,
+        build_document, build_document_preview, calculate_total_hours, find_month_from_timesheet,
+        generate_timesheet_vec, get_string_month_year, Timesheet, TimesheetDocument,
     };
-    use chrono::{TimeZone, Utc};
+    use crate::utils::locale::Locale;
+    use chrono::{NaiveDate, TimeZone, Utc};
     use expect_test::expect_file;
     use nanoid::nanoid;
     use serde_json::json;
@@ -293,6 +353,9 @@ mod test {
             user: user.clone(),
             approver: approver.clone(),
             timesheets: timesheets.clone(),
+            locale: Locale::En,
+            first_day: None,
+            signatures: vec![],
         };
 
         let generated_document = build_document(
@@ -315,6 +378,31 @@ mod test {
         assert_eq!(json!(generated_document), json!(document));
     }
 
+    #[test]
+    fn it_builds_a_document_preview_without_a_random_path() {
+        let options = vec![
+            Option::None,
+            Option::from("10".to_owned()),
+            Option::from("2021".to_owned()),
+        ];
+
+        let mut client_repository = ClientRepositories {
+            ..Default::default()
+        };
+        mocks::create_mock_client_repository(&mut client_repository);
+        client_repository.repositories = Option::from(vec![mocks::create_mock_repository()]);
+
+        let document =
+            build_document_preview(Rc::new(RefCell::new(client_repository)), options).unwrap();
+
+        assert_eq!(document.random_path, "".to_string());
+        assert_eq!(document.month_year, "October, 2021".to_string());
+        assert_eq!(document.first_day, NaiveDate::from_ymd_opt(2021, 10, 1));
+        assert_eq!(document.client.unwrap().client_name, "alphabet".to_string());
+        assert_eq!(document.timesheets.len(), 1);
+        assert_eq!(document.timesheets[0].total_hours, 24.0);
+    }
+
     #[test]
     fn it_calculates_total_hours() {
         let month = mocks::create_mock_timesheet_hours_for_month();
@@ -327,7 +415,7 @@ mod test {
             Option::from("blah blah".to_owned()),
             Option::from("2021".to_owned()),
         ];
-        assert!(get_string_month_year(&options[0], &options[1]).is_err());
+        assert!(get_string_month_year(&options[0], &options[1], Locale::En).is_err());
     }
 
     #[test]
@@ -336,7 +424,7 @@ mod test {
             Option::from("10".to_owned()),
             Option::from("blah blah".to_owned()),
         ];
-        assert!(get_string_month_year(&options[0], &options[1]).is_err());
+        assert!(get_string_month_year(&options[0], &options[1], Locale::En).is_err());
     }
 
     #[test]
@@ -345,7 +433,7 @@ mod test {
             Option::from("10".to_owned()),
             Option::from("1345".to_owned()),
         ];
-        assert!(get_string_month_year(&options[0], &options[1]).is_err());
+        assert!(get_string_month_year(&options[0], &options[1], Locale::En).is_err());
     }
 
     #[test]
@@ -354,7 +442,7 @@ mod test {
             Option::from("15".to_owned()),
             Option::from("1345".to_owned()),
         ];
-        assert!(get_string_month_year(&options[0], &options[1]).is_err());
+        assert!(get_string_month_year(&options[0], &options[1], Locale::En).is_err());
     }
 
     #[test]
@@ -364,11 +452,23 @@ mod test {
             Option::from("2021".to_owned()),
         ];
         assert_eq!(
-            get_string_month_year(&options[0], &options[1]).unwrap(),
+            get_string_month_year(&options[0], &options[1], Locale::En).unwrap(),
             "October, 2021".to_string()
         );
     }
 
+    #[test]
+    fn it_gets_a_localised_string_for_month_year() {
+        let options = [
+            Option::from("10".to_owned()),
+            Option::from("2021".to_owned()),
+        ];
+        assert_eq!(
+            get_string_month_year(&options[0], &options[1], Locale::De).unwrap(),
+            "Oktober 2021".to_string()
+        );
+    }
+
     #[test]
     fn returns_none_if_month_cannot_be_found() {
         let options = vec![
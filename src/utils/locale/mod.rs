@@ -0,0 +1,323 @@
+use crate::data::settings::Settings;
+use chrono::Weekday;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+const LOCALE_ENV: &str = "AUTOLOG_LOCALE";
+
+/// Languages timesheets and prompts can be shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Fr,
+}
+
+/// Prompts in the CLI string catalogue. Placeholders are {0}, {1}...
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    ClientOrRepositoryNotFound,
+    EditedConfig,
+    UpdatedConfig,
+    GeneratingTimesheet,
+    TimesheetExported,
+    Imported,
+    ImportSkippedRows,
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Accepts a language code or a POSIX locale, e.g. de, de-DE or de_DE.UTF-8
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let language = value
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match language.as_str() {
+            "en" => Ok(Locale::En),
+            "de" => Ok(Locale::De),
+            "fr" => Ok(Locale::Fr),
+            _ => Err(format!(
+                "Unsupported locale: {}. Supported locales are en, de, fr",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl Locale {
+    /// The locale for CLI prompts. AUTOLOG_LOCALE wins, then the settings file,
+    /// then LC_ALL or LANG, falling back to English
+    pub fn current() -> Self {
+        std::env::var(LOCALE_ENV)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .or_else(|| Settings::read().ok().and_then(|settings| settings.locale))
+            .or_else(|| {
+                ["LC_ALL", "LANG"]
+                    .iter()
+                    .filter_map(|name| std::env::var(name).ok())
+                    .find_map(|value| value.parse().ok())
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+            Locale::Fr => "fr",
+        }
+    }
+
+    pub fn month_name(&self, month: u32) -> &'static str {
+        let names = match self {
+            Locale::En => [
+                "January",
+                "February",
+                "March",
+                "April",
+                "May",
+                "June",
+                "July",
+                "August",
+                "September",
+                "October",
+                "November",
+                "December",
+            ],
+            Locale::De => [
+                "Januar",
+                "Februar",
+                "März",
+                "April",
+                "Mai",
+                "Juni",
+                "Juli",
+                "August",
+                "September",
+                "Oktober",
+                "November",
+                "Dezember",
+            ],
+            Locale::Fr => [
+                "janvier",
+                "février",
+                "mars",
+                "avril",
+                "mai",
+                "juin",
+                "juillet",
+                "août",
+                "septembre",
+                "octobre",
+                "novembre",
+                "décembre",
+            ],
+        };
+        names[(month.clamp(1, 12) - 1) as usize]
+    }
+
+    pub fn weekday_name(&self, weekday: Weekday) -> &'static str {
+        let names = match self {
+            Locale::En => ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+            Locale::De => ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+            Locale::Fr => ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+        };
+        names[weekday.num_days_from_monday() as usize]
+    }
+
+    /// e.g. "October, 2021", "Oktober 2021" or "octobre 2021"
+    pub fn format_month_year(&self, month: u32, year: &str) -> String {
+        match self {
+            Locale::En => format!("{}, {}", self.month_name(month), year),
+            Locale::De | Locale::Fr => format!("{} {}", self.month_name(month), year),
+        }
+    }
+
+    /// Formats hours with at most two decimals, using a decimal comma for de and fr
+    pub fn format_number(&self, value: f64) -> String {
+        let formatted = format!("{:.2}", value);
+        let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+        match self {
+            Locale::En => formatted.to_string(),
+            Locale::De | Locale::Fr => formatted.replace('.', ","),
+        }
+    }
+
+    /// Default labels of exported documents
+    pub fn labels(&self) -> BTreeMap<String, String> {
+        let labels = match self {
+            Locale::En => [
+                ("title", "Timesheet"),
+                ("client", "Client"),
+                ("client_contact_person", "Client contact person"),
+                ("client_address", "Client address"),
+                ("user", "User"),
+                ("approver", "Approver"),
+                ("project_number", "Project number"),
+                ("month", "Month"),
+                ("day", "Day"),
+                ("hours", "Hours"),
+                ("note", "Note"),
+                ("total", "Total"),
+                ("total_hours", "Total hours"),
+                ("days_worked", "days worked"),
+                ("absence", "Absence"),
+                ("absent", "Absent"),
+            ],
+            Locale::De => [
+                ("title", "Stundenzettel"),
+                ("client", "Kunde"),
+                ("client_contact_person", "Ansprechpartner"),
+                ("client_address", "Anschrift"),
+                ("user", "Mitarbeiter"),
+                ("approver", "Freigabe durch"),
+                ("project_number", "Projektnummer"),
+                ("month", "Monat"),
+                ("day", "Tag"),
+                ("hours", "Stunden"),
+                ("note", "Notiz"),
+                ("total", "Summe"),
+                ("total_hours", "Gesamtstunden"),
+                ("days_worked", "Arbeitstage"),
+                ("absence", "Abwesenheit"),
+                ("absent", "Abwesend"),
+            ],
+            Locale::Fr => [
+                ("title", "Feuille de temps"),
+                ("client", "Client"),
+                ("client_contact_person", "Interlocuteur"),
+                ("client_address", "Adresse"),
+                ("user", "Consultant"),
+                ("approver", "Approbateur"),
+                ("project_number", "Numéro de projet"),
+                ("month", "Mois"),
+                ("day", "Jour"),
+                ("hours", "Heures"),
+                ("note", "Remarque"),
+                ("total", "Total"),
+                ("total_hours", "Total des heures"),
+                ("days_worked", "jours travaillés"),
+                ("absence", "Absence"),
+                ("absent", "Absent"),
+            ],
+        };
+
+        labels
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    pub fn text(&self, message: Message) -> &'static str {
+        match (self, message) {
+            (Locale::En, Message::ClientOrRepositoryNotFound) => "Client or repository not found.",
+            (Locale::De, Message::ClientOrRepositoryNotFound) => {
+                "Kunde oder Repository nicht gefunden."
+            }
+            (Locale::Fr, Message::ClientOrRepositoryNotFound) => "Client ou dépôt introuvable.",
+            (Locale::En, Message::EditedConfig) => "autolog successfully edited",
+            (Locale::De, Message::EditedConfig) => "autolog erfolgreich bearbeitet",
+            (Locale::Fr, Message::EditedConfig) => "autolog modifié avec succès",
+            (Locale::En, Message::UpdatedConfig) => "autolog successfully updated",
+            (Locale::De, Message::UpdatedConfig) => "autolog erfolgreich aktualisiert",
+            (Locale::Fr, Message::UpdatedConfig) => "autolog mis à jour avec succès",
+            (Locale::En, Message::GeneratingTimesheet) => "Generating timesheet for {0}...",
+            (Locale::De, Message::GeneratingTimesheet) => "Stundenzettel für {0} wird erstellt...",
+            (Locale::Fr, Message::GeneratingTimesheet) => {
+                "Génération de la feuille de temps pour {0}..."
+            }
+            (Locale::En, Message::TimesheetExported) => "Timesheet for {0} exported to {1}",
+            (Locale::De, Message::TimesheetExported) => "Stundenzettel für {0} exportiert nach {1}",
+            (Locale::Fr, Message::TimesheetExported) => {
+                "Feuille de temps pour {0} exportée vers {1}"
+            }
+            (Locale::En, Message::Imported) => "Imported {0} hours across {1} days",
+            (Locale::De, Message::Imported) => "{0} Stunden an {1} Tagen importiert",
+            (Locale::Fr, Message::Imported) => "{0} heures importées sur {1} jours",
+            (Locale::En, Message::ImportSkippedRows) => {
+                "Skipped {0} rows without a matching client and repository. \
+                Pass --client and --namespace to choose one:"
+            }
+            (Locale::De, Message::ImportSkippedRows) => {
+                "{0} Zeilen ohne passenden Kunden und Repository übersprungen. \
+                Mit --client und --namespace lässt sich eines wählen:"
+            }
+            (Locale::Fr, Message::ImportSkippedRows) => {
+                "{0} lignes sans client ni dépôt correspondant ignorées. \
+                Utilisez --client et --namespace pour en choisir un :"
+            }
+        }
+    }
+
+    /// Looks up a message and fills its placeholders in order
+    pub fn format(&self, message: Message, values: &[&str]) -> String {
+        values
+            .iter()
+            .enumerate()
+            .fold(self.text(message).to_string(), |text, (index, value)| {
+                text.replace(&format!("{{{}}}", index), value)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use envtestkit::lock::lock_test;
+    use envtestkit::set_env;
+    use std::ffi::OsString;
+
+    #[test]
+    fn it_parses_a_locale() {
+        assert_eq!("de".parse::<Locale>(), Ok(Locale::De));
+        assert_eq!("fr-CH".parse::<Locale>(), Ok(Locale::Fr));
+        assert_eq!("en_GB.UTF-8".parse::<Locale>(), Ok(Locale::En));
+        assert!("es".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn it_formats_month_and_weekday_names() {
+        assert_eq!(Locale::En.format_month_year(10, "2021"), "October, 2021");
+        assert_eq!(Locale::De.format_month_year(3, "2021"), "März 2021");
+        assert_eq!(Locale::Fr.format_month_year(8, "2021"), "août 2021");
+        assert_eq!(Locale::De.weekday_name(Weekday::Thu), "Do");
+    }
+
+    #[test]
+    fn it_formats_numbers_with_a_decimal_comma() {
+        assert_eq!(Locale::En.format_number(7.5), "7.5");
+        assert_eq!(Locale::De.format_number(7.5), "7,5");
+        assert_eq!(Locale::Fr.format_number(8.0), "8");
+        assert_eq!(Locale::De.format_number(2.333), "2,33");
+    }
+
+    #[test]
+    fn it_formats_a_message_from_the_catalogue() {
+        assert_eq!(
+            Locale::De.format(Message::TimesheetExported, &["Oktober 2021", "a.ods"]),
+            "Stundenzettel für Oktober 2021 exportiert nach a.ods"
+        );
+    }
+
+    #[test]
+    fn it_reads_the_current_locale_from_the_environment() {
+        let _lock = lock_test();
+        let _locale = set_env(OsString::from(LOCALE_ENV), "fr");
+        assert_eq!(Locale::current(), Locale::Fr);
+    }
+}
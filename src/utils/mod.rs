@@ -1,26 +1,80 @@
+pub mod activity;
+pub mod assignment;
+pub mod calendar;
 pub mod date;
+pub mod email;
+pub mod export;
 pub mod file;
+pub mod hooks;
+pub mod import;
+pub mod journal;
 pub mod link;
+pub mod locale;
+pub mod scan;
+pub mod signing;
+pub mod time_source;
 
-use dialoguer::Confirm;
 use dotenv::dotenv;
 use random_string::generate;
 use std::env;
 use std::error::Error;
 use std::process::Output;
+use std::sync::OnceLock;
+
+/// How prompts are answered. Scripts and CI pass --no-input so autolog never waits on
+/// stdin, or --yes to also accept every confirmation
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum InputMode {
+    #[default]
+    Interactive,
+    NoInput,
+    AssumeYes,
+}
+
+static INPUT_MODE: OnceLock<InputMode> = OnceLock::new();
+
+impl InputMode {
+    /// The answer to a confirmation that can't be asked, or None when it can be
+    pub fn confirmation(&self) -> Result<Option<bool>, Box<dyn Error>> {
+        match self {
+            InputMode::Interactive => Ok(None),
+            InputMode::AssumeYes => Ok(Some(true)),
+            InputMode::NoInput => {
+                Err("Confirmation needed. Pass --yes to confirm without input".into())
+            }
+        }
+    }
 
-pub fn confirm() -> Result<bool, Box<dyn Error>> {
-    if is_test_mode() {
-        return Ok(true);
+    /// Fail, naming the flag to pass instead, when a value can't be asked for
+    pub fn require(&self, label: &str, flag: &str) -> Result<(), Box<dyn Error>> {
+        match self {
+            InputMode::Interactive => Ok(()),
+            _ => Err(format!(
+                "Missing value for '{}'. Pass {} when running without input",
+                label, flag
+            )
+            .into()),
+        }
     }
+}
+
+pub fn set_input_mode(mode: InputMode) {
+    INPUT_MODE.get_or_init(|| mode);
+}
 
-    Ok(Confirm::new().default(true).interact()?)
+pub fn get_input_mode() -> InputMode {
+    INPUT_MODE.get().copied().unwrap_or_default()
+}
+
+pub fn is_interactive() -> bool {
+    get_input_mode() == InputMode::Interactive
 }
 
 pub fn is_test_mode() -> bool {
     dotenv().ok();
-    let test_mode = env::var("TEST_MODE").unwrap();
-    test_mode.parse::<bool>().unwrap()
+    env::var("TEST_MODE")
+        .map(|test_mode| test_mode.parse::<bool>().unwrap_or(false))
+        .unwrap_or(false)
 }
 
 pub fn exit_process() {
@@ -70,6 +124,26 @@ mod tests {
         assert_eq!(is_test_mode(), false);
     }
 
+    #[test]
+    fn it_answers_confirmations_without_input() {
+        assert_eq!(InputMode::Interactive.confirmation().unwrap(), None);
+        assert_eq!(InputMode::AssumeYes.confirmation().unwrap(), Some(true));
+        assert!(InputMode::NoInput.confirmation().is_err());
+    }
+
+    #[test]
+    fn it_names_the_flag_for_a_missing_value() {
+        assert!(InputMode::Interactive.require("Client", "--client").is_ok());
+        assert!(InputMode::AssumeYes.require("Client", "--client").is_err());
+        assert_eq!(
+            InputMode::NoInput
+                .require("Client", "--client")
+                .unwrap_err()
+                .to_string(),
+            "Missing value for 'Client'. Pass --client when running without input"
+        );
+    }
+
     #[test]
     fn should_return_true_if_config_file_is_found() {
         let mut buffer = String::new();
@@ -0,0 +1,505 @@
+use crate::data::client_repositories::{Client, ClientRepositories, User};
+use crate::data::repository::Repository;
+use crate::interface::help_prompt::ConfigurationDoc;
+use crate::utils::assignment;
+use crate::utils::export::slugify;
+use nanoid::nanoid;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::{DirEntry, WalkDir};
+
+/// What a proposed client name was taken from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientSource {
+    Rule,
+    Path,
+    Remote,
+}
+
+/// A git repository found below the scanned directory, with the client it is proposed for
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredRepository {
+    pub path: PathBuf,
+    pub remote: Option<String>,
+    pub client: Option<String>,
+    pub source: Option<ClientSource>,
+}
+
+impl fmt::Display for ClientSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientSource::Rule => write!(f, "rule"),
+            ClientSource::Path => write!(f, "path"),
+            ClientSource::Remote => write!(f, "remote"),
+        }
+    }
+}
+
+impl DiscoveredRepository {
+    pub fn get_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry.file_name().to_string_lossy().starts_with('.')
+}
+
+/// Find every git repository below root. Hidden directories are skipped, and the search
+/// doesn't descend into a repository once one is found
+pub fn find_repositories(root: &Path) -> Vec<PathBuf> {
+    let mut repositories = vec![];
+    let mut entries = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_hidden(entry));
+
+    while let Some(entry) = entries.next() {
+        // directories that can't be read are left out rather than failing the scan
+        let Ok(entry) = entry else {
+            continue;
+        };
+
+        if entry.file_type().is_dir() && entry.path().join(".git").exists() {
+            repositories.push(entry.path().to_path_buf());
+            entries.skip_current_dir();
+        }
+    }
+
+    repositories
+}
+
+/// The url of the 'origin' remote, or the first remote when there is no origin
+pub fn get_remote_url(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .arg("remote")
+        .arg("-v")
+        .output()
+        .ok()?;
+    let remotes = crate::utils::trim_output_from_utf8(output).ok()?;
+
+    let mut urls = remotes.lines().filter_map(|line| {
+        let mut parts = line.split_whitespace();
+        Some((parts.next()?, parts.next()?))
+    });
+
+    let first = urls.clone().next().map(|(_, url)| url.to_string());
+    urls.find(|(name, _)| *name == "origin")
+        .map(|(_, url)| url.to_string())
+        .or(first)
+}
+
+/// The organisation or user a remote url belongs to, e.g. 'acme' for
+/// git@github.com:acme/api.git or https://gitlab.com/acme/team/api
+pub fn get_remote_owner(url: &str) -> Option<String> {
+    // local paths can be remotes too, but they don't name an owner
+    if !url.contains("://") && !url.contains(':') {
+        return None;
+    }
+
+    let regex =
+        regex::Regex::new(r"^(?:[\w+.\-]+://)?(?:[^@/]+@)?[^:/]+(?::\d+)?[:/](?P<owner>[^/]+)/")
+            .unwrap();
+
+    regex
+        .captures(url)
+        .and_then(|cap| cap.name("owner"))
+        .map(|owner| owner.as_str().to_string())
+}
+
+/// Propose a client for a repository. A client's assignment rules come first, then
+/// repositories grouped in a directory below root (root/<client>/<repository>) take the
+/// directory's name, otherwise the remote's owner is used. Names matching an existing
+/// client use that client's spelling
+pub fn propose_client(
+    root: &Path,
+    path: &Path,
+    remote: Option<&String>,
+    config: &ConfigurationDoc,
+) -> Option<(String, ClientSource)> {
+    let owner = remote.and_then(|url| get_remote_owner(url));
+
+    if let Some(client_name) = assignment::find_client(config, path, owner.as_deref()) {
+        return Some((client_name, ClientSource::Rule));
+    }
+
+    let components: Vec<String> = path
+        .strip_prefix(root)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    let proposal = if components.len() > 1 {
+        Some((components[0].clone(), ClientSource::Path))
+    } else {
+        owner.map(|owner| (owner, ClientSource::Remote))
+    };
+
+    proposal.map(|(name, source)| {
+        let existing = config
+            .iter()
+            .map(|client| client.get_client_name())
+            .find(|client_name| slugify(client_name) == slugify(&name));
+
+        (existing.unwrap_or(name), source)
+    })
+}
+
+/// Whether a repository at path is already under a client in the config file
+pub fn is_initialised(config: &ConfigurationDoc, path: &Path) -> bool {
+    config
+        .iter()
+        .filter_map(|client| client.repositories.as_ref())
+        .flatten()
+        .filter_map(|repository| repository.git_path.as_ref())
+        .any(|git_path| Path::new(git_path).parent() == Some(path))
+}
+
+/// Find the repositories below root that aren't in the config file yet, each with a
+/// proposed client
+pub fn discover(
+    root: &Path,
+    config: &ConfigurationDoc,
+) -> Result<Vec<DiscoveredRepository>, Box<dyn Error>> {
+    let root = std::fs::canonicalize(root)?;
+
+    Ok(find_repositories(&root)
+        .into_iter()
+        .filter(|path| !is_initialised(config, path))
+        .map(|path| {
+            let remote = get_remote_url(&path);
+            let proposal = propose_client(&root, &path, remote.as_ref(), config);
+
+            DiscoveredRepository {
+                client: proposal.as_ref().map(|(client, _)| client.clone()),
+                source: proposal.map(|(_, source)| source),
+                path,
+                remote,
+            }
+        })
+        .collect())
+}
+
+/// Find the repository containing path when it isn't in the config file yet and one
+/// of the clients' assignment rules matches it
+pub fn discover_assigned(
+    path: &Path,
+    config: &ConfigurationDoc,
+) -> Result<Option<DiscoveredRepository>, Box<dyn Error>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .arg("rev-parse")
+        .arg("--show-toplevel")
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let path = std::fs::canonicalize(crate::utils::trim_output_from_utf8(output)?)?;
+    if is_initialised(config, &path) {
+        return Ok(None);
+    }
+
+    let remote = get_remote_url(&path);
+    let owner = remote.as_ref().and_then(|url| get_remote_owner(url));
+
+    Ok(
+        assignment::find_client(config, &path, owner.as_deref()).map(|client| {
+            DiscoveredRepository {
+                path,
+                remote,
+                client: Some(client),
+                source: Some(ClientSource::Rule),
+            }
+        }),
+    )
+}
+
+/// Add repositories to a client in the config, creating the client when it doesn't exist.
+/// Repositories take the client's details so they can be found by client later
+pub fn add_repositories_to_client(
+    config: &mut ConfigurationDoc,
+    client_name: &str,
+    mut repositories: Vec<Repository>,
+) {
+    let index = match config
+        .iter()
+        .position(|client| client.get_client_name() == client_name)
+    {
+        Some(index) => index,
+        None => {
+            let first = repositories.first();
+            config.push(ClientRepositories {
+                client: Option::from(Client {
+                    id: nanoid!(),
+                    client_name: client_name.to_string(),
+                    client_address: String::new(),
+                    client_contact_person: String::new(),
+                }),
+                user: Option::from(User {
+                    id: nanoid!(),
+                    name: first.and_then(|repo| repo.name.clone()).unwrap_or_default(),
+                    email: first
+                        .and_then(|repo| repo.email.clone())
+                        .unwrap_or_default(),
+                    is_alias: false,
+                    thumbnail: Option::None,
+                }),
+                repositories: Option::from(vec![]),
+                ..Default::default()
+            });
+            config.len() - 1
+        }
+    };
+
+    let client_repositories = &mut config[index];
+    let client = client_repositories.client.clone().unwrap();
+    let user_id = client_repositories
+        .user
+        .as_ref()
+        .map(|user| user.id.clone());
+
+    for repository in repositories.iter_mut() {
+        repository
+            .set_client_id(client.id.clone())
+            .set_client_name(client.client_name.clone())
+            .set_client_address(client.client_address.clone())
+            .set_client_contact_person(client.client_contact_person.clone());
+        if let Some(user_id) = &user_id {
+            repository.set_user_id(user_id.clone());
+        }
+    }
+
+    client_repositories
+        .repositories
+        .get_or_insert_with(Vec::new)
+        .extend(repositories);
+}
+
+/// Read each repository's details, generate its timesheets from the git history and add
+/// it to its proposed client. Repositories without a proposed client are left out.
+/// Returns the number of repositories added
+pub fn onboard(
+    config: &mut ConfigurationDoc,
+    discovered: &[DiscoveredRepository],
+) -> Result<usize, Box<dyn Error>> {
+    let mut clients: BTreeMap<String, Vec<Repository>> = BTreeMap::new();
+
+    for found in discovered {
+        let Some(client_name) = &found.client else {
+            continue;
+        };
+
+        let mut repository = Repository {
+            repo_path: Option::from(found.path.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        repository
+            .find_repository_details_from()?
+            .set_repository_id(nanoid!());
+
+        clients
+            .entry(client_name.clone())
+            .or_default()
+            .push(repository);
+    }
+
+    let mut count = 0;
+    for (client_name, repositories) in clients {
+        // repositories added together under a client share out the hours between them
+        let mut batch = ClientRepositories {
+            repositories: Option::from(repositories),
+            ..Default::default()
+        };
+        batch
+            .exec_generate_timesheets_from_git_history()
+            .compare_logs_and_set_timesheets();
+
+        let repositories = batch.repositories.unwrap_or_default();
+        count += repositories.len();
+        add_repositories_to_client(config, &client_name, repositories);
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::mocks;
+    use crate::utils::assignment::AssignmentRule;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn mock_config() -> ConfigurationDoc {
+        let mut client_repositories = ClientRepositories::default();
+        mocks::create_mock_client_repository(&mut client_repositories);
+        vec![client_repositories]
+    }
+
+    #[test]
+    fn it_finds_repositories_below_a_directory() {
+        let dir = tempdir().unwrap();
+        for path in [
+            "acme/api/.git",
+            "acme/api/vendor/nested/.git",
+            "acme/web/.git",
+            "globex/tools/cli/.git",
+            ".cache/hidden/.git",
+            "notes",
+        ] {
+            fs::create_dir_all(dir.path().join(path)).unwrap();
+        }
+
+        let repositories = find_repositories(dir.path());
+
+        assert_eq!(
+            repositories,
+            vec![
+                dir.path().join("acme/api"),
+                dir.path().join("acme/web"),
+                dir.path().join("globex/tools/cli"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_gets_the_owner_of_a_remote() {
+        for (url, owner) in [
+            ("git@github.com:acme/api.git", Some("acme")),
+            ("https://github.com/acme/api", Some("acme")),
+            ("ssh://git@gitlab.com:2222/acme/team/api.git", Some("acme")),
+            (
+                "https://user@bitbucket.org/globex/tools.git",
+                Some("globex"),
+            ),
+            ("/srv/git/api.git", None),
+            ("../api", None),
+        ] {
+            assert_eq!(get_remote_owner(url).as_deref(), owner, "{}", url);
+        }
+    }
+
+    #[test]
+    fn it_proposes_a_client_from_the_path() {
+        let root = Path::new("/work");
+        let remote = "git@github.com:someone-else/api.git".to_string();
+
+        assert_eq!(
+            propose_client(
+                root,
+                &root.join("acme/api"),
+                Some(&remote),
+                &ConfigurationDoc::new()
+            ),
+            Some(("acme".to_string(), ClientSource::Path))
+        );
+    }
+
+    #[test]
+    fn it_proposes_a_client_from_the_remote() {
+        let root = Path::new("/work");
+        let remote = "git@github.com:acme/api.git".to_string();
+
+        assert_eq!(
+            propose_client(
+                root,
+                &root.join("api"),
+                Some(&remote),
+                &ConfigurationDoc::new()
+            ),
+            Some(("acme".to_string(), ClientSource::Remote))
+        );
+        assert_eq!(
+            propose_client(root, &root.join("api"), None, &ConfigurationDoc::new()),
+            None
+        );
+    }
+
+    #[test]
+    fn it_proposes_an_existing_client() {
+        let root = Path::new("/work");
+
+        assert_eq!(
+            propose_client(root, &root.join("Alphabet/api"), None, &mock_config()),
+            Some(("alphabet".to_string(), ClientSource::Path))
+        );
+    }
+
+    #[test]
+    fn it_proposes_a_client_from_an_assignment_rule() {
+        let root = Path::new("/work");
+        let remote = "git@github.com:acme-corp/api.git".to_string();
+        let mut config = mock_config();
+        config[0].add_assignment_rule(AssignmentRule {
+            remote_org: Option::from("acme-corp".to_string()),
+            path: None,
+        });
+
+        assert_eq!(
+            propose_client(root, &root.join("globex/api"), Some(&remote), &config),
+            Some(("alphabet".to_string(), ClientSource::Rule))
+        );
+    }
+
+    #[test]
+    fn it_checks_whether_a_repository_is_initialised() {
+        let mut config = mock_config();
+        config[0].repositories.as_mut().unwrap()[0].git_path =
+            Option::from("/work/acme/api/.git/".to_string());
+
+        assert!(is_initialised(&config, Path::new("/work/acme/api")));
+        assert!(!is_initialised(&config, Path::new("/work/acme/web")));
+    }
+
+    #[test]
+    fn it_adds_repositories_to_an_existing_client() {
+        let mut config = mock_config();
+        let repository = Repository {
+            namespace: Option::from("api".to_string()),
+            ..Default::default()
+        };
+
+        add_repositories_to_client(&mut config, "alphabet", vec![repository]);
+
+        let repositories = config[0].repositories.as_ref().unwrap();
+        assert_eq!(config.len(), 1);
+        assert_eq!(repositories.len(), 2);
+        assert_eq!(repositories[1].client_name.as_deref(), Some("alphabet"));
+        assert_eq!(repositories[1].client_id, Some(config[0].get_client_id()));
+    }
+
+    #[test]
+    fn it_adds_repositories_to_a_new_client() {
+        let mut config = mock_config();
+        let repository = Repository {
+            namespace: Option::from("api".to_string()),
+            name: Option::from("Jim Jones".to_string()),
+            email: Option::from("jim@jones.com".to_string()),
+            ..Default::default()
+        };
+
+        add_repositories_to_client(&mut config, "acme", vec![repository]);
+
+        assert_eq!(config.len(), 2);
+        assert_eq!(config[1].get_client_name(), "acme");
+        assert_eq!(config[1].user.as_ref().unwrap().email, "jim@jones.com");
+        assert_eq!(
+            config[1].repositories.as_ref().unwrap()[0]
+                .client_name
+                .as_deref(),
+            Some("acme")
+        );
+    }
+}
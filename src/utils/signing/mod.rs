@@ -0,0 +1,331 @@
+use crate::utils::link::link_builder::TimesheetDocument;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Who signed a timesheet. The user signs it when it's sent, the approver once
+/// they've approved it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningRole {
+    User,
+    Approver,
+}
+
+impl fmt::Display for SigningRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SigningRole::User => write!(f, "user"),
+            SigningRole::Approver => write!(f, "approver"),
+        }
+    }
+}
+
+impl FromStr for SigningRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(SigningRole::User),
+            "approver" => Ok(SigningRole::Approver),
+            _ => Err(format!(
+                "'{}' isn't a signing role. Use user or approver",
+                s
+            )),
+        }
+    }
+}
+
+/// An Ed25519 signature over the document's canonical form, with the public key to check it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentSignature {
+    pub role: SigningRole,
+    pub signed_by: Option<String>,
+    pub public_key: String,
+    pub signature: String,
+    pub signed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignatureStatus {
+    Valid,
+    /// The signature doesn't match the document, so it's changed since it was signed
+    Invalid,
+    /// The signature matches, but not with the key stored for the client
+    UnexpectedKey,
+}
+
+impl fmt::Display for SignatureStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureStatus::Valid => write!(f, "valid"),
+            SignatureStatus::Invalid => write!(f, "changed"),
+            SignatureStatus::UnexpectedKey => write!(f, "unknown key"),
+        }
+    }
+}
+
+/// Read the signing key, generating one the first time. Returns true when it's new
+pub fn load_or_generate_key(path: &Path) -> Result<(SigningKey, bool), Box<dyn Error>> {
+    if path.exists() {
+        let encoded = std::fs::read_to_string(path)?;
+        let bytes: [u8; 32] = STANDARD
+            .decode(encoded.trim())?
+            .try_into()
+            .map_err(|_| format!("The signing key at {} isn't valid", path.display()))?;
+
+        return Ok((SigningKey::from_bytes(&bytes), false));
+    }
+
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|err| format!("Couldn't generate a key: {}", err))?;
+    let key = SigningKey::from_bytes(&bytes);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, STANDARD.encode(key.to_bytes()) + "\n")?;
+
+    // the key is only readable by its owner
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok((key, true))
+}
+
+/// Check a public key as printed by sign, i.e. one given for an approver
+pub fn validate_public_key(public_key: &str) -> Result<String, Box<dyn Error>> {
+    decode_public_key(public_key)
+        .map(|_| public_key.to_string())
+        .map_err(|_| format!("'{}' isn't an Ed25519 public key", public_key).into())
+}
+
+fn decode_public_key(public_key: &str) -> Result<VerifyingKey, Box<dyn Error>> {
+    let bytes: [u8; 32] = STANDARD
+        .decode(public_key)?
+        .try_into()
+        .map_err(|_| "Public key isn't 32 bytes")?;
+
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+pub fn encode_public_key(key: &SigningKey) -> String {
+    STANDARD.encode(key.verifying_key().to_bytes())
+}
+
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect::<Map<String, Value>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+/// The bytes that are signed: the document as json without its signatures, with
+/// object keys sorted and no whitespace, so it's the same wherever it's serialised
+pub fn canonical_bytes(document: &TimesheetDocument) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut value = serde_json::to_value(document)?;
+    if let Value::Object(map) = &mut value {
+        map.remove("signatures");
+    }
+
+    Ok(serde_json::to_vec(&sort_keys(value))?)
+}
+
+/// Sign the document, replacing an earlier signature for the same role
+pub fn sign(
+    document: &mut TimesheetDocument,
+    key: &SigningKey,
+    role: SigningRole,
+    signed_by: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let signature = key.sign(&canonical_bytes(document)?);
+
+    document
+        .signatures
+        .retain(|signature| signature.role != role);
+    document.signatures.push(DocumentSignature {
+        role,
+        signed_by,
+        public_key: encode_public_key(key),
+        signature: STANDARD.encode(signature.to_bytes()),
+        signed_at: Utc::now(),
+    });
+
+    Ok(())
+}
+
+fn check_signature(bytes: &[u8], signature: &DocumentSignature) -> Result<bool, Box<dyn Error>> {
+    let verifying_key = decode_public_key(&signature.public_key)?;
+    let signature_bytes: [u8; 64] = STANDARD
+        .decode(&signature.signature)?
+        .try_into()
+        .map_err(|_| "Signature isn't 64 bytes")?;
+
+    Ok(verifying_key
+        .verify(bytes, &Signature::from_bytes(&signature_bytes))
+        .is_ok())
+}
+
+/// Check every signature on the document. expected_key returns the public key stored
+/// for a role, if there is one, which the signature has to have been made with
+pub fn verify(
+    document: &TimesheetDocument,
+    expected_key: impl Fn(SigningRole) -> Option<String>,
+) -> Result<Vec<(DocumentSignature, SignatureStatus)>, Box<dyn Error>> {
+    let bytes = canonical_bytes(document)?;
+
+    Ok(document
+        .signatures
+        .iter()
+        .map(|signature| {
+            let status = match check_signature(&bytes, signature) {
+                Ok(true) => match expected_key(signature.role) {
+                    Some(key) if key != signature.public_key => SignatureStatus::UnexpectedKey,
+                    _ => SignatureStatus::Valid,
+                },
+                _ => SignatureStatus::Invalid,
+            };
+            (signature.clone(), status)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::export::tests::create_mock_document;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn statuses(document: &TimesheetDocument) -> Vec<SignatureStatus> {
+        verify(document, |_| None)
+            .unwrap()
+            .into_iter()
+            .map(|(_, status)| status)
+            .collect()
+    }
+
+    #[test]
+    fn it_generates_a_key_once() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("autolog").join("signing_key");
+
+        let (key, created) = load_or_generate_key(&path).unwrap();
+        assert!(created);
+        let (loaded_key, created) = load_or_generate_key(&path).unwrap();
+        assert!(!created);
+        assert_eq!(encode_public_key(&key), encode_public_key(&loaded_key));
+
+        std::fs::write(&path, "not a key").unwrap();
+        assert!(load_or_generate_key(&path).is_err());
+    }
+
+    #[test]
+    fn it_serialises_a_document_the_same_way_without_its_signatures() {
+        let mut document = create_mock_document();
+        let bytes = canonical_bytes(&document).unwrap();
+
+        sign(
+            &mut document,
+            &SigningKey::from_bytes(&[1; 32]),
+            SigningRole::User,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(canonical_bytes(&document).unwrap(), bytes);
+        assert!(String::from_utf8(bytes)
+            .unwrap()
+            .starts_with("{\"approver\":{\"approvers_email\""));
+    }
+
+    #[test]
+    fn it_signs_and_verifies_a_document() {
+        let mut document = create_mock_document();
+        sign(
+            &mut document,
+            &SigningKey::from_bytes(&[1; 32]),
+            SigningRole::User,
+            Some("Jim Jones".to_string()),
+        )
+        .unwrap();
+        sign(
+            &mut document,
+            &SigningKey::from_bytes(&[2; 32]),
+            SigningRole::Approver,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            statuses(&document),
+            vec![SignatureStatus::Valid, SignatureStatus::Valid]
+        );
+
+        // signing again replaces the signature
+        sign(
+            &mut document,
+            &SigningKey::from_bytes(&[2; 32]),
+            SigningRole::Approver,
+            None,
+        )
+        .unwrap();
+        assert_eq!(document.signatures.len(), 2);
+    }
+
+    #[test]
+    fn it_detects_a_document_changed_after_it_was_signed() {
+        let mut document = create_mock_document();
+        sign(
+            &mut document,
+            &SigningKey::from_bytes(&[2; 32]),
+            SigningRole::Approver,
+            None,
+        )
+        .unwrap();
+
+        document.timesheets[0].timesheet[0] = json!({"hours": 12.0, "weekend": false})
+            .as_object()
+            .unwrap()
+            .clone();
+
+        assert_eq!(statuses(&document), vec![SignatureStatus::Invalid]);
+    }
+
+    #[test]
+    fn it_detects_a_signature_from_an_unexpected_key() {
+        let mut document = create_mock_document();
+        let key = SigningKey::from_bytes(&[1; 32]);
+        sign(&mut document, &key, SigningRole::User, None).unwrap();
+
+        let expected = encode_public_key(&key);
+        let result = verify(&document, |_| Some(expected.clone())).unwrap();
+        assert_eq!(result[0].1, SignatureStatus::Valid);
+
+        let other = encode_public_key(&SigningKey::from_bytes(&[3; 32]));
+        assert_eq!(validate_public_key(&other).unwrap(), other);
+        assert!(validate_public_key("bm90IGEga2V5").is_err());
+        let result = verify(&document, |_| Some(other.clone())).unwrap();
+        assert_eq!(result[0].1, SignatureStatus::UnexpectedKey);
+    }
+}
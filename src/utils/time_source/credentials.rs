@@ -0,0 +1,215 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Stores credentials for each time source in its own file, so linking or unlinking
+/// one source never touches another. Files can only be read and written by the user.
+#[derive(Debug, Clone)]
+pub struct CredentialStore {
+    dir: PathBuf,
+    read_only: bool,
+}
+
+impl CredentialStore {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        CredentialStore {
+            dir: dir.as_ref().to_path_buf(),
+            read_only: false,
+        }
+    }
+
+    /// A store that never writes, for a dry run. A refreshed token is only kept for the run
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    pub fn path_for(&self, provider: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", provider))
+    }
+
+    pub fn load<T: DeserializeOwned>(&self, provider: &str) -> Result<Option<T>, Box<dyn Error>> {
+        let path = self.path_for(provider);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    pub fn save<T: Serialize>(
+        &self,
+        provider: &str,
+        credentials: &T,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.read_only {
+            return Ok(());
+        }
+        self.create_dir()?;
+
+        let path = self.path_for(provider);
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&path)?;
+
+        // mode only applies to new files, so tighten any file written by an older version
+        restrict_permissions(&path, 0o600)?;
+        file.write_all(serde_json::to_string(credentials)?.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn create_dir(&self) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)?;
+        restrict_permissions(&self.dir, 0o700)?;
+        Ok(())
+    }
+
+    pub fn remove(&self, provider: &str) -> Result<bool, Box<dyn Error>> {
+        let path = self.path_for(provider);
+
+        if self.read_only || !path.exists() {
+            return Ok(false);
+        }
+
+        fs::remove_file(path)?;
+        Ok(true)
+    }
+
+    /// Move a token stored by an older version into the provider's credentials file
+    pub fn migrate_legacy_token(
+        &self,
+        legacy_path: &Path,
+        provider: &str,
+    ) -> Result<&Self, Box<dyn Error>> {
+        if !self.read_only && legacy_path.exists() && !self.path_for(provider).exists() {
+            self.create_dir()?;
+            fs::copy(legacy_path, self.path_for(provider))?;
+            restrict_permissions(&self.path_for(provider), 0o600)?;
+            fs::remove_file(legacy_path)?;
+        }
+
+        Ok(self)
+    }
+}
+
+/// Only the owner can read credentials. Other platforms keep their default permissions
+fn restrict_permissions(path: &Path, mode: u32) -> Result<(), Box<dyn Error>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_saves_loads_and_removes_credentials_per_provider() {
+        let dir = tempdir().unwrap();
+        let store = CredentialStore::new(dir.path().join("credentials"));
+        let credentials = HashMap::from([("token".to_string(), "abc".to_string())]);
+
+        assert!(store
+            .load::<HashMap<String, String>>("gcal")
+            .unwrap()
+            .is_none());
+
+        store.save("gcal", &credentials).unwrap();
+        store.save("outlook", &credentials).unwrap();
+        assert_eq!(
+            store.load::<HashMap<String, String>>("gcal").unwrap(),
+            Some(credentials)
+        );
+
+        assert!(store.remove("gcal").unwrap());
+        assert!(!store.remove("gcal").unwrap());
+        assert!(store.path_for("outlook").exists());
+    }
+
+    #[test]
+    fn it_never_writes_to_a_read_only_store() {
+        let dir = tempdir().unwrap();
+        let store = CredentialStore::new(dir.path().join("credentials"));
+        store
+            .save("gcal", &HashMap::from([("token", "abc")]))
+            .unwrap();
+        let read_only = store.clone().read_only();
+
+        read_only
+            .save("gcal", &HashMap::from([("token", "refreshed")]))
+            .unwrap();
+        assert!(!read_only.remove("gcal").unwrap());
+
+        assert_eq!(
+            read_only.load::<HashMap<String, String>>("gcal").unwrap(),
+            Some(HashMap::from([("token".to_string(), "abc".to_string())]))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_only_lets_the_user_read_credentials() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let store = CredentialStore::new(dir.path().join("credentials"));
+        fs::create_dir_all(dir.path().join("credentials")).unwrap();
+        fs::write(store.path_for("gcal"), "{}").unwrap();
+        fs::set_permissions(store.path_for("gcal"), fs::Permissions::from_mode(0o644)).unwrap();
+
+        store
+            .save("gcal", &HashMap::from([("token", "abc")]))
+            .unwrap();
+
+        let mode = |path: PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(store.path_for("gcal")), 0o600);
+        assert_eq!(mode(dir.path().join("credentials")), 0o700);
+    }
+
+    #[test]
+    fn it_migrates_a_legacy_token() {
+        let dir = tempdir().unwrap();
+        let legacy_path = dir.path().join(".autolog.token");
+        fs::write(&legacy_path, "{\"access_token\":\"abc\"}").unwrap();
+
+        let store = CredentialStore::new(dir.path().join("credentials"));
+        store.migrate_legacy_token(&legacy_path, "gcal").unwrap();
+
+        assert!(!legacy_path.exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(
+                fs::metadata(store.path_for("gcal"))
+                    .unwrap()
+                    .permissions()
+                    .mode()
+                    & 0o777,
+                0o600
+            );
+        }
+        assert_eq!(
+            fs::read_to_string(store.path_for("gcal")).unwrap(),
+            "{\"access_token\":\"abc\"}"
+        );
+    }
+}
@@ -0,0 +1,414 @@
+extern crate google_calendar3 as calendar3;
+use crate::data::settings::Settings;
+use crate::utils::calendar::google_calendar::{create_https_client, GoogleCalendar};
+use crate::utils::calendar::{CalendarRule, CalendarService};
+use crate::utils::file::file_reader;
+use crate::utils::time_source::credentials::CredentialStore;
+use crate::utils::time_source::{LinkStatus, TimeSource};
+use async_trait::async_trait;
+use calendar3::hyper;
+use calendar3::oauth2;
+use calendar3::oauth2::storage::{TokenInfo, TokenStorage};
+use serde::Deserialize;
+use std::error::Error;
+use std::path::PathBuf;
+use time::OffsetDateTime;
+
+const SCOPES: [&str; 1] = ["https://www.googleapis.com/auth/calendar"];
+const REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
+
+/// Google Calendar, authenticated with OAuth using the user's client secret.
+/// Tokens are refreshed whenever they're read after expiring, and revoked when unlinked.
+pub struct GoogleCalendarSource {
+    secret_path: PathBuf,
+    revoke_url: String,
+}
+
+/// Lets the OAuth flow write the full token, including the refresh token, to the credential store
+struct CredentialTokenStorage {
+    credentials: CredentialStore,
+    provider: &'static str,
+}
+
+#[derive(Deserialize)]
+struct RefreshResponse {
+    access_token: String,
+    expires_in: Option<i64>,
+}
+
+#[async_trait]
+impl TokenStorage for CredentialTokenStorage {
+    async fn set(&self, _scopes: &[&str], token: TokenInfo) -> anyhow::Result<()> {
+        self.credentials
+            .save(self.provider, &token)
+            .map_err(|err| anyhow::anyhow!(err.to_string()))
+    }
+
+    async fn get(&self, _scopes: &[&str]) -> Option<TokenInfo> {
+        self.credentials.load(self.provider).ok().flatten()
+    }
+}
+
+impl Default for GoogleCalendarSource {
+    fn default() -> Self {
+        GoogleCalendarSource::new(Settings::default().resolve_client_secret_path(None))
+    }
+}
+
+impl GoogleCalendarSource {
+    pub fn new(secret_path: PathBuf) -> Self {
+        GoogleCalendarSource {
+            secret_path,
+            revoke_url: REVOKE_URL.to_string(),
+        }
+    }
+
+    fn load_token(
+        &self,
+        credentials: &CredentialStore,
+    ) -> Result<Option<TokenInfo>, Box<dyn Error>> {
+        credentials.load(self.name())
+    }
+
+    async fn read_secret(&self) -> Result<oauth2::ApplicationSecret, Box<dyn Error>> {
+        if !self.secret_path.exists() {
+            return Err(format!(
+                "OAuth client secret not found at {}. Pass --secret, set AUTOLOG_CLIENT_SECRET \
+                or add client_secret_path to {}",
+                self.secret_path.display(),
+                file_reader::get_settings_path().display()
+            )
+            .into());
+        }
+
+        oauth2::read_application_secret(&self.secret_path)
+            .await
+            .map_err(|err| format!("Error reading {}: {}", self.secret_path.display(), err).into())
+    }
+
+    async fn post_form(
+        &self,
+        url: &str,
+        form: &[(&str, &str)],
+    ) -> Result<(hyper::StatusCode, Vec<u8>), Box<dyn Error>> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(form)
+            .finish();
+        let request = hyper::Request::post(url)
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(hyper::Body::from(body))?;
+
+        let response = create_https_client()?.request(request).await?;
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await?;
+
+        Ok((status, bytes.to_vec()))
+    }
+
+    async fn refresh_token(
+        &self,
+        secret: &oauth2::ApplicationSecret,
+        refresh_token: &str,
+    ) -> Result<TokenInfo, Box<dyn Error>> {
+        let (status, body) = self
+            .post_form(
+                &secret.token_uri,
+                &[
+                    ("client_id", &secret.client_id),
+                    ("client_secret", &secret.client_secret),
+                    ("refresh_token", refresh_token),
+                    ("grant_type", "refresh_token"),
+                ],
+            )
+            .await?;
+
+        if !status.is_success() {
+            return Err(format!(
+                "Refreshing the {} token failed with {}",
+                self.name(),
+                status
+            )
+            .into());
+        }
+
+        let response: RefreshResponse = serde_json::from_slice(&body)?;
+
+        Ok(TokenInfo {
+            access_token: Some(response.access_token),
+            refresh_token: Some(refresh_token.to_string()),
+            expires_at: response
+                .expires_in
+                .map(|seconds| OffsetDateTime::now_utc() + time::Duration::seconds(seconds)),
+            id_token: None,
+        })
+    }
+
+    /// Read the stored token, refreshing and storing it again if it has expired
+    async fn valid_token(
+        &self,
+        credentials: &CredentialStore,
+    ) -> Result<Option<TokenInfo>, Box<dyn Error>> {
+        let token = match self.load_token(credentials)? {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+
+        if !token.is_expired() {
+            return Ok(Some(token));
+        }
+
+        let refresh_token = match token.refresh_token.as_deref() {
+            Some(refresh_token) => refresh_token,
+            None => return Ok(None),
+        };
+
+        let secret = self.read_secret().await?;
+        let token = self.refresh_token(&secret, refresh_token).await?;
+        credentials.save(self.name(), &token)?;
+
+        Ok(Some(token))
+    }
+}
+
+#[async_trait(?Send)]
+impl TimeSource for GoogleCalendarSource {
+    fn name(&self) -> &'static str {
+        "gcal"
+    }
+
+    fn description(&self) -> &'static str {
+        "Google Calendar"
+    }
+
+    fn validate_rule(&self, _rule: &CalendarRule) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    async fn status(&self, credentials: &CredentialStore) -> Result<LinkStatus, Box<dyn Error>> {
+        Ok(match self.load_token(credentials)? {
+            None => LinkStatus::NotLinked,
+            Some(token) if !token.is_expired() || token.refresh_token.is_some() => {
+                LinkStatus::Linked
+            }
+            Some(_) => LinkStatus::Expired,
+        })
+    }
+
+    async fn link(&self, credentials: &CredentialStore) -> Result<(), Box<dyn Error>> {
+        let secret = self.read_secret().await?;
+
+        let auth = oauth2::InstalledFlowAuthenticator::builder(
+            secret,
+            oauth2::InstalledFlowReturnMethod::HTTPRedirect,
+        )
+        .with_storage(Box::new(CredentialTokenStorage {
+            credentials: credentials.clone(),
+            provider: self.name(),
+        }))
+        .build()
+        .await?;
+
+        auth.token(&SCOPES).await?;
+
+        Ok(())
+    }
+
+    async fn unlink(&self, credentials: &CredentialStore) -> Result<bool, Box<dyn Error>> {
+        let token = self.load_token(credentials).ok().flatten();
+        let removed = credentials.remove(self.name())?;
+
+        // revoking the refresh token also revokes every access token issued with it
+        if let Some(value) = token.and_then(|token| token.refresh_token.or(token.access_token)) {
+            let (status, _) = self
+                .post_form(&self.revoke_url, &[("token", &value)])
+                .await
+                .map_err(|err| format!("Credentials removed, but revoking failed: {}", err))?;
+
+            // an already invalid token is reported as a bad request, and needs no revoking
+            if !status.is_success() && status != hyper::StatusCode::BAD_REQUEST {
+                return Err(
+                    format!("Credentials removed, but revoking failed with {}", status).into(),
+                );
+            }
+        }
+
+        Ok(removed)
+    }
+
+    async fn calendar_service(
+        &self,
+        rule: &CalendarRule,
+        credentials: &CredentialStore,
+    ) -> Result<Option<Box<dyn CalendarService>>, Box<dyn Error>> {
+        let token = match self.valid_token(credentials).await? {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Box::new(GoogleCalendar::new(
+            token.access_token.ok_or("Token is missing")?,
+            rule.get_calendar_id().to_string(),
+        )?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::mocks;
+    use tempfile::tempdir;
+
+    fn create_mock_rule() -> CalendarRule {
+        CalendarRule {
+            service: "gcal".to_string(),
+            calendar_id: None,
+            attendee_domain: None,
+            title_regex: None,
+            namespace: None,
+            path: None,
+            action: crate::utils::calendar::CalendarAction::Hours,
+        }
+    }
+
+    fn create_mock_token(expires_at: OffsetDateTime) -> TokenInfo {
+        TokenInfo {
+            access_token: Some("abc".to_string()),
+            refresh_token: Some("refresh".to_string()),
+            expires_at: Some(expires_at),
+            id_token: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn it_is_not_linked_without_a_stored_token() {
+        let dir = tempdir().unwrap();
+        let store = CredentialStore::new(dir.path());
+        let source = GoogleCalendarSource::default();
+
+        assert_eq!(source.status(&store).await.unwrap(), LinkStatus::NotLinked);
+        assert!(source
+            .calendar_service(&create_mock_rule(), &store)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn it_reads_a_token_stored_by_an_older_version() {
+        let dir = tempdir().unwrap();
+        let store = CredentialStore::new(dir.path());
+        std::fs::write(
+            store.path_for("gcal"),
+            "{\"access_token\":\"abc\",\"expires_at\":null}",
+        )
+        .unwrap();
+
+        let source = GoogleCalendarSource::default();
+        assert_eq!(source.status(&store).await.unwrap(), LinkStatus::Linked);
+    }
+
+    #[tokio::test]
+    async fn it_refreshes_an_expired_token() {
+        let dir = tempdir().unwrap();
+        let store = CredentialStore::new(dir.path());
+        store
+            .save(
+                "gcal",
+                &create_mock_token(OffsetDateTime::now_utc() - time::Duration::hours(1)),
+            )
+            .unwrap();
+
+        let (base_url, request) = mocks::serve_mock_http_response(
+            "{\"access_token\":\"new\",\"expires_in\":3599,\"token_type\":\"Bearer\"}".to_string(),
+        );
+
+        let secret_path = dir.path().join("client_secret.json");
+        std::fs::write(
+            &secret_path,
+            format!(
+                "{{\"installed\":{{\"client_id\":\"id\",\"client_secret\":\"secret\",\
+                \"token_uri\":\"{}token\",\"auth_uri\":\"{}auth\",\"redirect_uris\":[]}}}}",
+                base_url, base_url
+            ),
+        )
+        .unwrap();
+
+        let source = GoogleCalendarSource::new(secret_path);
+
+        assert_eq!(source.status(&store).await.unwrap(), LinkStatus::Linked);
+
+        let token = source.valid_token(&store).await.unwrap().unwrap();
+        assert_eq!(token.access_token, Some("new".to_string()));
+        assert_eq!(token.refresh_token, Some("refresh".to_string()));
+        assert!(!token.is_expired());
+
+        let request = request.join().unwrap();
+        assert!(request.starts_with("POST /token"));
+        assert!(request.contains("grant_type=refresh_token"));
+        assert!(request.contains("refresh_token=refresh"));
+
+        let stored: TokenInfo = store.load("gcal").unwrap().unwrap();
+        assert_eq!(stored, token);
+    }
+
+    #[tokio::test]
+    async fn it_throws_an_error_when_the_client_secret_is_missing() {
+        let dir = tempdir().unwrap();
+        let source = GoogleCalendarSource::new(dir.path().join("missing.json"));
+
+        let error = source
+            .link(&CredentialStore::new(dir.path()))
+            .await
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("OAuth client secret not found at"));
+    }
+
+    #[tokio::test]
+    async fn it_is_expired_without_a_refresh_token() {
+        let dir = tempdir().unwrap();
+        let store = CredentialStore::new(dir.path());
+        store
+            .save(
+                "gcal",
+                &TokenInfo {
+                    refresh_token: None,
+                    ..create_mock_token(OffsetDateTime::now_utc() - time::Duration::hours(1))
+                },
+            )
+            .unwrap();
+
+        let source = GoogleCalendarSource::default();
+        assert_eq!(source.status(&store).await.unwrap(), LinkStatus::Expired);
+        assert!(source.valid_token(&store).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn it_revokes_and_removes_the_token_when_unlinked() {
+        let dir = tempdir().unwrap();
+        let store = CredentialStore::new(dir.path());
+        store
+            .save(
+                "gcal",
+                &create_mock_token(OffsetDateTime::now_utc() + time::Duration::hours(1)),
+            )
+            .unwrap();
+
+        let (base_url, request) = mocks::serve_mock_http_response("{}".to_string());
+        let source = GoogleCalendarSource {
+            revoke_url: format!("{}revoke", base_url),
+            ..Default::default()
+        };
+
+        assert!(source.unlink(&store).await.unwrap());
+        assert_eq!(source.status(&store).await.unwrap(), LinkStatus::NotLinked);
+
+        let request = request.join().unwrap();
+        assert!(request.starts_with("POST /revoke"));
+        assert!(request.ends_with("token=refresh"));
+
+        assert!(!source.unlink(&store).await.unwrap());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::utils::calendar::ics_calendar::IcsCalendar;
+use crate::utils::calendar::{CalendarRule, CalendarService};
+use crate::utils::time_source::credentials::CredentialStore;
+use crate::utils::time_source::{LinkStatus, TimeSource};
+use async_trait::async_trait;
+use std::error::Error;
+
+/// Local .ics files. Nothing is authenticated, each rule points at its own file or directory
+pub struct IcsSource {}
+
+#[async_trait(?Send)]
+impl TimeSource for IcsSource {
+    fn name(&self) -> &'static str {
+        "ics"
+    }
+
+    fn description(&self) -> &'static str {
+        "ICS calendar files"
+    }
+
+    fn validate_rule(&self, rule: &CalendarRule) -> Result<(), Box<dyn Error>> {
+        match &rule.path {
+            Some(_) => Ok(()),
+            None => Err("An ics calendar needs a --path to an .ics file or directory".into()),
+        }
+    }
+
+    async fn status(&self, _credentials: &CredentialStore) -> Result<LinkStatus, Box<dyn Error>> {
+        Ok(LinkStatus::NotRequired)
+    }
+
+    async fn link(&self, _credentials: &CredentialStore) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    async fn calendar_service(
+        &self,
+        rule: &CalendarRule,
+        _credentials: &CredentialStore,
+    ) -> Result<Option<Box<dyn CalendarService>>, Box<dyn Error>> {
+        self.validate_rule(rule)?;
+        Ok(Some(Box::new(IcsCalendar::new(
+            rule.path.as_ref().unwrap(),
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::calendar::CalendarAction;
+
+    #[test]
+    fn it_requires_a_path_for_an_ics_rule() {
+        let rule = CalendarRule {
+            service: "ics".to_string(),
+            calendar_id: None,
+            attendee_domain: None,
+            title_regex: None,
+            namespace: None,
+            path: None,
+            action: CalendarAction::Absence,
+        };
+
+        assert!(IcsSource {}.validate_rule(&rule).is_err());
+        assert!(IcsSource {}
+            .validate_rule(&CalendarRule {
+                path: Option::from("holidays.ics".to_string()),
+                ..rule
+            })
+            .is_ok());
+    }
+}
@@ -0,0 +1,179 @@
+pub mod credentials;
+pub mod gcal;
+pub mod ics;
+
+use crate::utils::calendar::{CalendarRule, CalendarService};
+use crate::utils::time_source::credentials::CredentialStore;
+use async_trait::async_trait;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkStatus {
+    Linked,
+    Expired,
+    NotLinked,
+    NotRequired,
+}
+
+impl fmt::Display for LinkStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            LinkStatus::Linked => "linked",
+            LinkStatus::Expired => "expired",
+            LinkStatus::NotLinked => "not linked",
+            LinkStatus::NotRequired => "no link required",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// A provider of time outside of git history, i.e. a calendar. Git history always builds
+/// the timesheet first. Each source then supplies the events for the month being made, which
+/// are matched against the client's rules and added to days by `Repository::add_calendar_event`.
+/// Sources only ever add hours, notes and absences, and never change days the user has edited.
+#[async_trait(?Send)]
+pub trait TimeSource {
+    /// Name used for the --service flag, the calendar rules and the credentials file
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// Check a rule has everything the source needs before it's stored
+    fn validate_rule(&self, rule: &CalendarRule) -> Result<(), Box<dyn Error>>;
+
+    async fn status(&self, credentials: &CredentialStore) -> Result<LinkStatus, Box<dyn Error>>;
+
+    /// Authenticate and store credentials. Sources without authentication do nothing
+    async fn link(&self, credentials: &CredentialStore) -> Result<(), Box<dyn Error>>;
+
+    /// Remove any stored credentials, returning whether there were any
+    async fn unlink(&self, credentials: &CredentialStore) -> Result<bool, Box<dyn Error>> {
+        credentials.remove(self.name())
+    }
+
+    /// Create the service to read a rule's events from, or None if the source isn't linked
+    async fn calendar_service(
+        &self,
+        rule: &CalendarRule,
+        credentials: &CredentialStore,
+    ) -> Result<Option<Box<dyn CalendarService>>, Box<dyn Error>>;
+}
+
+/// Holds every time source that can be linked. New sources are registered in `default`
+pub struct TimeSourceRegistry {
+    sources: Vec<Box<dyn TimeSource>>,
+}
+
+impl Default for TimeSourceRegistry {
+    fn default() -> Self {
+        let mut registry = TimeSourceRegistry::new();
+        registry
+            .register(Box::new(gcal::GoogleCalendarSource::default()))
+            .register(Box::new(ics::IcsSource {}));
+        registry
+    }
+}
+
+impl TimeSourceRegistry {
+    pub fn new() -> Self {
+        TimeSourceRegistry { sources: vec![] }
+    }
+
+    pub fn register(&mut self, source: Box<dyn TimeSource>) -> &mut Self {
+        self.sources
+            .retain(|existing| existing.name() != source.name());
+        self.sources.push(source);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Result<&dyn TimeSource, Box<dyn Error>> {
+        self.sources
+            .iter()
+            .find(|source| source.name().eq_ignore_ascii_case(name))
+            .map(|source| source.as_ref())
+            .ok_or_else(|| {
+                format!(
+                    "Unsupported service: {}. Supported services are {}",
+                    name,
+                    self.names().join(", ")
+                )
+                .into()
+            })
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.sources.iter().map(|source| source.name()).collect()
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = &dyn TimeSource> {
+        self.sources.iter().map(|source| source.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockSource {}
+
+    #[async_trait(?Send)]
+    impl TimeSource for MockSource {
+        fn name(&self) -> &'static str {
+            "gcal"
+        }
+
+        fn description(&self) -> &'static str {
+            "Mock calendar"
+        }
+
+        fn validate_rule(&self, _rule: &CalendarRule) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn status(
+            &self,
+            _credentials: &CredentialStore,
+        ) -> Result<LinkStatus, Box<dyn Error>> {
+            Ok(LinkStatus::NotRequired)
+        }
+
+        async fn link(&self, _credentials: &CredentialStore) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        async fn calendar_service(
+            &self,
+            _rule: &CalendarRule,
+            _credentials: &CredentialStore,
+        ) -> Result<Option<Box<dyn CalendarService>>, Box<dyn Error>> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn it_registers_the_default_sources() {
+        let registry = TimeSourceRegistry::default();
+        assert_eq!(registry.names(), vec!["gcal", "ics"]);
+        assert_eq!(registry.get("ICS").unwrap().name(), "ics");
+    }
+
+    #[test]
+    fn it_replaces_a_source_registered_with_the_same_name() {
+        let mut registry = TimeSourceRegistry::default();
+        registry.register(Box::new(MockSource {}));
+
+        assert_eq!(registry.names(), vec!["ics", "gcal"]);
+        assert_eq!(registry.get("gcal").unwrap().description(), "Mock calendar");
+    }
+
+    #[test]
+    fn it_throws_an_error_for_an_unsupported_source() {
+        let registry = TimeSourceRegistry::default();
+        let error = registry.get("outlook").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Unsupported service: outlook. Supported services are gcal, ics"
+        );
+    }
+}